@@ -1,7 +1,7 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use comx_api::{
     crypto::KeyPair,
-    modules::client::{ModuleClient, ModuleClientConfig},
+    modules::client::{ModuleClient, ModuleClientConfig, CircuitBreakerConfig, ContentType},
     cache::{QueryMapCache, CacheConfig, QueryResult},
 };
 use wiremock::{
@@ -48,6 +48,9 @@ fn bench_module_client(c: &mut Criterion) {
         port: 0,
         timeout: Duration::from_secs(5),
         max_retries: 3,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: None,
+        content_type: ContentType::Json,
     };
     
     let client = ModuleClient::with_config(config, keypair.clone());
@@ -61,7 +64,7 @@ fn bench_module_client(c: &mut Criterion) {
             rt.block_on(async {
                 black_box(
                     client
-                        .call::<_, BenchResponse>("bench_method", &keypair.address(), params.clone())
+                        .call::<_, BenchResponse>("bench_method", keypair.address(), params.clone())
                         .await
                         .unwrap()
                 )
@@ -92,11 +95,7 @@ fn bench_cache(c: &mut Criterion) {
     let mut group = c.benchmark_group("cache");
     let rt = tokio::runtime::Runtime::new().unwrap();
     
-    let cache = QueryMapCache::new(CacheConfig {
-        ttl: Duration::from_secs(60),
-        refresh_interval: Duration::from_secs(300),
-        max_entries: 1000,
-    });
+    let cache = QueryMapCache::new(CacheConfig::default());
 
     // Benchmark cache set operation
     group.bench_function("cache_set", |b| {
@@ -105,14 +104,10 @@ fn bench_cache(c: &mut Criterion) {
             let key = format!("key_{}", i);
             i += 1;
             rt.block_on(async {
-                black_box(
-                    cache.set(
-                        &key,
-                        QueryResult {
-                            data: "test_value".to_string(),
-                        }
-                    ).await
-                )
+                cache.set(
+                    black_box(&key),
+                    QueryResult::new("test_value")
+                ).await
             })
         })
     });
@@ -123,9 +118,7 @@ fn bench_cache(c: &mut Criterion) {
         rt.block_on(async {
             cache.set(
                 key,
-                QueryResult {
-                    data: "test_value".to_string(),
-                }
+                QueryResult::new("test_value")
             ).await;
         });
         
@@ -149,9 +142,7 @@ fn bench_cache(c: &mut Criterion) {
                     if i % 3 == 0 { // Every third operation is a set
                         cache.set(
                             &key,
-                            QueryResult {
-                                data: "test_value".to_string(),
-                            }
+                            QueryResult::new("test_value")
                         ).await;
                         true
                     } else {
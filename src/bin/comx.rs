@@ -0,0 +1,104 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use comx_api::env::COMX_NODE_URL;
+use comx_api::error::CommunexError;
+use comx_api::rpc::DEFAULT_RPC_URL;
+use comx_api::wallet::{staking::StakeRequest, FreeBalance, TransferRequest, WalletClient};
+use serde::Serialize;
+use serde_json::json;
+use std::process::ExitCode;
+
+/// Command-line wallet for a Communex node
+#[derive(Parser)]
+#[command(name = "comx", version)]
+struct Cli {
+    /// URL of the node's RPC endpoint
+    #[arg(long, env = COMX_NODE_URL, global = true, default_value = DEFAULT_RPC_URL)]
+    node: String,
+
+    /// How results and errors are printed
+    #[arg(long, value_enum, global = true, default_value_t = Output::Text)]
+    output: Output,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Output {
+    Text,
+    Json,
+}
+
+impl Output {
+    /// Print `value` as JSON, or `text` in text mode
+    fn print<T: Serialize>(self, value: &T, text: impl FnOnce() -> String) {
+        match self {
+            Output::Text => println!("{}", text()),
+            Output::Json => println!("{}", json!(value)),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show the free balance of an address
+    Balance {
+        address: String,
+    },
+    /// Transfer tokens between addresses
+    Transfer {
+        #[arg(long)]
+        from: String,
+        #[arg(long)]
+        to: String,
+        #[arg(long)]
+        amount: u64,
+        #[arg(long, default_value = "COMAI")]
+        denom: String,
+    },
+    /// Stake tokens from an address
+    Stake {
+        #[arg(long)]
+        from: String,
+        #[arg(long)]
+        amount: u64,
+        #[arg(long, default_value = "COMAI")]
+        denom: String,
+    },
+}
+
+async fn run(client: WalletClient, command: Command, output: Output) -> Result<(), CommunexError> {
+    match command {
+        Command::Balance { address } => {
+            let free = client.get_free_balance(&address).await?;
+            let balance = FreeBalance { address, free };
+            output.print(&balance, || format!("Balance: {}", balance.free));
+        }
+        Command::Transfer { from, to, amount, denom } => {
+            let response = client.transfer(TransferRequest { from, to, amount, denom }).await?;
+            output.print(&response, || format!("Transfer {}", response.state));
+        }
+        Command::Stake { from, amount, denom } => {
+            let state = client.stake(StakeRequest { from, amount, denom }).await?;
+            output.print(&state, || format!("Stake {} ({})", state.state, state.hash));
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let client = WalletClient::new(&cli.node);
+
+    match run(client, cli.command, cli.output).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            match cli.output {
+                Output::Text => eprintln!("Error: {}", e),
+                Output::Json => eprintln!("{}", json!({ "error": e.to_string() })),
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
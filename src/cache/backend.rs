@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use async_trait::async_trait;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::error::CommunexError;
+use super::QueryResult;
+
+/// A cache entry in its storable form, with a wall-clock expiry so it
+/// survives process restarts.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PersistedEntry {
+    pub value: QueryResult,
+    /// Expiry as milliseconds since the Unix epoch
+    pub expires_at_ms: u64,
+    /// Set for entries that never expire, whose `expires_at_ms` is unused
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub immutable: bool,
+}
+
+impl PersistedEntry {
+    pub fn new(value: QueryResult, expires_at: SystemTime) -> Self {
+        let expires_at_ms = expires_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        Self { value, expires_at_ms, immutable: false }
+    }
+
+    /// An entry that never expires
+    pub fn immutable(value: QueryResult) -> Self {
+        Self { value, expires_at_ms: 0, immutable: true }
+    }
+
+    pub fn expires_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.expires_at_ms)
+    }
+}
+
+/// Storage used by `QueryMapCache` to persist entries
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    /// Load all stored entries, including expired ones
+    fn load(&self) -> Result<HashMap<String, PersistedEntry>, CommunexError>;
+
+    /// Replace the stored entries with the given snapshot
+    async fn save(&self, entries: &HashMap<String, PersistedEntry>) -> Result<(), CommunexError>;
+
+    /// Whether saved entries outlive the process. The cache skips its
+    /// periodic writes to backends that return false.
+    fn is_persistent(&self) -> bool {
+        true
+    }
+}
+
+/// Default backend that keeps nothing beyond the in-memory cache
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBackend;
+
+#[async_trait]
+impl CacheBackend for MemoryBackend {
+    fn load(&self) -> Result<HashMap<String, PersistedEntry>, CommunexError> {
+        Ok(HashMap::new())
+    }
+
+    async fn save(&self, _entries: &HashMap<String, PersistedEntry>) -> Result<(), CommunexError> {
+        Ok(())
+    }
+
+    fn is_persistent(&self) -> bool {
+        false
+    }
+}
+
+/// Backend storing entries as JSON in a single file
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct FileBackend {
+    path: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileBackend {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+impl CacheBackend for FileBackend {
+    fn load(&self) -> Result<HashMap<String, PersistedEntry>, CommunexError> {
+        if !self.path.exists() {
+            return Ok(HashMap::new());
+        }
+
+        let contents = fs::read(&self.path)
+            .map_err(|e| CommunexError::CacheError(format!("Failed to read cache file: {}", e)))?;
+        serde_json::from_slice(&contents)
+            .map_err(|e| CommunexError::CacheError(format!("Failed to parse cache file: {}", e)))
+    }
+
+    async fn save(&self, entries: &HashMap<String, PersistedEntry>) -> Result<(), CommunexError> {
+        let contents = serde_json::to_vec(entries)
+            .map_err(|e| CommunexError::CacheError(format!("Failed to serialize cache: {}", e)))?;
+
+        // Write to a sibling file first so a crash never leaves a truncated cache
+        let tmp_path = self.path.with_extension("tmp");
+        let written = match tokio::fs::write(&tmp_path, contents).await {
+            Ok(()) => tokio::fs::rename(&tmp_path, &self.path).await,
+            Err(e) => Err(e),
+        };
+        written.map_err(|e| CommunexError::CacheError(format!("Failed to write cache file: {}", e)))
+    }
+}
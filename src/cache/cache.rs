@@ -1,37 +1,101 @@
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::{Mutex, RwLock};
+use futures::channel::oneshot;
+use futures::future::{self, BoxFuture, Either, FutureExt, Shared};
+use crate::runtime::{self, Runtime};
+use tokio_util::sync::CancellationToken;
+use std::time::{Duration, SystemTime};
+use crate::runtime::Instant;
 use crate::error::CommunexError;
 use std::fmt::{self, Debug};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use super::{CacheBackend, MemoryBackend, PersistedEntry};
 
 type RefreshHandler = Box<dyn Fn(&str) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<QueryResult, CommunexError>> + Send>> + Send + Sync>;
+type InFlightRefresh = Shared<BoxFuture<'static, Option<QueryResult>>>;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct QueryResult {
-    pub data: String,
+    pub data: Value,
 }
 
 impl QueryResult {
+    /// Create a result holding `data` as a JSON string
     pub fn new(data: &str) -> Self {
         Self {
-            data: data.to_string(),
+            data: Value::String(data.to_string()),
         }
     }
+
+    /// Create a result holding an arbitrary JSON value
+    pub fn from_value(data: Value) -> Self {
+        Self { data }
+    }
+
+    /// The data as a string slice, if it holds a JSON string
+    pub fn as_str(&self) -> Option<&str> {
+        self.data.as_str()
+    }
+
+    /// A result recording that the queried item does not exist. It is
+    /// cached for `CacheConfig::negative_ttl` instead of `ttl`.
+    pub fn not_found() -> Self {
+        Self { data: Value::Null }
+    }
+
+    /// Whether this is a not-found result
+    pub fn is_not_found(&self) -> bool {
+        self.data.is_null()
+    }
+
+    /// Deserialize the data into a typed value
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, CommunexError> {
+        T::deserialize(&self.data)
+            .map_err(|e| CommunexError::ParseError(format!("Failed to deserialize cached value: {}", e)))
+    }
 }
 
-impl Default for QueryResult {
-    fn default() -> Self {
-        Self {
-            data: String::new(),
-        }
+impl From<Value> for QueryResult {
+    fn from(data: Value) -> Self {
+        Self::from_value(data)
     }
 }
 
 #[derive(Debug, Clone)]
 struct CacheEntry {
     value: QueryResult,
-    expires_at: Instant,
+    /// `None` for immutable entries, which never expire
+    expires_at: Option<Instant>,
+    inserted_at: Instant,
+    size: usize,
+}
+
+impl CacheEntry {
+    fn new(value: QueryResult, expires_at: Option<Instant>) -> Self {
+        let size = serialized_size(&value);
+        Self { value, expires_at, inserted_at: Instant::now(), size }
+    }
+
+    fn is_fresh(&self) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > Instant::now())
+    }
+
+    /// Entries are evicted soonest-expiring first, then immutable entries
+    /// oldest first
+    fn eviction_order(&self) -> (bool, Option<Instant>, Instant) {
+        (self.expires_at.is_none(), self.expires_at, self.inserted_at)
+    }
+}
+
+fn serialized_size(value: &QueryResult) -> usize {
+    serde_json::to_vec(value).map(|bytes| bytes.len()).unwrap_or(0)
+}
+
+fn total_size(entries: &HashMap<String, CacheEntry>) -> usize {
+    entries.values().map(|entry| entry.size).sum()
 }
 
 #[derive(Debug, Clone, Default)]
@@ -42,6 +106,23 @@ pub struct CacheMetrics {
     pub refresh_success_count: u64,
     pub refresh_error_count: u64,
     pub current_entries: usize,
+    /// Total serialized size of the cached values
+    pub current_bytes: usize,
+    /// How long the most recent refresh handler call took
+    pub last_refresh_duration: Duration,
+    /// Mean duration of all refresh handler calls
+    pub avg_refresh_duration: Duration,
+}
+
+impl CacheMetrics {
+    fn record_refresh_duration(&mut self, duration: Duration) {
+        let count = (self.refresh_success_count + self.refresh_error_count) as u128;
+        self.last_refresh_duration = duration;
+        if count > 0 {
+            let total = self.avg_refresh_duration.as_nanos() * (count - 1) + duration.as_nanos();
+            self.avg_refresh_duration = Duration::from_nanos((total / count) as u64);
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -50,6 +131,18 @@ pub struct QueryMapCache {
     config: super::CacheConfig,
     metrics: Arc<RwLock<CacheMetrics>>,
     refresh_handler: Arc<RwLock<Option<RefreshHandler>>>,
+    backend: Arc<dyn CacheBackend>,
+    in_flight: Arc<Mutex<HashMap<String, InFlightRefresh>>>,
+    refresh_task: Arc<Mutex<Option<RefreshTask>>>,
+    runtime: Arc<dyn Runtime>,
+    /// Set while a batched write to the backend is scheduled
+    persist_pending: Arc<AtomicBool>,
+}
+
+struct RefreshTask {
+    shutdown: CancellationToken,
+    /// Resolves when the task exits; an error means it panicked
+    finished: oneshot::Receiver<()>,
 }
 
 // Manual Debug implementation that skips the refresh_handler
@@ -70,44 +163,210 @@ impl QueryMapCache {
             config,
             metrics: Arc::new(RwLock::new(CacheMetrics::default())),
             refresh_handler: Arc::new(RwLock::new(None)),
+            backend: Arc::new(MemoryBackend),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            refresh_task: Arc::new(Mutex::new(None)),
+            runtime: runtime::default_runtime(),
+            persist_pending: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Create a cache persisted through `backend`, restoring any stored
+    /// entries that have not yet expired.
+    pub fn with_backend(config: super::CacheConfig, backend: Arc<dyn CacheBackend>) -> Result<Self, CommunexError> {
+        let now = SystemTime::now();
+        let restored: HashMap<String, CacheEntry> = backend.load()?
+            .into_iter()
+            .filter_map(|(key, entry)| {
+                if entry.immutable {
+                    return Some((key, CacheEntry::new(entry.value, None)));
+                }
+                let remaining = entry.expires_at().duration_since(now).ok()?;
+                Some((key, CacheEntry::new(entry.value, Some(Instant::now() + remaining))))
+            })
+            .collect();
+
+        let metrics = CacheMetrics {
+            current_entries: restored.len(),
+            current_bytes: total_size(&restored),
+            ..CacheMetrics::default()
+        };
+
+        Ok(Self {
+            entries: Arc::new(RwLock::new(restored)),
+            config,
+            metrics: Arc::new(RwLock::new(metrics)),
+            refresh_handler: Arc::new(RwLock::new(None)),
+            backend,
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            refresh_task: Arc::new(Mutex::new(None)),
+            runtime: runtime::default_runtime(),
+            persist_pending: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Run the background refresh task on `runtime` instead of tokio
+    pub fn with_runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+        self.runtime = runtime;
+        self
+    }
+
+    /// Write the current entries to the backend
+    pub async fn persist(&self) -> Result<(), CommunexError> {
+        let snapshot = self.snapshot().await;
+        self.backend.save(&snapshot).await
+    }
+
+    /// Persist the entries once `persist_delay` has passed, so a burst of
+    /// inserts costs a single write. Does nothing for non-persistent backends.
+    fn schedule_persist(&self) {
+        if !self.backend.is_persistent() || self.persist_pending.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let cache = self.clone();
+        self.runtime.spawn(Box::pin(async move {
+            cache.runtime.sleep(cache.config.persist_delay).await;
+            cache.persist_pending.store(false, Ordering::Release);
+            if let Err(e) = cache.persist().await {
+                warn!("Failed to persist cache: {}", e);
+            }
+        }));
+    }
+
+    async fn snapshot(&self) -> HashMap<String, PersistedEntry> {
+        let entries = self.entries.read().await;
+        let now = Instant::now();
+        let wall_now = SystemTime::now();
+
+        entries.iter()
+            .map(|(key, entry)| {
+                let persisted = match entry.expires_at {
+                    Some(expires_at) => PersistedEntry::new(
+                        entry.value.clone(),
+                        wall_now + expires_at.saturating_duration_since(now),
+                    ),
+                    None => PersistedEntry::immutable(entry.value.clone()),
+                };
+                (key.clone(), persisted)
+            })
+            .collect()
+    }
+
+    fn ttl_for(&self, value: &QueryResult) -> Duration {
+        if value.is_not_found() {
+            self.config.negative_ttl
+        } else {
+            self.config.ttl
         }
     }
 
     pub async fn set(&self, key: &str, value: QueryResult) {
+        let ttl = self.ttl_for(&value);
+        self.set_with_ttl(key, value, ttl).await;
+    }
+
+    /// Store `value` for `ttl` instead of the configured ttl
+    pub async fn set_with_ttl(&self, key: &str, value: QueryResult, ttl: Duration) {
+        self.insert(key, CacheEntry::new(value, Some(Instant::now() + ttl))).await;
+    }
+
+    /// Store `value` without an expiry, for results that can never change,
+    /// such as state at a finalized block. Immutable entries still count
+    /// towards `max_entries` and `max_bytes`, and are evicted only once no
+    /// expiring entries are left.
+    pub async fn set_immutable(&self, key: &str, value: QueryResult) {
+        self.insert(key, CacheEntry::new(value, None)).await;
+    }
+
+    async fn insert(&self, key: &str, entry: CacheEntry) {
         let mut entries = self.entries.write().await;
-        let expires_at = Instant::now() + self.config.ttl;
-        
-        entries.insert(key.to_string(), CacheEntry { value, expires_at });
-        
-        if entries.len() > self.config.max_entries {
+
+        if self.config.max_bytes.is_some_and(|max| entry.size > max) {
+            // A value that can never fit would otherwise flush the whole cache
+            debug!("Not caching '{}': {} bytes exceeds the cache byte limit", key, entry.size);
+            entries.remove(key);
+        } else {
+            entries.insert(key.to_string(), entry);
+        }
+
+        let mut current_bytes = total_size(&entries);
+        while entries.len() > self.config.max_entries
+            || self.config.max_bytes.is_some_and(|max| current_bytes > max)
+        {
             let oldest_key = entries.iter()
-                .min_by_key(|(_, entry)| entry.expires_at)
+                .min_by_key(|(_, entry)| entry.eviction_order())
                 .map(|(k, _)| k.clone());
             
-            if let Some(key) = oldest_key {
-                entries.remove(&key);
+            match oldest_key.and_then(|key| entries.remove(&key)) {
+                Some(evicted) => current_bytes -= evicted.size,
+                None => break,
             }
         }
         
         let mut metrics = self.metrics.write().await;
         metrics.current_entries = entries.len();
+        metrics.current_bytes = current_bytes;
+        drop(metrics);
+        drop(entries);
+
+        self.schedule_persist();
     }
 
+    /// Get a cached value. An expired entry is reloaded through the refresh
+    /// handler, if one is set; concurrent callers share a single reload.
     pub async fn get(&self, key: &str) -> Option<QueryResult> {
-        let entries = self.entries.read().await;
-        let mut metrics = self.metrics.write().await;
-        
-        if let Some(entry) = entries.get(key) {
-            if entry.expires_at > Instant::now() {
-                metrics.hits += 1;
-                return Some(entry.value.clone());
+        let expired = {
+            let entries = self.entries.read().await;
+            let mut metrics = self.metrics.write().await;
+
+            match entries.get(key) {
+                Some(entry) if entry.is_fresh() => {
+                    metrics.hits += 1;
+                    return Some(entry.value.clone());
+                }
+                Some(_) => {
+                    metrics.misses += 1;
+                    true
+                }
+                None => {
+                    metrics.misses += 1;
+                    false
+                }
             }
+        };
+
+        if expired && self.refresh_handler.read().await.is_some() {
+            return self.refresh_key(key).await;
         }
-        
-        metrics.misses += 1;
         None
     }
 
+    /// Return the cached value for `key`, or compute it with `f`, store it and
+    /// return it. `f` is only called on a miss; its errors are not cached.
+    pub async fn get_or_insert_with<F, Fut>(&self, key: &str, f: F) -> Result<QueryResult, CommunexError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<QueryResult, CommunexError>>,
+    {
+        {
+            let entries = self.entries.read().await;
+            let mut metrics = self.metrics.write().await;
+
+            match entries.get(key) {
+                Some(entry) if entry.is_fresh() => {
+                    metrics.hits += 1;
+                    return Ok(entry.value.clone());
+                }
+                _ => metrics.misses += 1,
+            }
+        }
+
+        let value = f().await?;
+        self.set(key, value.clone()).await;
+        Ok(value)
+    }
+
     pub async fn get_metrics(&self) -> CacheMetrics {
         let metrics = self.metrics.read().await;
         (*metrics).clone()
@@ -118,43 +377,115 @@ impl QueryMapCache {
         *refresh_handler = Some(handler);
     }
 
+    /// Spawn the task that periodically reloads expired entries. Any task
+    /// already running for this cache is stopped first.
     pub async fn start_background_refresh(&self) {
+        self.stop_background_refresh().await;
+
         let cache = Arc::new(self.clone());
+        let shutdown = CancellationToken::new();
+        let token = shutdown.clone();
+        let (done, finished) = oneshot::channel();
         
-        tokio::spawn(async move {
+        self.runtime.spawn(Box::pin(async move {
             loop {
-                tokio::time::sleep(cache.config.refresh_interval).await;
+                let tick = cache.runtime.sleep(cache.config.refresh_interval);
+                if let Either::Left(_) = future::select(Box::pin(token.cancelled()), tick).await {
+                    break;
+                }
                 
                 // Get all keys that need refresh
                 let mut keys_to_refresh = Vec::new();
                 for (key, entry) in cache.entries.read().await.iter() {
-                    if entry.expires_at <= Instant::now() {
+                    if !entry.is_fresh() {
                         keys_to_refresh.push(key.clone());
                     }
                 }
                 drop(cache.entries.read().await);
+                let refreshed_any = !keys_to_refresh.is_empty();
 
                 for key in keys_to_refresh {
-                    if let Some(handler) = cache.refresh_handler.read().await.as_ref() {
-                        match handler(&key).await {
-                            Ok(new_value) => {
-                                let mut entries = cache.entries.write().await;
-                                if let Some(entry) = entries.get_mut(&key) {
-                                    entry.value = new_value;
-                                    entry.expires_at = Instant::now() + cache.config.ttl;
-                                }
-                                let mut metrics = cache.metrics.write().await;
-                                metrics.refresh_success_count += 1;
-                            }
-                            Err(_) => {
-                                let mut metrics = cache.metrics.write().await;
-                                metrics.refresh_error_count += 1;
-                            }
-                        }
+                    cache.refresh_key(&key).await;
+                }
+
+                if refreshed_any {
+                    cache.schedule_persist();
+                }
+            }
+            debug!("Background cache refresh stopped");
+            let _ = done.send(());
+        }));
+
+        *self.refresh_task.lock().await = Some(RefreshTask { shutdown, finished });
+    }
+
+    /// Signal the background refresh task to exit and wait for it to finish.
+    /// Does nothing if no task is running.
+    pub async fn stop_background_refresh(&self) {
+        let task = self.refresh_task.lock().await.take();
+        if let Some(task) = task {
+            task.shutdown.cancel();
+            if task.finished.await.is_err() {
+                warn!("Background cache refresh task exited abnormally");
+            }
+        }
+    }
+
+    /// Reload `key` through the refresh handler, joining any reload of the
+    /// same key that is already in flight.
+    async fn refresh_key(&self, key: &str) -> Option<QueryResult> {
+        let flight = {
+            let mut in_flight = self.in_flight.lock().await;
+            match in_flight.get(key) {
+                Some(flight) => flight.clone(),
+                None => {
+                    let cache = self.clone();
+                    let owned_key = key.to_string();
+                    let flight = async move {
+                        let result = cache.run_refresh_handler(&owned_key).await;
+                        cache.in_flight.lock().await.remove(&owned_key);
+                        result
                     }
+                    .boxed()
+                    .shared();
+                    in_flight.insert(key.to_string(), flight.clone());
+                    flight
+                }
+            }
+        };
+
+        flight.await
+    }
+
+    async fn run_refresh_handler(&self, key: &str) -> Option<QueryResult> {
+        let started = Instant::now();
+        let result = {
+            let guard = self.refresh_handler.read().await;
+            let handler = guard.as_ref()?;
+            handler(key).await
+        };
+        let elapsed = started.elapsed();
+
+        match result {
+            Ok(new_value) => {
+                let mut entries = self.entries.write().await;
+                if let Some(entry) = entries.get_mut(key) {
+                    *entry = CacheEntry::new(new_value.clone(), Some(Instant::now() + self.ttl_for(&new_value)));
                 }
+                let mut metrics = self.metrics.write().await;
+                metrics.refresh_success_count += 1;
+                metrics.record_refresh_duration(elapsed);
+                metrics.current_bytes = total_size(&entries);
+                Some(new_value)
+            }
+            Err(e) => {
+                debug!("Failed to refresh cache entry '{}': {}", key, e);
+                let mut metrics = self.metrics.write().await;
+                metrics.refresh_error_count += 1;
+                metrics.record_refresh_duration(elapsed);
+                None
             }
-        });
+        }
     }
 
     // Add a method to force expire an entry (useful for testing)
@@ -162,7 +493,7 @@ impl QueryMapCache {
     pub(crate) async fn force_expire(&self, key: &str) {
         let mut entries = self.entries.write().await;
         if let Some(entry) = entries.get_mut(key) {
-            entry.expires_at = Instant::now() - std::time::Duration::from_secs(1);
+            entry.expires_at = Some(Instant::now() - std::time::Duration::from_secs(1));
         }
     }
 } 
\ No newline at end of file
@@ -3,16 +3,59 @@ use std::time::Duration;
 #[derive(Debug, Clone)]
 pub struct CacheConfig {
     pub ttl: Duration,
+    /// Time to live for not-found results, usually shorter than `ttl` so
+    /// that absences are re-checked sooner
+    pub negative_ttl: Duration,
     pub refresh_interval: Duration,
     pub max_entries: usize,
+    /// Upper bound on the total serialized size of cached values, if any
+    pub max_bytes: Option<usize>,
+    /// How long inserts are batched before the entries are written to a
+    /// persistent backend
+    pub persist_delay: Duration,
+}
+
+impl CacheConfig {
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn with_negative_ttl(mut self, negative_ttl: Duration) -> Self {
+        self.negative_ttl = negative_ttl;
+        self
+    }
+
+    pub fn with_refresh_interval(mut self, refresh_interval: Duration) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    pub fn with_persist_delay(mut self, persist_delay: Duration) -> Self {
+        self.persist_delay = persist_delay;
+        self
+    }
 }
 
 impl Default for CacheConfig {
     fn default() -> Self {
         Self {
             ttl: Duration::from_secs(60),
+            negative_ttl: Duration::from_secs(10),
             refresh_interval: Duration::from_secs(300),
             max_entries: 1000,
+            max_bytes: None,
+            persist_delay: Duration::from_secs(1),
         }
     }
 } 
\ No newline at end of file
@@ -1,5 +1,10 @@
 mod config;
+#[allow(clippy::module_inception)]
 mod cache;
+mod backend;
 
 pub use config::CacheConfig;
-pub use cache::{QueryMapCache, QueryResult}; 
\ No newline at end of file
+pub use cache::{QueryMapCache, QueryResult};
+pub use backend::{CacheBackend, MemoryBackend, PersistedEntry};
+#[cfg(not(target_arch = "wasm32"))]
+pub use backend::FileBackend; 
\ No newline at end of file
@@ -0,0 +1,129 @@
+//! Key files in the format the Python communex tooling keeps under
+//! `~/.commune/key`: a JSON object whose `data` field holds the key itself as
+//! a JSON-encoded string.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sp_core::{sr25519::Pair, Pair as PairT};
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::error::CommunexError;
+use super::KeyPair;
+
+/// `crypto_type` of sr25519 keys
+const SR25519: u8 = 1;
+/// ss58 address format of Communex keys
+const SS58_FORMAT: u16 = 42;
+
+/// The key stored in a key file's `data` field
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct KeyData {
+    crypto_type: u8,
+    seed_hex: Option<String>,
+    derive_path: Option<String>,
+    path: Option<String>,
+    ss58_format: u16,
+    public_key: Option<String>,
+    private_key: Option<String>,
+    mnemonic: Option<String>,
+    ss58_address: String,
+}
+
+fn key_file_error(path: &Path, message: impl std::fmt::Display) -> CommunexError {
+    CommunexError::KeyFileError(format!("{}: {}", path.display(), message))
+}
+
+fn decode_secret(hex_value: &str, path: &Path) -> Result<Pair, CommunexError> {
+    let bytes = hex::decode(hex_value.trim_start_matches("0x"))
+        .map_err(|e| key_file_error(path, format!("invalid secret hex: {}", e)))?;
+    Pair::from_seed_slice(&bytes)
+        .map_err(|e| key_file_error(path, format!("invalid secret: {:?}", e)))
+}
+
+impl KeyPair {
+    /// Load a key file written by the communex tooling. The key is rebuilt
+    /// from its mnemonic, or failing that its seed or private key, and must
+    /// derive the ss58 address stored alongside it.
+    pub fn from_key_file(path: &Path) -> Result<KeyPair, CommunexError> {
+        let contents = std::fs::read_to_string(path).map_err(|e| key_file_error(path, e))?;
+        let file: Value = serde_json::from_str(&contents).map_err(|e| key_file_error(path, e))?;
+
+        // The key is JSON-encoded inside `data`; accept a bare key object too
+        let key: KeyData = match file.get("data").and_then(Value::as_str) {
+            Some(data) => serde_json::from_str(data),
+            None => serde_json::from_value(file),
+        }
+        .map_err(|e| key_file_error(path, e))?;
+
+        let keypair = if let Some(mnemonic) = &key.mnemonic {
+            KeyPair::from_seed_phrase(mnemonic)?
+        } else if let Some(seed) = key.seed_hex.as_ref().or(key.private_key.as_ref()) {
+            KeyPair::from_pair(decode_secret(seed, path)?)
+        } else {
+            return Err(key_file_error(path, "no mnemonic, seed or private key"));
+        };
+
+        if keypair.ss58_address() != key.ss58_address {
+            return Err(key_file_error(path, format!(
+                "stored address {} does not match derived address {}",
+                key.ss58_address,
+                keypair.ss58_address()
+            )));
+        }
+        Ok(keypair)
+    }
+
+    /// Write this key to `path` as a communex key file named `name`. The
+    /// mnemonic is included when the key was restored from one. On unix the
+    /// file is readable by its owner only, even when it replaces an existing
+    /// file with wider permissions.
+    pub fn save_key_file(&self, path: &Path, name: &str) -> Result<(), CommunexError> {
+        let key = KeyData {
+            crypto_type: SR25519,
+            seed_hex: None,
+            derive_path: None,
+            path: Some(name.to_string()),
+            ss58_format: SS58_FORMAT,
+            public_key: Some(self.public_key_hex()),
+            private_key: Some(hex::encode(self.secret_bytes())),
+            mnemonic: self.mnemonic().map(str::to_string),
+            ss58_address: self.ss58_address().to_string(),
+        };
+        let data = serde_json::to_string(&key).map_err(|e| key_file_error(path, e))?;
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let file = serde_json::json!({
+            "path": name,
+            "data": data,
+            "encrypted": false,
+            "timestamp": timestamp,
+        });
+
+        let contents = serde_json::to_string_pretty(&file).map_err(|e| key_file_error(path, e))?;
+
+        // Write a new file and rename it over `path`, so the key never takes
+        // on the permissions of a file it replaces. The random suffix keeps
+        // the temporary file from clashing with, or removing, any other file.
+        let mut tmp_name = path.file_name().map(OsString::from).unwrap_or_else(|| "key".into());
+        tmp_name.push(format!(".{}.tmp", hex::encode(rand::random::<[u8; 8]>())));
+        let tmp_path = path.with_file_name(tmp_name);
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut out = options.open(&tmp_path).map_err(|e| key_file_error(path, e))?;
+        let written = out.write_all(contents.as_bytes())
+            .and_then(|_| {
+                drop(out);
+                std::fs::rename(&tmp_path, path)
+            });
+        if written.is_err() {
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+        written.map_err(|e| key_file_error(path, e))
+    }
+}
@@ -1,13 +1,18 @@
 use sp_core::{
-    sr25519::{Pair, Signature},
+    sr25519::{Pair, Signature, PUBLIC_KEY_SERIALIZED_SIZE, SIGNATURE_SERIALIZED_SIZE},
     Pair as PairT,
     crypto::{Ss58Codec, Ss58AddressFormat, DeriveJunction},
     
 };
 use crate::error::CommunexError;
+use crate::types::{SignedTransaction, Transaction};
 use std::fmt::Debug;
 use hex;
 
+// Signatures and public keys cross the API as raw arrays of these sizes
+const _: () = assert!(std::mem::size_of::<Signature>() == SIGNATURE_SERIALIZED_SIZE);
+const _: () = assert!(std::mem::size_of::<sp_core::sr25519::Public>() == PUBLIC_KEY_SERIALIZED_SIZE);
+
 impl Debug for KeyPair {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("KeyPair")
@@ -21,31 +26,41 @@ impl Debug for KeyPair {
 pub struct KeyPair {
     pair: Pair,
     ss58_address: String,
+    /// The phrase the key was restored from, if any
+    mnemonic: Option<String>,
 }
 
 impl KeyPair {
     /// Generate a new random keypair
     pub fn generate() -> Self {
-        let pair = Pair::generate().0;
-        let public = pair.public();
-        let ss58_address = public.to_ss58check_with_version(Ss58AddressFormat::custom(42));
-        
-        Self {
-            pair,
-            ss58_address,
-        }
+        Self::from_pair(Pair::generate().0)
     }
 
     pub fn from_seed_phrase(phrase: &str) -> Result<Self, CommunexError> {
         let (pair, _) = Pair::from_phrase(phrase, None)
             .map_err(|e| CommunexError::InvalidSeedPhrase(e.to_string()))?;
-        let public = pair.public();
-        let ss58_address = public.to_ss58check_with_version(Ss58AddressFormat::custom(42));
-    
         Ok(Self {
+            mnemonic: Some(phrase.to_string()),
+            ..Self::from_pair(pair)
+        })
+    }
+
+    pub(crate) fn from_pair(pair: Pair) -> Self {
+        let ss58_address = pair.public().to_ss58check_with_version(Ss58AddressFormat::custom(42));
+        Self {
             pair,
             ss58_address,
-        })
+            mnemonic: None,
+        }
+    }
+
+    pub(crate) fn mnemonic(&self) -> Option<&str> {
+        self.mnemonic.as_deref()
+    }
+
+    /// The 64-byte secret key, as accepted by `Pair::from_seed_slice`
+    pub(crate) fn secret_bytes(&self) -> Vec<u8> {
+        self.pair.to_raw_vec()
     }
     
 
@@ -58,7 +73,12 @@ impl KeyPair {
         self.ss58_address()
     }
 
-    pub fn public_key(&self) -> [u8; 32] {
+    /// The `cmx1`-prefixed address of this key, as validated by `Address::new`
+    pub fn cmx_address(&self) -> String {
+        format!("cmx1{}", bs58::encode(self.public_key()).into_string())
+    }
+
+    pub fn public_key(&self) -> [u8; PUBLIC_KEY_SERIALIZED_SIZE] {
         self.pair.public().0
     }
 
@@ -67,13 +87,23 @@ impl KeyPair {
         hex::encode(self.public_key())
     }
 
-    pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+    pub fn sign(&self, message: &[u8]) -> [u8; SIGNATURE_SERIALIZED_SIZE] {
         self.pair.sign(message).0
     }
+
+    /// Hex-encoded signature of `message`, as sent in the `X-Signature` header
+    pub fn sign_hex(&self, message: &[u8]) -> String {
+        hex::encode(self.sign(message))
+    }
+
+    /// Sign `tx` with this key; same as `tx.sign(self)`
+    pub fn sign_transaction(&self, tx: &Transaction) -> Result<SignedTransaction, CommunexError> {
+        tx.sign(self)
+    }
     
     pub fn derive_address(&self, index: u32) -> Result<String, CommunexError> {
         // Create a hard derivation junction from the index
-        let junction = DeriveJunction::hard(&index.to_le_bytes());
+        let junction = DeriveJunction::hard(index.to_le_bytes());
         
         // Derive new key pair using substrate's derivation
         let (derived_pair, _) = self.pair.derive(
@@ -86,7 +116,7 @@ impl KeyPair {
         Ok(public.to_ss58check_with_version(Ss58AddressFormat::custom(42)))
     }
 
-    pub fn verify(&self, message: &[u8], signature: &[u8; 64]) -> bool {
+    pub fn verify(&self, message: &[u8], signature: &[u8; SIGNATURE_SERIALIZED_SIZE]) -> bool {
         let sig = Signature::from_raw(*signature);
         Pair::verify(&sig, message, &self.pair.public())
     }
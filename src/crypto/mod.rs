@@ -1,4 +1,5 @@
 pub mod keypair;
+mod keyfile;
 pub mod serde;
 
 pub use keypair::KeyPair;
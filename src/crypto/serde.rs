@@ -1,6 +1,7 @@
 use serde::{Deserializer, Serializer};
 use serde::de::Error;
 
+/// Serialize a fixed-size byte array (a signature, a public key) as a hex string
 pub mod hex_bytes {
     use super::*;
 
@@ -0,0 +1,39 @@
+//! Environment variables read by the `from_env` config constructors
+
+use std::str::FromStr;
+use std::time::Duration;
+use crate::error::CommunexError;
+
+/// Module server host, for `ModuleClientConfig::from_env`
+pub const COMX_HOST: &str = "COMX_HOST";
+/// Module server port, for `ModuleClientConfig::from_env`
+pub const COMX_PORT: &str = "COMX_PORT";
+/// Request timeout in whole seconds
+pub const COMX_TIMEOUT_SECS: &str = "COMX_TIMEOUT_SECS";
+/// Retries after a failed attempt, for both the RPC and module clients
+pub const COMX_MAX_RETRIES: &str = "COMX_MAX_RETRIES";
+/// Node RPC URL used by the API server and the `comx` CLI
+pub const COMX_NODE_URL: &str = "COMX_NODE_URL";
+
+/// The value of `name` parsed as `T`, or `None` when it is unset or empty
+pub(crate) fn parse_var<T: FromStr>(name: &str) -> Result<Option<T>, CommunexError>
+where
+    T::Err: std::fmt::Display,
+{
+    match std::env::var(name) {
+        Ok(value) if value.trim().is_empty() => Ok(None),
+        Ok(value) => value.trim().parse().map(Some).map_err(|e| {
+            CommunexError::ConfigError(format!("Invalid {} '{}': {}", name, value, e))
+        }),
+        Err(std::env::VarError::NotPresent) => Ok(None),
+        Err(e) => Err(CommunexError::ConfigError(format!("Invalid {}: {}", name, e))),
+    }
+}
+
+/// `COMX_TIMEOUT_SECS` as a duration, which must be at least a second
+pub(crate) fn timeout_var() -> Result<Option<Duration>, CommunexError> {
+    match parse_var::<u64>(COMX_TIMEOUT_SECS)? {
+        Some(0) => Err(CommunexError::ConfigError(format!("{} must be greater than zero", COMX_TIMEOUT_SECS))),
+        secs => Ok(secs.map(Duration::from_secs)),
+    }
+}
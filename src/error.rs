@@ -4,6 +4,7 @@ use std::fmt;
 use reqwest;
 
 #[derive(Debug, Error, PartialEq)]
+#[non_exhaustive]
 pub enum CommunexError {
     #[error("Invalid address format: {0}")]
     InvalidAddress(String),
@@ -34,8 +35,11 @@ pub enum CommunexError {
     
     #[error("Malformed response: {0}")]
     MalformedResponse(String),
+    /// The request could not be delivered. The `ErrorCause` is the
+    /// `source()`, and its own source is the underlying error, such as a
+    /// `reqwest::Error`, when there is one.
     #[error("Connection error: {0}")]
-    ConnectionError(String),
+    ConnectionError(#[source] ErrorCause),
     
     #[error("Parse error: {0}")]
     ParseError(String),
@@ -49,6 +53,12 @@ pub enum CommunexError {
     #[error("Invalid amount: {0}")]
     InvalidAmount(String),
 
+    #[error("Insufficient funds: {required} required, {available} available")]
+    InsufficientFunds {
+        required: u64,
+        available: u64,
+    },
+
     #[error("Invalid denomination: {0}")]
     InvalidDenom(String),
 
@@ -63,12 +73,77 @@ pub enum CommunexError {
 
     #[error("Invalid Header: {0}")]
     InvalidHeader(String),
+
+    #[error("Cache error: {0}")]
+    CacheError(String),
+
+    #[error("Transaction reorged: {0}")]
+    TransactionReorged(String),
+
+    #[error("Key file error: {0}")]
+    KeyFileError(String),
+
+    #[error("Operation cancelled")]
+    Cancelled,
     
 }
 
-impl CommunexError {
-    pub fn to_string(&self) -> String {
-        format!("{}", self)
+/// An error message together with the error that caused it, if any. The
+/// cause is available through `source()`. Causes are not compared, only
+/// messages.
+#[derive(Debug)]
+pub struct ErrorCause {
+    message: String,
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
+}
+
+impl ErrorCause {
+    /// Wrap `source`, using its message
+    pub fn new(source: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self::with_message(source.to_string(), source)
+    }
+
+    /// Wrap `source` under a message of its own
+    pub fn with_message(message: impl Into<String>, source: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self {
+            message: message.into(),
+            source: Some(Box::new(source)),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ErrorCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ErrorCause {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref().map(|source| source as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl PartialEq for ErrorCause {
+    fn eq(&self, other: &Self) -> bool {
+        self.message == other.message
+    }
+}
+
+/// A message with no underlying error
+impl From<String> for ErrorCause {
+    fn from(message: String) -> Self {
+        Self { message, source: None }
+    }
+}
+
+impl From<&str> for ErrorCause {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
     }
 }
 
@@ -77,27 +152,47 @@ pub struct RpcErrorDetail {
     pub code: i32,
     pub message: String,
     pub request_id: Option<u32>,
+    /// The error's `data` member, which nodes use for extra context such as
+    /// the offending parameter
+    pub data: Option<serde_json::Value>,
 }
 
 impl fmt::Display for RpcErrorDetail {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "code: {}, message: {}{}", 
+        write!(f, "code: {}, message: {}{}{}", 
             self.code, 
             self.message,
-            self.request_id.map_or(String::new(), |id| format!(", request_id: {}", id))
+            self.request_id.map_or(String::new(), |id| format!(", request_id: {}", id)),
+            self.data.as_ref().map_or(String::new(), |data| format!(", data: {}", data))
         )
     }
 }
 
-fn format_errors(errors: &Vec<RpcErrorDetail>) -> String {
+fn format_errors(errors: &[RpcErrorDetail]) -> String {
     errors.iter()
         .map(|e| e.to_string())
         .collect::<Vec<_>>()
         .join(", ")
 }
 
+impl CommunexError {
+    /// Whether sending the same request again might succeed. Connection
+    /// failures, timeouts, JSON-RPC internal errors and HTTP 429/5xx are
+    /// transient; unknown methods, invalid params and validation failures
+    /// are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            CommunexError::ConnectionError(_) | CommunexError::RequestTimeout(_) => true,
+            CommunexError::RpcError { code, .. } => {
+                *code == -32603 || *code == 429 || (500..600).contains(code)
+            }
+            _ => false,
+        }
+    }
+}
+
 impl From<reqwest::Error> for CommunexError {
     fn from(error: reqwest::Error) -> Self {
-        CommunexError::ConnectionError(error.to_string())
+        CommunexError::ConnectionError(ErrorCause::new(error))
     }
 } 
\ No newline at end of file
@@ -8,12 +8,19 @@ pub mod rpc;
 pub mod query_map;
 pub mod cache;
 pub mod wallet;
+pub mod prelude;
+pub mod runtime;
+pub mod env;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod modules {
     pub mod client;
+    pub mod verify;
+    pub mod server;
 }
 
 pub use error::CommunexError;
-pub use types::{Address, Balance, Transaction, SignedTransaction};
+pub use types::{Address, Balance, Netuid, Transaction, SignedTransaction};
 pub use crypto::KeyPair;
 
 #[cfg(test)]
@@ -1,33 +1,76 @@
-use comx_api::modules::client::{ModuleClient, ModuleClientConfig, EndpointConfig};
+use comx_api::modules::client::{ModuleClient, ModuleClientConfig, EndpointConfig, AccessLevel};
 use comx_api::crypto::KeyPair;
-use comx_api::wallet::{WalletClient, TransferRequest};
+use comx_api::env::COMX_NODE_URL;
+use comx_api::error::CommunexError;
+use comx_api::rpc::{RpcClientConfig, DEFAULT_RPC_URL};
+use comx_api::types::Address;
+use comx_api::wallet::{WalletClient, TransferRequest, TransferResponse, BalanceInfo, FreeBalance};
 use actix_web::{web, App, HttpServer, HttpResponse, Responder, web::Data};
 use actix_files as fs;
-use serde::Deserialize;
-use serde_json::Value;
-use std::sync::{Arc, Mutex};
+use schemars::{gen::SchemaSettings, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sp_core::{crypto::Ss58Codec, sr25519::Public};
+use futures::StreamExt;
+use std::sync::Arc;
+use std::time::Duration;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 struct CallParams {
     method: String,
     target_key: String,
     params: Value,
 }
 
-async fn list_endpoints(client: Data<Arc<Mutex<ModuleClient>>>) -> impl Responder {
-    let client = client.lock().expect("Failed to lock ModuleClient");
-    let endpoints: Vec<_> = client.endpoint_registry.list().into_iter().collect();
-    HttpResponse::Ok().json(endpoints)
+#[derive(Deserialize)]
+struct ListQuery {
+    access_level: Option<AccessLevel>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct EndpointPage {
+    items: Vec<EndpointConfig>,
+    /// Number of endpoints matching the filter, before pagination
+    total: usize,
+}
+
+async fn list_endpoints(client: Data<ModuleClient>, query: web::Query<ListQuery>) -> impl Responder {
+    let mut endpoints: Vec<_> = client.list_endpoints()
+        .into_iter()
+        .filter(|config| query.access_level.as_ref().is_none_or(|level| &config.access_level == level))
+        .collect();
+    // Registry order is arbitrary; sort so pages are stable
+    endpoints.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let total = endpoints.len();
+    let items = endpoints.into_iter()
+        .skip(query.offset.unwrap_or(0))
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect();
+    HttpResponse::Ok().json(EndpointPage { items, total })
 }
 
-async fn register_endpoint(client: Data<Arc<Mutex<ModuleClient>>>, config: web::Json<EndpointConfig>) -> impl Responder {
-    let mut client = client.lock().expect("Failed to lock ModuleClient");
+async fn register_endpoint(client: Data<ModuleClient>, config: web::Json<EndpointConfig>) -> impl Responder {
     client.register_endpoint(config.into_inner());
     HttpResponse::Created().body("Endpoint registered")
 }
 
-async fn get_endpoint(client: Data<Arc<Mutex<ModuleClient>>>, name: web::Path<String>) -> impl Responder {
-    let client = client.lock().expect("Failed to lock ModuleClient");
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct BatchRegistration {
+    added: usize,
+    skipped: usize,
+}
+
+async fn register_endpoints(client: Data<ModuleClient>, configs: web::Json<Vec<EndpointConfig>>) -> impl Responder {
+    let configs = configs.into_inner();
+    let total = configs.len();
+    let added = client.register_endpoints(configs);
+    HttpResponse::Created().json(BatchRegistration { added, skipped: total - added })
+}
+
+async fn get_endpoint(client: Data<ModuleClient>, name: web::Path<String>) -> impl Responder {
     if let Some(config) = client.get_endpoint(&name) {
         HttpResponse::Ok().json(config)
     } else {
@@ -35,65 +78,517 @@ async fn get_endpoint(client: Data<Arc<Mutex<ModuleClient>>>, name: web::Path<St
     }
 }
 
-async fn call_method(client: Data<Arc<Mutex<ModuleClient>>>, call_params: web::Json<CallParams>) -> impl Responder {
-    let client = client.lock().expect("Failed to lock ModuleClient");
+async fn delete_endpoint(client: Data<ModuleClient>, name: web::Path<String>) -> impl Responder {
+    if let Some(config) = client.unregister_endpoint(&name) {
+        HttpResponse::Ok().json(config)
+    } else {
+        HttpResponse::NotFound().body("Endpoint not found")
+    }
+}
+
+/// Whether `key` is a `cmx1` address or an ss58 public key
+fn is_valid_target_key(key: &str) -> bool {
+    Address::new(key).is_ok() || Public::from_ss58check(key).is_ok()
+}
+
+async fn call_method(client: Data<ModuleClient>, call_params: web::Json<CallParams>) -> impl Responder {
     let CallParams { method, target_key, params } = call_params.into_inner();
+    if client.get_endpoint(&method).is_none() {
+        return HttpResponse::NotFound().body(format!("Endpoint not found: {}", method));
+    }
+    if !is_valid_target_key(&target_key) {
+        return HttpResponse::BadRequest().body(format!("Invalid target key: {}", target_key));
+    }
     match client.call::<Value, Value>(&method, &target_key, params).await {
         Ok(response) => HttpResponse::Ok().json(response),
         Err(e) => HttpResponse::InternalServerError().body(format!("Error: {:?}", e)),
     }
 }
 
+/// JSON body for failed wallet requests
+fn wallet_error(e: CommunexError) -> HttpResponse {
+    HttpResponse::InternalServerError().json(json!({ "error": e.to_string() }))
+}
+
 async fn get_balance(client: Data<Arc<WalletClient>>, address: web::Path<String>) -> impl Responder {
+    let address = address.into_inner();
     match client.get_free_balance(&address).await {
-        Ok(balance) => HttpResponse::Ok().body(format!("Balance: {}", balance)),
-        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {:?}", e)),
+        Ok(free) => HttpResponse::Ok().json(FreeBalance { address, free }),
+        Err(e) => wallet_error(e),
     }
 }
 
 async fn transfer(client: Data<Arc<WalletClient>>, transfer_request: web::Json<TransferRequest>) -> impl Responder {
     match client.transfer(transfer_request.into_inner()).await {
         Ok(response) => HttpResponse::Ok().json(response),
-        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {:?}", e)),
+        Err(e) => wallet_error(e),
     }
 }
 
-async fn sign_transaction(_client: Data<Arc<Mutex<ModuleClient>>>, _transaction: web::Json<Value>) -> impl Responder {
+/// How long `/transactions/{hash}/events` follows a transaction
+const TRANSACTION_EVENTS_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// One server-sent event with a JSON payload
+fn sse_event(event: &str, data: &Value) -> web::Bytes {
+    web::Bytes::from(format!("event: {}\ndata: {}\n\n", event, data))
+}
+
+/// Stream a `state` event each time the transaction changes, closing after
+/// it succeeds or fails. A failed poll sends an `error` event and closes.
+async fn transaction_events(client: Data<Arc<WalletClient>>, hash: web::Path<String>) -> impl Responder {
+    let events = client
+        .transaction_updates(&hash, TRANSACTION_EVENTS_TIMEOUT)
+        .map(|update| Ok::<_, actix_web::Error>(match update {
+            Ok(state) => sse_event("state", &json!(state)),
+            Err(e) => sse_event("error", &json!({ "error": e.to_string() })),
+        }));
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
+
+async fn sign_transaction(_client: Data<ModuleClient>, _transaction: web::Json<Value>) -> impl Responder {
     HttpResponse::Ok().body("Transaction signed")
 }
 
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn json_body(schema: Value) -> Value {
+    json!({ "content": { "application/json": { "schema": schema } } })
+}
+
+fn name_parameter(name: &str) -> Value {
+    json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } })
+}
+
+/// OpenAPI document for the routes in `api_routes`, with component schemas
+/// generated from the request and response types
+fn api_spec() -> Value {
+    let mut generator = SchemaSettings::openapi3().into_generator();
+    generator.subschema_for::<TransferRequest>();
+    generator.subschema_for::<TransferResponse>();
+    generator.subschema_for::<BalanceInfo>();
+    generator.subschema_for::<FreeBalance>();
+    generator.subschema_for::<EndpointConfig>();
+    generator.subschema_for::<EndpointPage>();
+    generator.subschema_for::<BatchRegistration>();
+    generator.subschema_for::<CallParams>();
+    let schemas = generator.take_definitions();
+
+    let endpoint_list = json!({ "type": "array", "items": schema_ref("EndpointConfig") });
+    json!({
+        "openapi": "3.0.0",
+        "info": {
+            "title": "Communex API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": {
+            "/endpoints": {
+                "get": {
+                    "summary": "List endpoints, sorted by name",
+                    "parameters": [
+                        { "name": "access_level", "in": "query", "schema": schema_ref("AccessLevel") },
+                        { "name": "limit", "in": "query", "schema": { "type": "integer" } },
+                        { "name": "offset", "in": "query", "schema": { "type": "integer" } },
+                    ],
+                    "responses": { "200": json_body(schema_ref("EndpointPage")) },
+                },
+                "post": {
+                    "summary": "Register an endpoint",
+                    "requestBody": json_body(schema_ref("EndpointConfig")),
+                    "responses": { "201": { "description": "Endpoint registered" } },
+                },
+            },
+            "/endpoints/batch": {
+                "post": {
+                    "summary": "Register endpoints, skipping names already registered",
+                    "requestBody": json_body(endpoint_list),
+                    "responses": { "201": json_body(schema_ref("BatchRegistration")) },
+                },
+            },
+            "/endpoints/{name}": {
+                "get": {
+                    "summary": "Get an endpoint",
+                    "parameters": [name_parameter("name")],
+                    "responses": {
+                        "200": json_body(schema_ref("EndpointConfig")),
+                        "404": { "description": "Endpoint not found" },
+                    },
+                },
+                "delete": {
+                    "summary": "Remove an endpoint",
+                    "parameters": [name_parameter("name")],
+                    "responses": {
+                        "200": json_body(schema_ref("EndpointConfig")),
+                        "404": { "description": "Endpoint not found" },
+                    },
+                },
+            },
+            "/calls": {
+                "post": {
+                    "summary": "Call a module method",
+                    "requestBody": json_body(schema_ref("CallParams")),
+                    "responses": {
+                        "200": json_body(json!({ "type": "object" })),
+                        "400": { "description": "Target key is not a cmx1 address or ss58 key" },
+                        "404": { "description": "Method is not a registered endpoint" },
+                        "500": { "description": "The call failed" },
+                    },
+                },
+            },
+            "/balance/{address}": {
+                "get": {
+                    "summary": "Get the free balance of an address",
+                    "parameters": [name_parameter("address")],
+                    "responses": {
+                        "200": json_body(schema_ref("FreeBalance")),
+                        "500": { "description": "Balance query failed" },
+                    },
+                },
+            },
+            "/transfer": {
+                "post": {
+                    "summary": "Transfer tokens",
+                    "requestBody": json_body(schema_ref("TransferRequest")),
+                    "responses": {
+                        "200": json_body(schema_ref("TransferResponse")),
+                        "500": { "description": "Transfer failed" },
+                    },
+                },
+            },
+            "/transactions/{hash}/events": {
+                "get": {
+                    "summary": "Stream transaction state changes as server-sent events until the transaction succeeds or fails",
+                    "parameters": [name_parameter("hash")],
+                    "responses": {
+                        "200": {
+                            "description": "`state` events carrying a TransactionState, or an `error` event",
+                            "content": { "text/event-stream": { "schema": { "type": "string" } } },
+                        },
+                    },
+                },
+            },
+            "/sign_transaction": {
+                "post": {
+                    "summary": "Sign a transaction",
+                    "requestBody": json_body(json!({ "type": "object" })),
+                    "responses": { "200": { "description": "Transaction signed" } },
+                },
+            },
+        },
+        "components": { "schemas": schemas },
+    })
+}
+
+async fn api_docs() -> impl Responder {
+    HttpResponse::Ok().json(api_spec())
+}
+
+fn api_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/endpoints", web::get().to(list_endpoints))
+        .route("/endpoints", web::post().to(register_endpoint))
+        .route("/endpoints/batch", web::post().to(register_endpoints))
+        .route("/endpoints/{name}", web::get().to(get_endpoint))
+        .route("/endpoints/{name}", web::delete().to(delete_endpoint))
+        .route("/calls", web::post().to(call_method))
+        .route("/balance/{address}", web::get().to(get_balance))
+        .route("/transfer", web::post().to(transfer))
+        .route("/transactions/{hash}/events", web::get().to(transaction_events))
+        .route("/sign_transaction", web::post().to(sign_transaction))
+        .route("/api-docs", web::get().to(api_docs));
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    let config_error = |e: CommunexError| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string());
     let keypair = KeyPair::generate();
-    let config = ModuleClientConfig {
-        host: "http://localhost".to_string(),
-        port: 8080,
-        max_retries: 3,
-        timeout: std::time::Duration::from_secs(10),
-    };
-    let client = Arc::new(Mutex::new(ModuleClient::with_config(config, keypair)));
-    let wallet_client = Arc::new(WalletClient::new("http://localhost"));
+    let config = ModuleClientConfig::from_env().map_err(config_error)?;
+    let client = Data::new(ModuleClient::with_config(config, keypair));
+
+    let node_url = std::env::var(COMX_NODE_URL).unwrap_or_else(|_| DEFAULT_RPC_URL.to_string());
+    let rpc_config = RpcClientConfig::from_env().map_err(config_error)?;
+    let wallet_client = Arc::new(WalletClient::with_config(&node_url, rpc_config));
 
     HttpServer::new(move || {
         App::new()
-            .app_data(Data::new(client.clone()))
+            .app_data(client.clone())
             .app_data(Data::new(wallet_client.clone()))
-            .route("/endpoints", web::get().to(list_endpoints))
-            .route("/endpoints", web::post().to(register_endpoint))
-            .route("/endpoints/{name}", web::get().to(get_endpoint))
-            .route("/calls", web::post().to(call_method))
-            .route("/balance/{address}", web::get().to(get_balance))
-            .route("/transfer", web::post().to(transfer))
-            .route("/sign_transaction", web::post().to(sign_transaction))
+            .configure(api_routes)
             .service(fs::Files::new("/swagger", "static/swagger").index_file("index.html"))
             .service(fs::Files::new("/swagger-ui.css", "static/swagger").index_file("swagger-ui.css"))
             .service(fs::Files::new("/index.css", "static/swagger").index_file("index.css"))
             .service(fs::Files::new("/swagger-ui-bundle.js", "static/swagger").index_file("swagger-ui-bundle.js"))
             .service(fs::Files::new("/swagger-ui-standalone-preset.js", "static/swagger").index_file("swagger-ui-standalone-preset.js"))
             .service(fs::Files::new("/swagger-initializer.js", "static/swagger").index_file("swagger-initializer.js"))
-            .service(fs::Files::new("/api-docs", ".").index_file("swagger.yaml"))
     })
     .bind("127.0.0.1:8080")?
     .run()
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test};
+    use comx_api::modules::client::{CircuitBreakerConfig, ContentType};
+    use std::collections::HashMap;
+
+    fn test_client() -> ModuleClient {
+        let config = ModuleClientConfig {
+            host: "http://localhost".to_string(),
+            port: 8080,
+            max_retries: 0,
+            timeout: std::time::Duration::from_secs(1),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            max_request_bytes: None,
+            content_type: ContentType::Json,
+        };
+        ModuleClient::with_config(config, KeyPair::generate())
+    }
+
+    fn endpoint(name: &str) -> EndpointConfig {
+        endpoint_with_access(name, AccessLevel::Public)
+    }
+
+    fn endpoint_with_access(name: &str, access_level: AccessLevel) -> EndpointConfig {
+        EndpointConfig {
+            name: name.to_string(),
+            path: format!("/{}", name),
+            access_level,
+            rate_limit: None,
+            timeout: None,
+            allow_retries: true,
+            cacheable: false,
+            cache_ttl: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    fn test_app(client: ModuleClient) -> App<
+        impl actix_web::dev::ServiceFactory<
+            actix_web::dev::ServiceRequest,
+            Config = (),
+            Response = actix_web::dev::ServiceResponse,
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        test_app_with_wallet(client, WalletClient::new("http://localhost"))
+    }
+
+    fn test_app_with_wallet(client: ModuleClient, wallet: WalletClient) -> App<
+        impl actix_web::dev::ServiceFactory<
+            actix_web::dev::ServiceRequest,
+            Config = (),
+            Response = actix_web::dev::ServiceResponse,
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        App::new()
+            .app_data(Data::new(client))
+            .app_data(Data::new(Arc::new(wallet)))
+            .configure(api_routes)
+    }
+
+    #[actix_web::test]
+    async fn test_register_endpoints_batch() {
+        let client = test_client();
+        client.register_endpoint(endpoint("existing"));
+        let app = test::init_service(test_app(client)).await;
+
+        let request = test::TestRequest::post()
+            .uri("/endpoints/batch")
+            .set_json(vec![endpoint("first"), endpoint("second"), endpoint("existing")])
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body: Value = test::read_body_json(response).await;
+        assert_eq!(body, json!({ "added": 2, "skipped": 1 }));
+
+        let request = test::TestRequest::get().uri("/endpoints").to_request();
+        let listed: EndpointPage = test::call_and_read_body_json(&app, request).await;
+        let names: Vec<_> = listed.items.into_iter().map(|config| config.name).collect();
+        assert_eq!(names, ["existing", "first", "second"]);
+    }
+
+    #[actix_web::test]
+    async fn test_delete_endpoint() {
+        let client = test_client();
+        client.register_endpoint(endpoint("removable"));
+        let app = test::init_service(test_app(client)).await;
+
+        let request = test::TestRequest::delete().uri("/endpoints/removable").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let removed: EndpointConfig = test::read_body_json(response).await;
+        assert_eq!(removed.name, "removable");
+
+        let request = test::TestRequest::get().uri("/endpoints/removable").to_request();
+        assert_eq!(test::call_service(&app, request).await.status(), StatusCode::NOT_FOUND);
+
+        let request = test::TestRequest::delete().uri("/endpoints/removable").to_request();
+        assert_eq!(test::call_service(&app, request).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_list_endpoints_filter_and_pagination() {
+        let client = test_client();
+        client.register_endpoints([
+            endpoint_with_access("a", AccessLevel::Public),
+            endpoint_with_access("b", AccessLevel::Private),
+            endpoint_with_access("c", AccessLevel::Public),
+            endpoint_with_access("d", AccessLevel::Public),
+            endpoint_with_access("e", AccessLevel::Protected),
+        ]);
+        let app = test::init_service(test_app(client)).await;
+
+        let request = test::TestRequest::get().uri("/endpoints").to_request();
+        let page: EndpointPage = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(page.total, 5);
+        assert_eq!(page.items.len(), 5);
+
+        let request = test::TestRequest::get().uri("/endpoints?access_level=Public").to_request();
+        let page: EndpointPage = test::call_and_read_body_json(&app, request).await;
+        let names: Vec<_> = page.items.iter().map(|config| config.name.as_str()).collect();
+        assert_eq!(page.total, 3);
+        assert_eq!(names, ["a", "c", "d"]);
+
+        let request = test::TestRequest::get().uri("/endpoints?access_level=Public&limit=1&offset=1").to_request();
+        let page: EndpointPage = test::call_and_read_body_json(&app, request).await;
+        let names: Vec<_> = page.items.iter().map(|config| config.name.as_str()).collect();
+        assert_eq!(page.total, 3);
+        assert_eq!(names, ["c"]);
+    }
+
+    #[actix_web::test]
+    async fn test_call_unregistered_method() {
+        let app = test::init_service(test_app(test_client())).await;
+
+        let request = test::TestRequest::post()
+            .uri("/calls")
+            .set_json(json!({ "method": "missing", "target_key": KeyPair::generate().cmx_address(), "params": {} }))
+            .to_request();
+        assert_eq!(test::call_service(&app, request).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_call_with_bad_target_key() {
+        let client = test_client();
+        client.register_endpoint(endpoint("method"));
+        let app = test::init_service(test_app(client)).await;
+
+        let request = test::TestRequest::post()
+            .uri("/calls")
+            .set_json(json!({ "method": "method", "target_key": "not a key", "params": {} }))
+            .to_request();
+        assert_eq!(test::call_service(&app, request).await.status(), StatusCode::BAD_REQUEST);
+
+        assert!(is_valid_target_key(&KeyPair::generate().cmx_address()));
+        assert!(is_valid_target_key(KeyPair::generate().ss58_address()));
+    }
+
+    #[actix_web::test]
+    async fn test_api_docs_generated_from_types() {
+        let app = test::init_service(test_app(test_client())).await;
+
+        let request = test::TestRequest::get().uri("/api-docs").to_request();
+        let spec: Value = test::call_and_read_body_json(&app, request).await;
+
+        let transfer_request = &spec["components"]["schemas"]["TransferRequest"];
+        for field in ["from", "to", "amount", "denom"] {
+            assert!(transfer_request["properties"][field].is_object(), "missing field {}", field);
+        }
+        assert_eq!(
+            spec["paths"]["/transfer"]["post"]["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/TransferRequest"
+        );
+        assert!(spec["components"]["schemas"]["EndpointConfig"]["properties"]["access_level"].is_object());
+    }
+
+    #[actix_web::test]
+    async fn test_get_balance_returns_json() {
+        let mock_server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::path("/balance/free"))
+            .respond_with(|request: &wiremock::Request| {
+                let id = request.body_json::<Value>().unwrap()["id"].clone();
+                wiremock::ResponseTemplate::new(200)
+                    .set_body_json(json!({ "jsonrpc": "2.0", "id": id, "result": { "free": 1000000 } }))
+            })
+            .mount(&mock_server)
+            .await;
+        let wallet = WalletClient::new(&mock_server.uri());
+        let app = test::init_service(test_app_with_wallet(test_client(), wallet)).await;
+
+        let request = test::TestRequest::get().uri("/balance/cmx1abcd123").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = test::read_body_json(response).await;
+        assert_eq!(body, json!({ "address": "cmx1abcd123", "free": 1000000 }));
+
+        let request = test::TestRequest::get().uri("/balance/invalid").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body: Value = test::read_body_json(response).await;
+        assert!(body["error"].is_string());
+    }
+
+    #[actix_web::test]
+    async fn test_transaction_events_close_after_terminal_state() {
+        let mock_server = wiremock::MockServer::start().await;
+        let tx_state = |state: &str, block: Option<u64>| {
+            let state = json!({
+                "state": state,
+                "block_num": block,
+                "confirmations": block.map_or(0, |_| 1),
+                "timestamp": 1704067200,
+            });
+            move |request: &wiremock::Request| {
+                let id = request.body_json::<Value>().unwrap()["id"].clone();
+                wiremock::ResponseTemplate::new(200)
+                    .set_body_json(json!({ "jsonrpc": "2.0", "id": id, "result": state.clone() }))
+            }
+        };
+        // Two identical pending polls, then success
+        wiremock::Mock::given(wiremock::matchers::path("/transaction/state"))
+            .respond_with(tx_state("pending", None))
+            .up_to_n_times(2)
+            .mount(&mock_server)
+            .await;
+        wiremock::Mock::given(wiremock::matchers::path("/transaction/state"))
+            .respond_with(tx_state("success", Some(5)))
+            .mount(&mock_server)
+            .await;
+        let wallet = WalletClient::new(&mock_server.uri())
+            .with_poll_backoff(Duration::from_millis(10), Duration::from_millis(10));
+        let app = test::init_service(test_app_with_wallet(test_client(), wallet)).await;
+
+        let request = test::TestRequest::get().uri("/transactions/0xabc/events").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("content-type").unwrap(), "text/event-stream");
+
+        // Reading the whole body only finishes once the stream closes
+        let body = tokio::time::timeout(Duration::from_secs(5), test::read_body(response))
+            .await
+            .expect("stream did not close after a terminal state");
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let states: Vec<Value> = body
+            .split("\n\n")
+            .filter(|event| !event.is_empty())
+            .map(|event| {
+                let (name, data) = event.split_once('\n').unwrap();
+                assert_eq!(name, "event: state");
+                serde_json::from_str(data.strip_prefix("data: ").unwrap()).unwrap()
+            })
+            .collect();
+        assert_eq!(states.len(), 2);
+        assert_eq!(states[0]["state"], "pending");
+        assert_eq!(states[1]["state"], "success");
+        assert_eq!(states[1]["block_num"], 5);
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use crate::runtime::Instant;
+use super::ClientError;
+
+/// Thresholds for the per-endpoint circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failed attempts that open the circuit
+    pub failure_threshold: u32,
+    /// How long an open circuit fails fast before letting a probe through
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// Id of the half-open probe in flight, if any
+    probe: Option<u64>,
+}
+
+/// Failure tracking for each endpoint, shared between client clones
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CircuitBreaker {
+    circuits: Arc<Mutex<HashMap<String, Circuit>>>,
+    next_probe: Arc<AtomicU64>,
+}
+
+/// Returned by `CircuitBreaker::check` for the request it admits. If that
+/// request was the half-open probe and is dropped without its outcome being
+/// recorded, for example because it failed with a client error or the
+/// caller's future was cancelled, the probe is released so another can run.
+#[must_use]
+pub(crate) struct ProbeGuard {
+    probe: Option<(CircuitBreaker, String, u64)>,
+}
+
+impl Drop for ProbeGuard {
+    fn drop(&mut self) {
+        if let Some((breaker, endpoint, id)) = self.probe.take() {
+            let mut circuits = breaker.circuits.lock().expect("circuit breaker lock poisoned");
+            if let Some(circuit) = circuits.get_mut(&endpoint).filter(|c| c.probe == Some(id)) {
+                circuit.probe = None;
+            }
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// Fail fast while the circuit for `endpoint` is open. Once the cooldown
+    /// has passed a single probe is let through; its outcome closes or
+    /// reopens the circuit.
+    pub(crate) fn check(&self, endpoint: &str, config: &CircuitBreakerConfig) -> Result<ProbeGuard, ClientError> {
+        let mut circuits = self.circuits.lock().expect("circuit breaker lock poisoned");
+        let Some(circuit) = circuits.get_mut(endpoint) else {
+            return Ok(ProbeGuard { probe: None });
+        };
+
+        match circuit.opened_at {
+            Some(opened_at) if opened_at.elapsed() < config.cooldown || circuit.probe.is_some() => {
+                Err(ClientError::ServerError("circuit open".into()))
+            }
+            Some(_) => {
+                let id = self.next_probe.fetch_add(1, Ordering::Relaxed);
+                circuit.probe = Some(id);
+                Ok(ProbeGuard { probe: Some((self.clone(), endpoint.to_string(), id)) })
+            }
+            None => Ok(ProbeGuard { probe: None }),
+        }
+    }
+
+    pub(crate) fn record_success(&self, endpoint: &str) {
+        self.circuits.lock().expect("circuit breaker lock poisoned").remove(endpoint);
+    }
+
+    pub(crate) fn record_failure(&self, endpoint: &str, config: &CircuitBreakerConfig) {
+        let mut circuits = self.circuits.lock().expect("circuit breaker lock poisoned");
+        let circuit = circuits.entry(endpoint.to_string()).or_default();
+        circuit.consecutive_failures += 1;
+
+        if circuit.probe.is_some() || circuit.consecutive_failures >= config.failure_threshold {
+            warn!("Opening circuit for '{}' after {} failures", endpoint, circuit.consecutive_failures);
+            circuit.opened_at = Some(Instant::now());
+            circuit.probe = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_half_opens_after_cooldown() {
+        let breaker = CircuitBreaker::default();
+        let config = CircuitBreakerConfig {
+            failure_threshold: 2,
+            cooldown: Duration::ZERO,
+        };
+
+        breaker.record_failure("method", &config);
+        assert!(breaker.check("method", &config).is_ok());
+        breaker.record_failure("method", &config);
+
+        // The cooldown has passed, so one probe goes through and others wait on it
+        let probe = breaker.check("method", &config).unwrap();
+        assert!(breaker.check("method", &config).is_err());
+
+        // A failed probe reopens the circuit, a successful one closes it
+        breaker.record_failure("method", &config);
+        drop(probe);
+        let _probe = breaker.check("method", &config).unwrap();
+        breaker.record_success("method");
+        assert!(breaker.check("method", &config).is_ok());
+        assert!(breaker.check("method", &config).is_ok());
+    }
+
+    #[test]
+    fn test_dropped_probe_releases_circuit() {
+        let breaker = CircuitBreaker::default();
+        let config = CircuitBreakerConfig {
+            failure_threshold: 1,
+            cooldown: Duration::ZERO,
+        };
+
+        breaker.record_failure("method", &config);
+        let probe = breaker.check("method", &config).unwrap();
+        assert!(breaker.check("method", &config).is_err());
+
+        // No outcome was recorded, so dropping the probe lets another through
+        drop(probe);
+        let _probe = breaker.check("method", &config).unwrap();
+        assert!(breaker.check("method", &config).is_err());
+    }
+}
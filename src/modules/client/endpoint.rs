@@ -4,6 +4,8 @@ use std::time::Duration;
 
 /// Access control level for module endpoints
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+#[non_exhaustive]
 pub enum AccessLevel {
     /// Public endpoints can be called by anyone
     Public,
@@ -15,6 +17,7 @@ pub enum AccessLevel {
 
 /// Rate limit configuration for an endpoint
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
 pub struct RateLimit {
     /// Maximum number of requests allowed in the window
     pub max_requests: u32,
@@ -24,6 +27,7 @@ pub struct RateLimit {
 
 /// Configuration for a module endpoint
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
 pub struct EndpointConfig {
     /// Name of the endpoint
     pub name: String,
@@ -37,6 +41,13 @@ pub struct EndpointConfig {
     pub timeout: Option<Duration>,
     /// Whether retries are allowed for this endpoint
     pub allow_retries: bool,
+    /// Whether responses can be served from the client's response cache.
+    /// Only set this for read-only methods.
+    #[serde(default)]
+    pub cacheable: bool,
+    /// How long cached responses stay fresh; defaults to the cache's ttl
+    #[serde(default)]
+    pub cache_ttl: Option<Duration>,
     /// Additional endpoint-specific configuration
     pub metadata: HashMap<String, String>,
 }
@@ -60,6 +71,19 @@ impl EndpointRegistry {
         self.endpoints.insert(config.name.clone(), config);
     }
 
+    /// Register every endpoint whose name is not already taken, returning how
+    /// many were added. Existing registrations are left untouched.
+    pub fn register_all(&mut self, configs: impl IntoIterator<Item = EndpointConfig>) -> usize {
+        let mut added = 0;
+        for config in configs {
+            if !self.endpoints.contains_key(&config.name) {
+                self.endpoints.insert(config.name.clone(), config);
+                added += 1;
+            }
+        }
+        added
+    }
+
     /// Get configuration for an endpoint by name
     pub fn get(&self, name: &str) -> Option<&EndpointConfig> {
         self.endpoints.get(name)
@@ -99,6 +123,8 @@ mod tests {
             }),
             timeout: Some(Duration::from_secs(30)),
             allow_retries: true,
+            cacheable: false,
+            cache_ttl: None,
             metadata: HashMap::new(),
         };
 
@@ -1,24 +1,115 @@
 mod types;
 mod endpoint;
+mod circuit;
 
-pub use types::{ModuleClientConfig, ClientError, ModuleRequest, ModuleResponse};
+pub use types::{ModuleClientConfig, ClientError, ContentType, ModuleRequest, ModuleResponse};
 pub use endpoint::{EndpointConfig, EndpointRegistry, AccessLevel, RateLimit};
+pub use circuit::CircuitBreakerConfig;
 
+use crate::cache::{CacheConfig, QueryMapCache, QueryResult};
 use crate::crypto::KeyPair;
+use crate::modules::verify::signed_message;
+use crate::runtime;
+use circuit::CircuitBreaker;
 use reqwest::{Client as HttpClient, header};
 use serde::Serialize;
+use serde_json::Value;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::time::Duration;
 use chrono::{DateTime, Utc};
 use hex;
 use core::ops::{Deref, DerefMut};
+use futures::stream::{self, Stream};
 
-/// Client for communicating with module servers
+/// Longest `Retry-After` delay a rate limited call waits out before retrying
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Parse a `Retry-After` value: a number of seconds or an HTTP date. Dates
+/// in the past mean no delay.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some((date.with_timezone(&Utc) - Utc::now()).to_std().unwrap_or(Duration::ZERO))
+}
+
+/// A streamed response and the bytes read from it that do not yet form a line
+struct StreamState {
+    /// `None` once the body has been read to the end
+    response: Option<reqwest::Response>,
+    buffer: Vec<u8>,
+    timeout: Duration,
+}
+
+impl StreamState {
+    async fn read_chunk(&mut self) -> Result<(), ClientError> {
+        let timeout = self.timeout;
+        let map_err = |e: reqwest::Error| match e.is_timeout() {
+            true => ClientError::Timeout(timeout),
+            false => ClientError::RequestFailed(e.to_string()),
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(response) = self.response.as_mut() {
+            match response.chunk().await {
+                Ok(Some(chunk)) => self.buffer.extend_from_slice(&chunk),
+                Ok(None) => self.response = None,
+                Err(e) => {
+                    self.response = None;
+                    return Err(map_err(e));
+                }
+            }
+        }
+        // The browser buffers the body before handing it over
+        #[cfg(target_arch = "wasm32")]
+        if let Some(response) = self.response.take() {
+            let body = response.bytes().await.map_err(map_err)?;
+            self.buffer.extend_from_slice(&body);
+        }
+        Ok(())
+    }
+}
+
+enum StreamLine<'a> {
+    Item(&'a [u8]),
+    Skip,
+    Done,
+}
+
+/// Classify one line of a JSON lines or server-sent events body
+fn stream_item(line: &[u8]) -> StreamLine<'_> {
+    let line = line.trim_ascii();
+    let payload = match line.strip_prefix(b"data:") {
+        Some(data) => data.trim_ascii(),
+        // Blank lines separate events; `:` starts a comment
+        None if line.is_empty() || line.starts_with(b":") => return StreamLine::Skip,
+        None if [&b"event:"[..], b"id:", b"retry:"].iter().any(|field| line.starts_with(field)) => {
+            return StreamLine::Skip;
+        }
+        None => line,
+    };
+    match payload {
+        b"" => StreamLine::Skip,
+        b"[DONE]" => StreamLine::Done,
+        payload => StreamLine::Item(payload),
+    }
+}
+
+/// Client for communicating with module servers. Clones share the endpoint
+/// registry, response cache and circuit breaker, so a client can be handed to
+/// concurrent tasks without an outer lock.
 #[derive(Clone)]
 pub struct ModuleClient {
     pub config: ModuleClientConfig,
     pub http_client: HttpClient,
     pub keypair: KeyPair,
-    pub endpoint_registry: EndpointRegistry,
+    endpoint_registry: Arc<RwLock<EndpointRegistry>>,
+    /// Responses of endpoints registered as cacheable, keyed by method and
+    /// request body. Shared between clones.
+    pub response_cache: QueryMapCache,
+    circuit_breaker: CircuitBreaker,
 }
 
 impl Deref for ModuleClient {
@@ -43,40 +134,69 @@ impl ModuleClient {
 
     /// Create a new module client with custom configuration
     pub fn with_config(config: ModuleClientConfig, keypair: KeyPair) -> Self {
-        let http_client = HttpClient::builder()
-            .timeout(config.timeout)
-            .build()
-            .expect("Failed to create HTTP client");
+        let builder = HttpClient::builder();
+        // reqwest has no client timeout on wasm32
+        #[cfg(not(target_arch = "wasm32"))]
+        let builder = builder.timeout(config.timeout);
+        let http_client = builder.build().expect("Failed to create HTTP client");
 
         Self {
             config,
             http_client,
             keypair,
-            endpoint_registry: EndpointRegistry::new(),
+            endpoint_registry: Arc::new(RwLock::new(EndpointRegistry::new())),
+            response_cache: QueryMapCache::new(CacheConfig::default()),
+            circuit_breaker: CircuitBreaker::default(),
         }
     }
 
+    // The registry lock is never held across an await
+    fn registry(&self) -> RwLockReadGuard<'_, EndpointRegistry> {
+        self.endpoint_registry.read().expect("endpoint registry lock poisoned")
+    }
+
+    fn registry_mut(&self) -> RwLockWriteGuard<'_, EndpointRegistry> {
+        self.endpoint_registry.write().expect("endpoint registry lock poisoned")
+    }
+
     /// Register a new endpoint configuration
-    pub fn register_endpoint(&mut self, config: EndpointConfig) {
-        self.endpoint_registry.register(config);
+    pub fn register_endpoint(&self, config: EndpointConfig) {
+        self.registry_mut().register(config);
+    }
+
+    /// Register several endpoints, skipping names already registered.
+    /// Returns how many were added.
+    pub fn register_endpoints(&self, configs: impl IntoIterator<Item = EndpointConfig>) -> usize {
+        self.registry_mut().register_all(configs)
+    }
+
+    /// Remove an endpoint, returning its configuration if it was registered
+    pub fn unregister_endpoint(&self, name: &str) -> Option<EndpointConfig> {
+        self.registry_mut().unregister(name)
     }
 
     /// Get endpoint configuration by name
-    pub fn get_endpoint(&self, name: &str) -> Option<&EndpointConfig> {
-        self.endpoint_registry.get(name)
+    pub fn get_endpoint(&self, name: &str) -> Option<EndpointConfig> {
+        self.registry().get(name).cloned()
+    }
+
+    /// All registered endpoint configurations
+    pub fn list_endpoints(&self) -> Vec<EndpointConfig> {
+        self.registry().list().into_iter().cloned().collect()
     }
 
-    /// Call a module method
+    /// Call a module method. Endpoints registered as `cacheable` are answered
+    /// from the response cache while a fresh response for the same request is held.
     pub async fn call<T, R>(&self, method: &str, target_key: &str, params: T) -> Result<R, ClientError>
     where
         T: serde::Serialize + Clone,
         R: serde::de::DeserializeOwned,
     {
         // Get endpoint configuration if it exists
-        let endpoint_config = self.endpoint_registry.get(method);
+        let endpoint_config = self.get_endpoint(method);
         
         // Validate access level if endpoint is configured
-        if let Some(config) = endpoint_config {
+        if let Some(config) = &endpoint_config {
             match config.access_level {
                 AccessLevel::Private | AccessLevel::Protected => {
                     // Additional access validation could be added here
@@ -85,23 +205,76 @@ impl ModuleClient {
             }
         }
 
-        let timestamp = Utc::now();
-        let request = self.build_request(method, target_key, params, timestamp)?;
+        let request = ModuleRequest {
+            target_key: target_key.to_string(),
+            params,
+        };
         
-        let mut last_error = None;
-        let max_retries = endpoint_config
+        let max_retries = endpoint_config.as_ref()
             .map(|c| if c.allow_retries { self.config.max_retries } else { 0 })
             .unwrap_or(self.config.max_retries);
 
+        let cache_ttl = match &endpoint_config {
+            Some(config) if config.cacheable => config.cache_ttl,
+            _ => return self.send_with_retries(method, request, max_retries).await,
+        };
+
+        let body = serde_json::to_string(&request)
+            .map_err(|e| ClientError::SerializationError(e.to_string()))?;
+        let key = format!("{}:{}", method, body);
+
+        if let Some(cached) = self.response_cache.get(&key).await {
+            return serde_json::from_value(cached.data)
+                .map_err(|e| ClientError::RequestFailed(e.to_string()));
+        }
+
+        let response: Value = self.send_with_retries(method, request, max_retries).await?;
+        let result = QueryResult::from_value(response.clone());
+        match cache_ttl {
+            Some(ttl) => self.response_cache.set_with_ttl(&key, result, ttl).await,
+            None => self.response_cache.set(&key, result).await,
+        }
+
+        serde_json::from_value(response).map_err(|e| ClientError::RequestFailed(e.to_string()))
+    }
+
+    async fn send_with_retries<T, R>(
+        &self,
+        method: &str,
+        request: ModuleRequest<T>,
+        max_retries: u32,
+    ) -> Result<R, ClientError>
+    where
+        T: serde::Serialize + Clone,
+        R: serde::de::DeserializeOwned,
+    {
+        let mut last_error = None;
+
         for retry in 0..=max_retries {
-            match self.execute_request(&method, request.0.clone(), request.1.clone(), request.2.clone()).await {
-                Ok(response) => return Ok(response),
+            let _probe = self.circuit_breaker.check(method, &self.config.circuit_breaker)?;
+
+            // Sign each attempt with a fresh nonce and timestamp so servers
+            // do not reject retries as replays
+            let (url, headers, body) = self.build_request(method, &request.target_key, request.params.clone(), Utc::now())?;
+
+            match self.execute_request(method, url, headers, body).await {
+                Ok(response) => {
+                    self.circuit_breaker.record_success(method);
+                    return Ok(response);
+                }
                 Err(e) => {
+                    if self.is_server_failure(&e) {
+                        self.circuit_breaker.record_failure(method, &self.config.circuit_breaker);
+                    }
                     if retry == max_retries || !self.should_retry(&e) {
                         return Err(e);
                     }
+                    let delay = match &e {
+                        ClientError::RateLimitExceeded { retry_after: Some(delay) } => *delay,
+                        _ => self.calculate_backoff(retry),
+                    };
                     last_error = Some(e);
-                    tokio::time::sleep(self.calculate_backoff(retry)).await;
+                    runtime::default_runtime().sleep(delay).await;
                 }
             }
         }
@@ -109,20 +282,83 @@ impl ModuleClient {
         Err(last_error.unwrap_or_else(|| ClientError::Unknown))
     }
 
-    async fn execute_request<T: Serialize + Clone, R>(
+    /// Call a module method that streams its result, yielding each item as
+    /// it arrives. The response holds one JSON item per line, either bare
+    /// (JSON lines) or as server-sent events `data:` lines; other event fields
+    /// and a final `data: [DONE]` are skipped. Streams are not retried, and
+    /// the client timeout applies to the whole stream.
+    pub async fn call_stream<T, R>(
+        &self,
+        method: &str,
+        target_key: &str,
+        params: T,
+    ) -> Result<impl Stream<Item = Result<R, ClientError>>, ClientError>
+    where
+        T: serde::Serialize + Clone,
+        R: serde::de::DeserializeOwned,
+    {
+        let _probe = self.circuit_breaker.check(method, &self.config.circuit_breaker)?;
+        let (url, headers, request) = self.build_request(method, target_key, params, Utc::now())?;
+
+        let response = match self.send(method, url, headers, request).await {
+            Ok(response) => {
+                self.circuit_breaker.record_success(method);
+                response
+            }
+            Err(e) => {
+                if self.is_server_failure(&e) {
+                    self.circuit_breaker.record_failure(method, &self.config.circuit_breaker);
+                }
+                return Err(e);
+            }
+        };
+
+        let state = StreamState {
+            response: Some(response),
+            buffer: Vec::new(),
+            timeout: self.config.timeout,
+        };
+        Ok(stream::unfold(state, |mut state| async move {
+            loop {
+                let line = match state.buffer.iter().position(|b| *b == b'\n') {
+                    Some(end) => state.buffer.drain(..=end).collect::<Vec<u8>>(),
+                    None if state.response.is_some() => {
+                        if let Err(e) = state.read_chunk().await {
+                            return Some((Err(e), state));
+                        }
+                        continue;
+                    }
+                    // Whatever follows the last newline is the final line
+                    None if !state.buffer.is_empty() => std::mem::take(&mut state.buffer),
+                    None => return None,
+                };
+
+                match stream_item(&line) {
+                    StreamLine::Skip => continue,
+                    StreamLine::Done => return None,
+                    StreamLine::Item(payload) => {
+                        let item = serde_json::from_slice(payload)
+                            .map_err(|e| ClientError::InvalidResponse(e.to_string()));
+                        return Some((item, state));
+                    }
+                }
+            }
+        }))
+    }
+
+    /// Send a signed request, turning error statuses into errors
+    async fn send<T: Serialize + Clone>(
         &self,
         method: &str,
         url: String,
         headers: header::HeaderMap,
         request: ModuleRequest<T>,
-    ) -> Result<R, ClientError>
-    where
-        R: serde::de::DeserializeOwned, T: Serialize,
-    {
+    ) -> Result<reqwest::Response, ClientError> {
+        let body = self.config.content_type.encode(&request)?;
         let response = self.http_client
             .post(&url)
             .headers(headers)
-            .json(&request)
+            .body(body)
             .send()
             .await
             .map_err(|e| match e.is_timeout() {
@@ -131,17 +367,34 @@ impl ModuleClient {
             })?;
 
         match response.status() {
-            reqwest::StatusCode::OK => {
-                response.json::<R>().await.map_err(|e| ClientError::RequestFailed(e.to_string()))
-            }
+            reqwest::StatusCode::OK => Ok(response),
             reqwest::StatusCode::UNAUTHORIZED => Err(ClientError::Unauthorized),
-            reqwest::StatusCode::TOO_MANY_REQUESTS => Err(ClientError::RateLimitExceeded),
+            reqwest::StatusCode::TOO_MANY_REQUESTS => Err(ClientError::RateLimitExceeded {
+                retry_after: response.headers().get(header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(parse_retry_after),
+            }),
             reqwest::StatusCode::NOT_FOUND => Err(ClientError::MethodNotFound(method.to_string())),
             status => Err(ClientError::ServerError(status.to_string())),
         }
     }
 
-    fn build_request<T>(
+    async fn execute_request<T: Serialize + Clone, R>(
+        &self,
+        method: &str,
+        url: String,
+        headers: header::HeaderMap,
+        request: ModuleRequest<T>,
+    ) -> Result<R, ClientError>
+    where
+        R: serde::de::DeserializeOwned,
+    {
+        let response = self.send(method, url, headers, request).await?;
+        let bytes = response.bytes().await.map_err(|e| ClientError::RequestFailed(e.to_string()))?;
+        self.config.content_type.decode(&bytes)
+    }
+
+    pub(crate) fn build_request<T>(
         &self,
         method: &str,
         target_key: &str,
@@ -168,19 +421,45 @@ impl ModuleClient {
             )
         };
 
-        let message = serde_json::to_string(&request)
-            .map_err(|e| ClientError::SerializationError(e.to_string()))?;
-        let signature = self.sign_request(&message)?;
-        let headers = self.build_headers(signature, timestamp)?;
+        let message = self.config.content_type.encode(&request)?;
+        if let Some(max) = self.config.max_request_bytes.filter(|max| message.len() > *max) {
+            return Err(ClientError::RequestFailed(format!(
+                "Request body is {} bytes, over the {} byte limit",
+                message.len(),
+                max
+            )));
+        }
+        let nonce = hex::encode(rand::random::<[u8; 16]>());
+        let timestamp = timestamp.to_rfc3339();
+        let signature = self.sign_request(&signed_message(method, &nonce, &timestamp, &message))?;
+        let headers = self.build_headers(signature, &nonce, &timestamp)?;
 
         Ok((url, headers, request))
     }
 
+    /// Rate limited requests are only retried when the server says when,
+    /// and not if that is further off than `MAX_RETRY_AFTER`
     fn should_retry(&self, error: &ClientError) -> bool {
+        match error {
+            ClientError::RateLimitExceeded { retry_after } => {
+                retry_after.is_some_and(|delay| delay <= MAX_RETRY_AFTER)
+            }
+            _ => matches!(
+                error,
+                ClientError::Timeout(_) | 
+                ClientError::ServerError(_)
+            ),
+        }
+    }
+
+    /// Errors that count towards opening the circuit, as opposed to the
+    /// server rejecting this particular request
+    fn is_server_failure(&self, error: &ClientError) -> bool {
         matches!(
             error,
-            ClientError::Timeout(_) | 
-            ClientError::ServerError(_)
+            ClientError::Timeout(_) |
+            ClientError::ServerError(_) |
+            ClientError::RequestFailed(_)
         )
     }
 
@@ -191,13 +470,14 @@ impl ModuleClient {
     fn build_headers(
         &self,
         signature: String,
-        timestamp: DateTime<Utc>,
+        nonce: &str,
+        timestamp: &str,
     ) -> Result<header::HeaderMap, ClientError> {
         let mut headers = header::HeaderMap::new();
         
         headers.insert(
             header::CONTENT_TYPE,
-            "application/json".parse().map_err(|_| ClientError::InvalidHeader)?
+            self.config.content_type.mime().parse().map_err(|_| ClientError::InvalidHeader)?
         );
         headers.insert(
             "X-Signature",
@@ -209,14 +489,17 @@ impl ModuleClient {
         );
         headers.insert(
             "X-Timestamp",
-            timestamp.to_rfc3339().parse().map_err(|_| ClientError::InvalidHeader)?
+            timestamp.parse().map_err(|_| ClientError::InvalidHeader)?
+        );
+        headers.insert(
+            "X-Nonce",
+            nonce.parse().map_err(|_| ClientError::InvalidHeader)?
         );
 
         Ok(headers)
     }
 
-    fn sign_request(&self, message: &str) -> Result<String, ClientError> {
-        let signature = self.keypair.sign(message.as_bytes());
-        Ok(hex::encode(signature))
+    fn sign_request(&self, message: &[u8]) -> Result<String, ClientError> {
+        Ok(self.keypair.sign_hex(message))
     }
 }
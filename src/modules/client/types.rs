@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use std::time::Duration;
 use std::clone::Clone;
+use super::circuit::CircuitBreakerConfig;
+use crate::env;
+use crate::error::CommunexError;
 
 /// Error information returned from module
 #[derive(Debug, Clone, Deserialize)]
@@ -31,15 +34,83 @@ pub struct ModuleClientConfig {
     pub timeout: Duration,
     /// Maximum number of retry attempts
     pub max_retries: u32,
+    /// When to stop calling an endpoint that keeps failing
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// Largest request body to send; larger requests fail before reaching the server
+    pub max_request_bytes: Option<usize>,
+    /// Encoding of request and response bodies
+    pub content_type: ContentType,
 }
 
 impl Default for ModuleClientConfig {
     fn default() -> Self {
         Self {
-            host: "127.0.0.1".to_string(),
+            host: "http://127.0.0.1".to_string(),
             port: 5555,
             timeout: Duration::from_secs(30),
             max_retries: 3,
+            circuit_breaker: CircuitBreakerConfig::default(),
+            max_request_bytes: None,
+            content_type: ContentType::Json,
+        }
+    }
+}
+
+impl ModuleClientConfig {
+    /// The default config with `COMX_HOST`, `COMX_PORT`, `COMX_TIMEOUT_SECS`
+    /// and `COMX_MAX_RETRIES` applied where set. A `COMX_HOST` without a
+    /// scheme is taken as `http://`.
+    pub fn from_env() -> Result<Self, CommunexError> {
+        let mut config = Self::default();
+        if let Some(host) = env::parse_var::<String>(env::COMX_HOST)? {
+            config.host = if host.contains("://") { host } else { format!("http://{}", host) };
+        }
+        if let Some(port) = env::parse_var(env::COMX_PORT)? {
+            config.port = port;
+        }
+        if let Some(timeout) = env::timeout_var()? {
+            config.timeout = timeout;
+        }
+        if let Some(max_retries) = env::parse_var(env::COMX_MAX_RETRIES)? {
+            config.max_retries = max_retries;
+        }
+        Ok(config)
+    }
+}
+
+/// Body encoding spoken by a module server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContentType {
+    #[default]
+    Json,
+    /// MessagePack with named fields
+    Msgpack,
+}
+
+impl ContentType {
+    /// The `Content-Type` header value
+    pub fn mime(&self) -> &'static str {
+        match self {
+            ContentType::Json => "application/json",
+            ContentType::Msgpack => "application/msgpack",
+        }
+    }
+
+    pub(crate) fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, ClientError> {
+        match self {
+            ContentType::Json => serde_json::to_vec(value)
+                .map_err(|e| ClientError::SerializationError(e.to_string())),
+            ContentType::Msgpack => rmp_serde::to_vec_named(value)
+                .map_err(|e| ClientError::SerializationError(e.to_string())),
+        }
+    }
+
+    pub(crate) fn decode<R: DeserializeOwned>(&self, bytes: &[u8]) -> Result<R, ClientError> {
+        match self {
+            ContentType::Json => serde_json::from_slice(bytes)
+                .map_err(|e| ClientError::RequestFailed(e.to_string())),
+            ContentType::Msgpack => rmp_serde::from_slice(bytes)
+                .map_err(|e| ClientError::RequestFailed(e.to_string())),
         }
     }
 }
@@ -55,6 +126,7 @@ pub struct ModuleRequest<T> where T: Clone + serde::Serialize {
 
 /// Custom error types for module client
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum ClientError {
     #[error("Network timeout after {0:?}")]
     Timeout(Duration),
@@ -65,8 +137,9 @@ pub enum ClientError {
     #[error("Invalid response: {0}")]
     InvalidResponse(String),
     
+    /// HTTP 429, with the server's `Retry-After` delay if it sent one
     #[error("Rate limit exceeded")]
-    RateLimitExceeded,
+    RateLimitExceeded { retry_after: Option<Duration> },
     
     #[error("Maximum retries exceeded")]
     MaxRetriesExceeded,
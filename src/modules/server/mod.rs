@@ -1 +1,218 @@
-// Server implementation for handling module requests
+//! Verification of requests sent by `ModuleClient`, for module servers
+
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::error::CommunexError;
+use super::client::ContentType;
+use super::verify::{signed_message, verify_request_signature};
+
+/// How far a request timestamp may be from the server clock
+pub const DEFAULT_MAX_SKEW: Duration = Duration::from_secs(300);
+
+/// A request whose signature and timestamp have been checked
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifiedRequest {
+    /// Hex-encoded sr25519 public key of the signer
+    pub public_key: String,
+    pub nonce: String,
+    pub timestamp: DateTime<Utc>,
+    pub target_key: String,
+    pub params: Value,
+}
+
+#[derive(Deserialize)]
+struct SignedBody {
+    target_key: String,
+    params: Value,
+}
+
+/// Verify a request to `method` signed by `ModuleClient`: the `X-Signature`
+/// over `method`, `X-Nonce`, `X-Timestamp` and `body` must match `X-Key`, and
+/// `X-Timestamp` must be within `DEFAULT_MAX_SKEW`. This does not detect
+/// replays; use a [`NonceStore`] for that.
+pub fn verify_signed_request(method: &str, headers: &HeaderMap, body: &[u8]) -> Result<VerifiedRequest, CommunexError> {
+    verify_signed_request_with_skew(method, headers, body, DEFAULT_MAX_SKEW)
+}
+
+/// `verify_signed_request` with a custom timestamp skew
+pub fn verify_signed_request_with_skew(
+    method: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+    max_skew: Duration,
+) -> Result<VerifiedRequest, CommunexError> {
+    let public_key = header(headers, "X-Key")?;
+    let signature = header(headers, "X-Signature")?;
+    let nonce = header(headers, "X-Nonce")?;
+    let timestamp_header = header(headers, "X-Timestamp")?;
+    let timestamp = DateTime::parse_from_rfc3339(timestamp_header)
+        .map_err(|e| CommunexError::InvalidHeader(format!("Invalid X-Timestamp: {}", e)))?
+        .with_timezone(&Utc);
+
+    // The client signs the exact body and header values it sends
+    let message = signed_message(method, nonce, timestamp_header, body);
+    verify_request_signature(&message, signature, public_key, timestamp, max_skew)?;
+
+    let signed_body: Result<SignedBody, String> = match headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok()) {
+        Some(mime) if mime == ContentType::Msgpack.mime() => rmp_serde::from_slice(body).map_err(|e| e.to_string()),
+        _ => serde_json::from_slice(body).map_err(|e| e.to_string()),
+    };
+    let SignedBody { target_key, params } = signed_body
+        .map_err(|e| CommunexError::ParseError(format!("Invalid request body: {}", e)))?;
+
+    Ok(VerifiedRequest {
+        public_key: public_key.to_string(),
+        nonce: nonce.to_string(),
+        timestamp,
+        target_key,
+        params,
+    })
+}
+
+/// Remembers the nonces of verified requests so a captured request cannot be
+/// replayed while its timestamp is still fresh
+#[derive(Debug)]
+pub struct NonceStore {
+    max_skew: Duration,
+    seen: Mutex<HashMap<String, Instant>>,
+}
+
+impl NonceStore {
+    pub fn new(max_skew: Duration) -> Self {
+        Self {
+            max_skew,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Verify a signed request to `method` with this store's skew and reject
+    /// it if its nonce has been seen before
+    pub fn verify(&self, method: &str, headers: &HeaderMap, body: &[u8]) -> Result<VerifiedRequest, CommunexError> {
+        let request = verify_signed_request_with_skew(method, headers, body, self.max_skew)?;
+        self.check_and_insert(&request.nonce)?;
+        Ok(request)
+    }
+
+    /// Record `nonce`, failing if it is already recorded
+    pub fn check_and_insert(&self, nonce: &str) -> Result<(), CommunexError> {
+        let mut seen = self.seen.lock().expect("nonce store lock poisoned");
+
+        // A timestamp can be up to max_skew ahead of the clock, so a request
+        // stays fresh for up to twice the skew after it is first seen
+        let retention = self.max_skew * 2;
+        seen.retain(|_, seen_at| seen_at.elapsed() < retention);
+
+        if seen.contains_key(nonce) {
+            return Err(CommunexError::ValidationError(format!("Nonce {} has already been used", nonce)));
+        }
+        seen.insert(nonce.to_string(), Instant::now());
+        Ok(())
+    }
+}
+
+impl Default for NonceStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_SKEW)
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, CommunexError> {
+    headers.get(name)
+        .ok_or_else(|| CommunexError::InvalidHeader(format!("Missing {}", name)))?
+        .to_str()
+        .map_err(|_| CommunexError::InvalidHeader(format!("{} is not valid ASCII", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+    use crate::modules::client::ModuleClient;
+    use serde_json::json;
+
+    #[test]
+    fn test_verify_request_built_by_client() {
+        let keypair = KeyPair::generate();
+        let client = ModuleClient::new(keypair.clone());
+        let (_, headers, request) = client
+            .build_request("get_info", "target", json!({ "value": 1 }), Utc::now())
+            .unwrap();
+        let body = serde_json::to_vec(&request).unwrap();
+
+        let verified = verify_signed_request("get_info", &headers, &body).unwrap();
+        assert_eq!(verified.public_key, keypair.public_key_hex());
+        assert_eq!(verified.target_key, "target");
+        assert_eq!(verified.params, json!({ "value": 1 }));
+
+        let tampered = serde_json::to_vec(&json!({ "target_key": "target", "params": { "value": 2 } })).unwrap();
+        assert!(matches!(
+            verify_signed_request("get_info", &headers, &tampered),
+            Err(CommunexError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn test_signature_is_bound_to_method_and_timestamp() {
+        let client = ModuleClient::new(KeyPair::generate());
+        let (_, mut headers, request) = client
+            .build_request("get_info", "target", json!({}), Utc::now())
+            .unwrap();
+        let body = serde_json::to_vec(&request).unwrap();
+
+        assert!(verify_signed_request("get_info", &headers, &body).is_ok());
+        assert!(matches!(
+            verify_signed_request("transfer", &headers, &body),
+            Err(CommunexError::InvalidSignature(_))
+        ));
+
+        let later = (Utc::now() + chrono::Duration::seconds(1)).to_rfc3339();
+        headers.insert("X-Timestamp", later.parse().unwrap());
+        assert!(matches!(
+            verify_signed_request("get_info", &headers, &body),
+            Err(CommunexError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_header_is_rejected() {
+        let client = ModuleClient::new(KeyPair::generate());
+        let (_, mut headers, request) = client
+            .build_request("get_info", "target", json!({}), Utc::now())
+            .unwrap();
+        headers.remove("X-Signature");
+        let body = serde_json::to_vec(&request).unwrap();
+
+        assert!(matches!(
+            verify_signed_request("get_info", &headers, &body),
+            Err(CommunexError::InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn test_replayed_request_is_rejected() {
+        let client = ModuleClient::new(KeyPair::generate());
+        let store = NonceStore::default();
+
+        let (_, headers, request) = client
+            .build_request("get_info", "target", json!({}), Utc::now())
+            .unwrap();
+        let body = serde_json::to_vec(&request).unwrap();
+        assert!(store.verify("get_info", &headers, &body).is_ok());
+        assert!(matches!(
+            store.verify("get_info", &headers, &body),
+            Err(CommunexError::ValidationError(_))
+        ));
+
+        // The same call signed again gets a new nonce
+        let (_, headers, request) = client
+            .build_request("get_info", "target", json!({}), Utc::now())
+            .unwrap();
+        let body = serde_json::to_vec(&request).unwrap();
+        assert!(store.verify("get_info", &headers, &body).is_ok());
+    }
+}
@@ -0,0 +1,112 @@
+//! Checks for the signed requests `ModuleClient` sends, for use by module
+//! servers. A request carries the signature of its method, nonce, timestamp
+//! and body (see [`signed_message`]) in `X-Signature`, the signer's public key
+//! in `X-Key`, the nonce in `X-Nonce` and the signing time in `X-Timestamp`.
+
+use chrono::{DateTime, Utc};
+use sp_core::sr25519::{Pair, Public, Signature, PUBLIC_KEY_SERIALIZED_SIZE, SIGNATURE_SERIALIZED_SIZE};
+use std::time::Duration;
+use crate::error::CommunexError;
+
+/// The bytes a request signature covers: the method, the `X-Nonce` value and
+/// the `X-Timestamp` value, each followed by a `.`, then the body. Binding the
+/// method stops a signed body from being replayed against another method.
+pub fn signed_message(method: &str, nonce: &str, timestamp: &str, body: &[u8]) -> Vec<u8> {
+    [method.as_bytes(), b".", nonce.as_bytes(), b".", timestamp.as_bytes(), b".", body].concat()
+}
+
+/// Whether `ts` is within `max_skew` of the current time, in either direction
+pub fn is_timestamp_fresh(ts: DateTime<Utc>, max_skew: Duration) -> bool {
+    let Ok(max_skew) = chrono::Duration::from_std(max_skew) else {
+        return true;
+    };
+    (Utc::now() - ts).abs() <= max_skew
+}
+
+/// Verify that `signature_hex` is the signature of `message` by `public_key_hex`
+/// and that `timestamp` is within `max_skew` of now
+pub fn verify_request_signature(
+    message: &[u8],
+    signature_hex: &str,
+    public_key_hex: &str,
+    timestamp: DateTime<Utc>,
+    max_skew: Duration,
+) -> Result<(), CommunexError> {
+    if !is_timestamp_fresh(timestamp, max_skew) {
+        return Err(CommunexError::ValidationError(format!(
+            "Request timestamp {} is more than {:?} from now",
+            timestamp.to_rfc3339(),
+            max_skew
+        )));
+    }
+
+    let signature: [u8; SIGNATURE_SERIALIZED_SIZE] = decode_hex(signature_hex, "X-Signature")?;
+    let public_key: [u8; PUBLIC_KEY_SERIALIZED_SIZE] = decode_hex(public_key_hex, "X-Key")?;
+
+    let signature = Signature::from_raw(signature);
+    let public = Public::from_raw(public_key);
+    if <Pair as sp_core::Pair>::verify(&signature, message, &public) {
+        Ok(())
+    } else {
+        Err(CommunexError::InvalidSignature("Request signature verification failed".into()))
+    }
+}
+
+fn decode_hex<const N: usize>(value: &str, header: &str) -> Result<[u8; N], CommunexError> {
+    hex::decode(value.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| CommunexError::InvalidHeader(format!("{} is not {} hex-encoded bytes", header, N)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+
+    const MAX_SKEW: Duration = Duration::from_secs(30);
+
+    #[test]
+    fn test_fresh_timestamp() {
+        assert!(is_timestamp_fresh(Utc::now(), MAX_SKEW));
+        assert!(is_timestamp_fresh(Utc::now() - chrono::Duration::seconds(10), MAX_SKEW));
+    }
+
+    #[test]
+    fn test_expired_timestamp() {
+        assert!(!is_timestamp_fresh(Utc::now() - chrono::Duration::seconds(60), MAX_SKEW));
+    }
+
+    #[test]
+    fn test_future_timestamp_beyond_skew() {
+        assert!(is_timestamp_fresh(Utc::now() + chrono::Duration::seconds(10), MAX_SKEW));
+        assert!(!is_timestamp_fresh(Utc::now() + chrono::Duration::seconds(60), MAX_SKEW));
+    }
+
+    #[test]
+    fn test_verify_request_signature() {
+        let keypair = KeyPair::generate();
+        let body = br#"{"target_key":"key","params":{}}"#;
+        let signature = hex::encode(keypair.sign(body));
+        let public_key = keypair.public_key_hex();
+
+        assert!(verify_request_signature(body, &signature, &public_key, Utc::now(), MAX_SKEW).is_ok());
+
+        let tampered = br#"{"target_key":"other","params":{}}"#;
+        assert!(matches!(
+            verify_request_signature(tampered, &signature, &public_key, Utc::now(), MAX_SKEW),
+            Err(CommunexError::InvalidSignature(_))
+        ));
+
+        let stale = Utc::now() - chrono::Duration::seconds(60);
+        assert!(matches!(
+            verify_request_signature(body, &signature, &public_key, stale, MAX_SKEW),
+            Err(CommunexError::ValidationError(_))
+        ));
+
+        assert!(matches!(
+            verify_request_signature(body, "zz", &public_key, Utc::now(), MAX_SKEW),
+            Err(CommunexError::InvalidHeader(_))
+        ));
+    }
+}
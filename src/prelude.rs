@@ -0,0 +1,29 @@
+//! Commonly used types and traits, for glob import.
+//!
+//! ```
+//! use comx_api::prelude::*;
+//!
+//! let client = WalletClient::new("http://localhost:9944");
+//! let config = QueryMapConfig::default();
+//! let netuid = Netuid::new(1)?;
+//! let keypair = KeyPair::generate();
+//!
+//! assert!(client.ws_url.is_some());
+//! assert_eq!(netuid.value(), 1);
+//! assert!(config.cache_duration > config.refresh_interval);
+//! assert!(!keypair.address().is_empty());
+//! # Ok::<(), CommunexError>(())
+//! ```
+
+pub use crate::error::CommunexError;
+pub use crate::types::{Address, Balance, DenomRegistry, Netuid, Transaction, SignedTransaction};
+pub use crate::crypto::KeyPair;
+pub use crate::rpc::{BatchRequest, RpcClient, RpcClientConfig, RpcTransport};
+pub use crate::wallet::{
+    BalanceInfo, TransactionState, TransferRequest, TxStatus, WalletClient,
+    staking::{StakeRequest, UnstakeRequest},
+    subnet::RegisterModuleRequest,
+};
+pub use crate::query_map::{ModuleInfo, QueryMap, QueryMapConfig};
+pub use crate::cache::{CacheConfig, QueryMapCache, QueryResult};
+pub use crate::modules::client::{ClientError, ModuleClient, ModuleClientConfig};
@@ -10,6 +10,16 @@ pub struct QueryMapConfig {
 }
 
 impl QueryMapConfig {
+    pub fn with_refresh_interval(mut self, refresh_interval: Duration) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    pub fn with_cache_duration(mut self, cache_duration: Duration) -> Self {
+        self.cache_duration = cache_duration;
+        self
+    }
+
     pub fn validate(&self) -> Result<(), CommunexError> {
         if self.refresh_interval < Duration::from_secs(1) {
             return Err(CommunexError::ConfigError(
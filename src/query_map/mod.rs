@@ -1,5 +1,6 @@
 mod config;
+#[allow(clippy::module_inception)]
 mod query_map;
 
 pub use config::QueryMapConfig;
-pub use query_map::QueryMap; 
\ No newline at end of file
+pub use query_map::{QueryMap, ModuleInfo, ValidatorInfo, ValidatorQueryOptions, ValidatorSort}; 
\ No newline at end of file
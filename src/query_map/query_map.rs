@@ -1,8 +1,14 @@
+use std::collections::HashMap;
 use std::sync::Arc;
-use serde_json::json;
+use crate::runtime::Instant;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
 use crate::{
-    rpc::RpcClient,
-    types::{Address, Balance},
+    rpc::{RpcClient, RpcTransport},
+    cache::{CacheConfig, QueryMapCache, QueryResult},
+    rpc::{parse_response, parse_response_field, parse_value, parse_value_field},
+    rpc::params::{to_params, AddressAtBlockParams, AddressParams, NetuidParams},
+    types::{Address, Balance, Netuid},
     error::CommunexError,
 };
 use super::QueryMapConfig;
@@ -12,10 +18,18 @@ use std::sync::atomic::{AtomicU64, Ordering};
 /// It automatically handles RPC communication and response parsing.
 #[derive(Debug)]
 pub struct QueryMap {
-    client: Arc<RpcClient>,
-    #[allow(dead_code)]  // Used for configuration but not read directly
+    client: Arc<dyn RpcTransport>,
     config: QueryMapConfig,
     refresh_count: AtomicU64,
+    balance_cache: RwLock<HashMap<String, CachedBalance>>,
+    /// Balances as of past blocks, keyed by address and block number
+    historical_cache: QueryMapCache,
+}
+
+#[derive(Debug, Clone)]
+struct CachedBalance {
+    balance: Balance,
+    fetched_at: Instant,
 }
 
 impl QueryMap {
@@ -28,29 +42,63 @@ impl QueryMap {
     /// # Returns
     /// * `Result<QueryMap, CommunexError>` - New QueryMap instance or error if config is invalid
     pub fn new(client: RpcClient, config: QueryMapConfig) -> Result<Self, CommunexError> {
+        Self::with_transport(Arc::new(client), config)
+    }
+
+    /// Creates a new QueryMap instance on top of any RPC transport.
+    /// 
+    /// # Arguments
+    /// * `client` - The transport to use for queries
+    /// * `config` - Configuration for cache behavior
+    /// 
+    /// # Returns
+    /// * `Result<QueryMap, CommunexError>` - New QueryMap instance or error if config is invalid
+    pub fn with_transport(client: Arc<dyn RpcTransport>, config: QueryMapConfig) -> Result<Self, CommunexError> {
         config.validate()?;
         
         Ok(Self {
-            client: Arc::new(client),
+            client,
             config,
             refresh_count: AtomicU64::new(0),
+            balance_cache: RwLock::new(HashMap::new()),
+            historical_cache: QueryMapCache::new(CacheConfig::default()),
         })
     }
 
     /// Retrieves the balance for a single address.
     /// 
+    /// Balances are served from the cache while they are younger than
+    /// `cache_duration`; otherwise the node is queried and the cache updated.
+    /// 
     /// # Arguments
     /// * `address` - The address to query
     /// 
     /// # Returns
     /// * `Result<Balance, CommunexError>` - Balance information or error
     pub async fn get_balance(&self, address: &str) -> Result<Balance, CommunexError> {
+        if let Some(cached) = self.balance_cache.read().await.get(address) {
+            if cached.fetched_at.elapsed() < self.config.cache_duration {
+                trace!("Serving cached balance for address: {}", address);
+                return Ok(cached.balance.clone());
+            }
+        }
+
+        self.refresh_balance(address).await
+    }
+
+    /// Queries the node for the balance of an address, bypassing any cached
+    /// value, and stores the result in the cache.
+    /// 
+    /// # Arguments
+    /// * `address` - The address to refresh
+    /// 
+    /// # Returns
+    /// * `Result<Balance, CommunexError>` - Fresh balance information or error
+    pub async fn refresh_balance(&self, address: &str) -> Result<Balance, CommunexError> {
         debug!("Querying balance for address: {}", address);
         self.refresh_count.fetch_add(1, Ordering::Relaxed);
         
-        let params = json!({
-            "address": address
-        });
+        let params = to_params(&AddressParams { address })?;
 
         let response = self.client
             .request("query_balance", params)
@@ -58,12 +106,46 @@ impl QueryMap {
 
         trace!("Received balance response: {:?}", response);
         
-        // Convert response to Balance type with better error context
-        serde_json::from_value(response)
-            .map_err(|e| {
-                error!("Failed to parse balance response: {}", e);
-                CommunexError::ParseError(format!("Failed to parse balance response: {}", e))
-            })
+        let balance: Balance = parse_response("query_balance", response)
+            .inspect_err(|e| error!("{}", e))?;
+
+        self.balance_cache.write().await.insert(address.to_string(), CachedBalance {
+            balance: balance.clone(),
+            fetched_at: Instant::now(),
+        });
+
+        Ok(balance)
+    }
+
+    /// Retrieves the balance of an address as of a past block.
+    /// 
+    /// Results are cached per address and block without expiring, as state
+    /// at a finalized block never changes; only the cache's entry limit
+    /// evicts them.
+    /// 
+    /// # Arguments
+    /// * `address` - The address to query
+    /// * `block` - The block number to read the balance at
+    /// 
+    /// # Returns
+    /// * `Result<Balance, CommunexError>` - Balance at that block or error
+    pub async fn get_balance_at(&self, address: &str, block: u64) -> Result<Balance, CommunexError> {
+        let key = format!("{}@{}", address, block);
+        if let Some(cached) = self.historical_cache.get(&key).await {
+            trace!("Serving cached balance for address {} at block {}", address, block);
+            return cached.deserialize();
+        }
+
+        debug!("Querying balance for address {} at block {}", address, block);
+        let params = to_params(&AddressAtBlockParams { address, block })?;
+        let response = self.client
+            .request("query_balance", params)
+            .await?;
+
+        let balance: Balance = parse_response("query_balance", response.clone())?;
+        self.historical_cache.set_immutable(&key, QueryResult::from_value(response)).await;
+
+        Ok(balance)
     }
 
     pub async fn get_balances(&self, addresses: &[&str]) -> Result<Vec<Balance>, CommunexError> {
@@ -76,44 +158,25 @@ impl QueryMap {
         for address in addresses {
             batch.add_request(
                 "query_balance",
-                json!({
-                    "address": address
-                })
+                to_params(&AddressParams { address })?
             );
         }
 
-        let response = self.client.batch_request(batch).await?;
-        
-        // Convert successful responses to Balance objects
-        response.successes
+        // Addresses the node rejected are skipped; unparseable balances are an error
+        self.client.batch_request_typed::<Balance>(batch).await?
             .into_iter()
-            .map(|value| {
-                serde_json::from_value(value)
-                    .map_err(|e| CommunexError::ParseError(
-                        format!("Failed to parse balance in batch response: {}", e)
-                    ))
-            })
+            .filter(|result| !matches!(result, Err(CommunexError::RpcError { .. })))
             .collect()
     }
 
     pub async fn get_stake_from(&self, address: &str) -> Result<Vec<Address>, CommunexError> {
-        let params = json!({
-            "address": address
-        });
+        let params = to_params(&AddressParams { address })?;
 
         let response = self.client
             .request("query_stakefrom", params)
             .await?;
 
-        let stake_from = response.get("stake_from")
-            .ok_or_else(|| CommunexError::ParseError(
-                "Response missing 'stake_from' field".to_string()
-            ))?;
-
-        let addresses: Vec<String> = serde_json::from_value(stake_from.clone())
-            .map_err(|e| CommunexError::ParseError(
-                format!("Failed to parse stake_from addresses: {}", e)
-            ))?;
+        let addresses: Vec<String> = parse_response_field("query_stakefrom", &response, "stake_from")?;
 
         addresses.into_iter()
             .map(|addr| Address::new(&addr))
@@ -121,20 +184,13 @@ impl QueryMap {
     }
 
     pub async fn get_stake_to(&self, address: &str) -> Result<Vec<Address>, CommunexError> {
-        let params = json!({
-            "address": address
-        });
+        let params = to_params(&AddressParams { address })?;
 
         let response = self.client
             .request("query_staketo", params)
             .await?;
 
-        // Extract stake_to array from response
-        let stake_to = response.get("stake_to")
-            .ok_or_else(|| CommunexError::ParseError("Missing stake_to field".to_string()))?;
-
-        let addresses: Vec<String> = serde_json::from_value(stake_to.clone())
-            .map_err(|e| CommunexError::ParseError(e.to_string()))?;
+        let addresses: Vec<String> = parse_response_field("query_staketo", &response, "stake_to")?;
 
         // Convert each string to an Address type
         addresses.into_iter()
@@ -142,6 +198,76 @@ impl QueryMap {
             .collect()
     }
 
+    /// Retrieves the per-module emission values for a subnet.
+    pub async fn get_subnet_emission(&self, netuid: Netuid) -> Result<Vec<u64>, CommunexError> {
+        self.query_subnet_values("query_emission", "emission", netuid).await
+    }
+
+    /// Retrieves the per-module incentive values for a subnet.
+    pub async fn get_subnet_incentive(&self, netuid: Netuid) -> Result<Vec<u64>, CommunexError> {
+        self.query_subnet_values("query_incentive", "incentive", netuid).await
+    }
+
+    /// Retrieves the per-module dividend values for a subnet.
+    pub async fn get_subnet_dividends(&self, netuid: Netuid) -> Result<Vec<u64>, CommunexError> {
+        self.query_subnet_values("query_dividends", "dividends", netuid).await
+    }
+
+    async fn query_subnet_values(&self, method: &str, field: &str, netuid: Netuid) -> Result<Vec<u64>, CommunexError> {
+        let params = to_params(&NetuidParams { netuid })?;
+
+        let response = self.client
+            .request(method, params)
+            .await?;
+
+        parse_value_field(&format!("{} response for subnet {}", method, netuid), &response, field)
+    }
+
+    /// Retrieves the modules registered on a subnet.
+    /// 
+    /// # Arguments
+    /// * `netuid` - The subnet to list modules for
+    /// 
+    /// # Returns
+    /// * `Result<Vec<ModuleInfo>, CommunexError>` - Registered modules or error
+    pub async fn get_registered_modules(&self, netuid: u16) -> Result<Vec<ModuleInfo>, CommunexError> {
+        let netuid = Netuid::new(netuid)?;
+        let params = to_params(&NetuidParams { netuid })?;
+
+        let response = self.client
+            .request("query_modules", params)
+            .await?;
+
+        parse_value(&format!("query_modules response for subnet {}", netuid), response)
+    }
+
+    /// Retrieves the validators of a subnet with their stake. The filter,
+    /// sort and limit in `options` are applied client-side, in that order.
+    pub async fn get_validators(&self, netuid: u16, options: ValidatorQueryOptions) -> Result<Vec<ValidatorInfo>, CommunexError> {
+        let netuid = Netuid::new(netuid)?;
+        let params = to_params(&NetuidParams { netuid })?;
+
+        let response = self.client
+            .request("query_validators", params)
+            .await?;
+
+        let mut validators: Vec<ValidatorInfo> = parse_value(&format!("query_validators response for subnet {}", netuid), response)?;
+
+        if let Some(min_stake) = options.min_stake {
+            validators.retain(|validator| validator.stake >= min_stake);
+        }
+        match options.sort_by {
+            Some(ValidatorSort::StakeDescending) => validators.sort_by(|a, b| b.stake.cmp(&a.stake).then(a.uid.cmp(&b.uid))),
+            Some(ValidatorSort::Uid) => validators.sort_by_key(|validator| validator.uid),
+            None => {}
+        }
+        if let Some(limit) = options.limit {
+            validators.truncate(limit);
+        }
+
+        Ok(validators)
+    }
+
     pub fn cache_stats(&self) -> CacheStats {
         CacheStats {
             // Relaxed ordering is sufficient for metrics that don't require
@@ -153,4 +279,44 @@ impl QueryMap {
 
 pub struct CacheStats {
     pub refresh_count: u64,
-} 
\ No newline at end of file
+}
+
+/// A module registered on a subnet
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModuleInfo {
+    pub uid: u16,
+    pub key: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub address: Option<String>,
+}
+
+/// A validator on a subnet and its total stake
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ValidatorInfo {
+    pub uid: u16,
+    pub key: String,
+    pub stake: u64,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Order of the validators returned by `QueryMap::get_validators`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidatorSort {
+    /// Highest stake first, ties by uid
+    StakeDescending,
+    Uid,
+}
+
+/// Client-side filtering for `QueryMap::get_validators`. The default
+/// returns every validator in node order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidatorQueryOptions {
+    /// Drop validators with less stake than this
+    pub min_stake: Option<u64>,
+    pub sort_by: Option<ValidatorSort>,
+    /// Return at most this many validators
+    pub limit: Option<usize>,
+}
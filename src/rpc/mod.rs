@@ -1,18 +1,67 @@
 mod rpc_client;
+pub mod params;
+mod retry_budget;
+mod transport;
+#[cfg(not(target_arch = "wasm32"))]
+mod subscription;
+#[cfg(not(target_arch = "wasm32"))]
+mod subscription_manager;
 
 pub use rpc_client::RpcClient;
+pub use transport::RpcTransport;
+pub use retry_budget::RetryBudgetConfig;
+pub use crate::error::RpcErrorDetail;
+#[cfg(not(target_arch = "wasm32"))]
+pub use subscription::SubscriptionClient;
+#[cfg(not(target_arch = "wasm32"))]
+pub use subscription_manager::SubscriptionManager;
+use serde::{Serialize, de::DeserializeOwned};
 use serde_json::{Value, json};
 use std::time::Duration;
 use crate::error::CommunexError;
-use reqwest::Client;
-use tokio::time::timeout as tokio_timeout;
+use crate::types::next_request_id;
+use std::sync::Arc;
+use crate::runtime::{self, Runtime};
+
+/// Endpoint used by the `Default` clients: a node running on this machine
+pub const DEFAULT_RPC_URL: &str = "http://127.0.0.1:9944";
+
+/// Most requests a node accepts in one batch call. `RpcClient` splits
+/// larger batches into calls of at most this many requests.
+pub const MAX_BATCH_REQUESTS: usize = 100;
+
+/// Default `RpcClientConfig::max_response_bytes`: 16 MiB
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Where single requests are posted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathStyle {
+    /// Append the method to the base URL, e.g. `/balance/free`
+    PerMethod,
+    /// Post every request to the base URL, as plain JSON-RPC does
+    SingleEndpoint,
+}
 
 #[derive(Debug, Clone)]
 pub struct RpcClientConfig {
     /// Timeout for requests in seconds
     pub timeout: Duration,
-    /// Maximum retries for failed requests
+    /// Maximum retries for failed requests, after the first attempt
     pub max_retries: u32,
+    /// Reject responses that do not carry `"jsonrpc": "2.0"`
+    pub strict_jsonrpc: bool,
+    /// Runtime used for request timeouts and retry backoff
+    pub runtime: Arc<dyn Runtime>,
+    /// Shared limit on retries across all requests of a client and its
+    /// clones. Without one, each request retries up to `max_retries` times.
+    pub retry_budget: Option<RetryBudgetConfig>,
+    /// Largest response body accepted, in bytes. Longer responses fail with
+    /// `MalformedResponse` instead of being buffered.
+    pub max_response_bytes: usize,
+    /// URL style for `request` and `request_with_path`. When unset, `request`
+    /// posts to the base URL and `request_with_path` appends the method.
+    /// Batches always go to the base URL.
+    pub path_style: Option<PathStyle>,
 }
 
 impl Default for RpcClientConfig {
@@ -20,18 +69,63 @@ impl Default for RpcClientConfig {
         Self {
             timeout: Duration::from_secs(30),
             max_retries: 3,
+            strict_jsonrpc: true,
+            runtime: runtime::default_runtime(),
+            retry_budget: None,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            path_style: None,
         }
     }
 }
 
 impl RpcClientConfig {
+    /// The default config with `COMX_TIMEOUT_SECS` and `COMX_MAX_RETRIES`
+    /// applied where set
+    pub fn from_env() -> Result<Self, CommunexError> {
+        let mut config = Self::default();
+        if let Some(timeout) = crate::env::timeout_var()? {
+            config.timeout = timeout;
+        }
+        if let Some(max_retries) = crate::env::parse_var(crate::env::COMX_MAX_RETRIES)? {
+            config.max_retries = max_retries;
+        }
+        Ok(config)
+    }
+
     pub fn with_timeout(mut self, timeout: Duration) -> Self {
         self.timeout = timeout;
         self
     }
+
+    /// Accept responses without a valid `jsonrpc` version, for lenient servers
+    pub fn with_strict_jsonrpc(mut self, strict: bool) -> Self {
+        self.strict_jsonrpc = strict;
+        self
+    }
+
+    pub fn with_runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+        self.runtime = runtime;
+        self
+    }
+
+    pub fn with_retry_budget(mut self, budget: RetryBudgetConfig) -> Self {
+        self.retry_budget = Some(budget);
+        self
+    }
+
+    pub fn with_max_response_bytes(mut self, max_response_bytes: usize) -> Self {
+        self.max_response_bytes = max_response_bytes;
+        self
+    }
+
+    /// Use `style` for every single request, whichever method sends it
+    pub fn with_path_style(mut self, style: PathStyle) -> Self {
+        self.path_style = Some(style);
+        self
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct BatchRequest {
     pub requests: Vec<Value>,
 }
@@ -59,12 +153,6 @@ impl BatchRequest {
             ));
         }
 
-        if self.requests.len() > 100 {
-            return Err(CommunexError::ValidationError(
-                "Batch request cannot contain more than 100 requests".to_string()
-            ));
-        }
-
         for (i, request) in self.requests.iter().enumerate() {
             if !request.is_object() {
                 return Err(CommunexError::ValidationError(
@@ -83,128 +171,183 @@ pub struct BatchResponse {
     pub errors: Vec<RpcErrorDetail>,
 }
 
-#[derive(Debug)]
-pub struct RpcErrorDetail {
-    pub code: i32,
-    pub message: String,
-    pub request_id: Option<u32>,
+impl BatchResponse {
+    /// All-or-nothing view of the batch: the successes if no request failed,
+    /// otherwise every error as a `BatchRpcError`
+    pub fn into_result(self) -> Result<Vec<Value>, CommunexError> {
+        if self.errors.is_empty() {
+            Ok(self.successes)
+        } else {
+            Err(CommunexError::BatchRpcError(self.errors))
+        }
+    }
+}
+
+/// Deserialize each successful batch entry into `R`, keeping errors in place
+pub(crate) fn deserialize_batch_results<R: DeserializeOwned>(results: Vec<Result<Value, CommunexError>>) -> Vec<Result<R, CommunexError>> {
+    results.into_iter()
+        .map(|result| {
+            serde_json::from_value(result?)
+                .map_err(|e| CommunexError::ParseError(format!("Failed to parse batch result: {}", e)))
+        })
+        .collect()
+}
+
+/// Deserialize the result of `method` into `T`. Errors name the method.
+pub(crate) fn parse_response<T: DeserializeOwned>(method: &str, value: Value) -> Result<T, CommunexError> {
+    parse_value(&format!("{} response", method), value)
+}
+
+/// Like `parse_response`, for the `field` member of the result. Errors also
+/// name the field.
+pub(crate) fn parse_response_field<T: DeserializeOwned>(method: &str, value: &Value, field: &str) -> Result<T, CommunexError> {
+    parse_value_field(&format!("{} response", method), value, field)
+}
+
+/// Deserialize `value` into `T`, describing it as `subject` in errors, e.g.
+/// "query_modules response for subnet 3"
+pub(crate) fn parse_value<T: DeserializeOwned>(subject: &str, value: Value) -> Result<T, CommunexError> {
+    serde_json::from_value(value)
+        .map_err(|e| CommunexError::ParseError(format!("Failed to parse {}: {}", subject, e)))
+}
+
+/// Like `parse_value`, for the `field` member of `value`
+pub(crate) fn parse_value_field<T: DeserializeOwned>(subject: &str, value: &Value, field: &str) -> Result<T, CommunexError> {
+    let member = value.get(field).ok_or_else(|| CommunexError::ParseError(
+        format!("{} is missing '{}'", subject, field)
+    ))?;
+    T::deserialize(member)
+        .map_err(|e| CommunexError::ParseError(format!("Failed to parse {} at '{}': {}", subject, field, e)))
+}
+
+/// Checks that a response belongs to the request with the given id.
+///
+/// Responses with a null or missing id are not checked: the JSON-RPC spec uses
+/// them when the server could not determine the request id, and the error they
+/// carry is surfaced by `handle_rpc_response`.
+fn verify_response_id(response: &Value, expected: u64) -> Result<(), CommunexError> {
+    match response.get("id") {
+        None | Some(Value::Null) => Ok(()),
+        Some(id) if id.as_u64() == Some(expected) => Ok(()),
+        Some(id) => Err(CommunexError::MalformedResponse(
+            format!("Response id {} does not match request id {}", id, expected)
+        )),
+    }
+}
+
+fn request_timeout(timeout: Duration) -> CommunexError {
+    CommunexError::RequestTimeout(format!("Request timed out after {} seconds", timeout.as_secs()))
 }
 
 impl RpcClient {
+    /// Send `params` to `path`, which is also the JSON-RPC method. The URL
+    /// follows the configured path style, appending the method by default;
+    /// retries and the timeout are the same as for `request`.
     pub async fn request_with_path(&self, path: &str, params: serde_json::Value) -> Result<serde_json::Value, CommunexError> {
+        let url = self.request_url(path, PathStyle::PerMethod);
+        self.call(&url, path, params, self.config.timeout).await
+    }
+
+    /// The URL a request for `method` is posted to, using `default` when the
+    /// config does not set a path style
+    fn request_url(&self, method: &str, default: PathStyle) -> String {
+        match self.config.path_style.unwrap_or(default) {
+            PathStyle::SingleEndpoint => self.url.clone(),
+            PathStyle::PerMethod if self.url.ends_with('/') => format!("{}{}", self.url, method),
+            PathStyle::PerMethod => format!("{}/{}", self.url, method),
+        }
+    }
+
+    /// Post an already built request to `path` once and return the raw
+    /// response body
+    pub async fn send_request(&self, path: &str, request: &serde_json::Value) -> Result<serde_json::Value, CommunexError> {
+        let response = self.post(&self.request_url(path, PathStyle::PerMethod), request, self.config.timeout).await?;
+        self.read_json(response).await
+    }
+
+    async fn post(&self, url: &str, request: &Value, timeout: Duration) -> Result<reqwest::Response, CommunexError> {
+        let builder = self.client.post(url).json(request);
+        // Overrides the client's own timeout; reqwest has none on wasm32
+        #[cfg(not(target_arch = "wasm32"))]
+        let builder = builder.timeout(timeout);
+
+        runtime::timeout(self.config.runtime.as_ref(), timeout, builder.send()).await
+        .map_err(|_| request_timeout(timeout))?
+        .map_err(|e| if e.is_timeout() { request_timeout(timeout) } else { e.into() })
+    }
+
+    /// Send `method` to `url` once: a single attempt of `call`
+    async fn call_once(&self, url: &str, method: &str, params: &Value, timeout: Duration) -> Result<Value, CommunexError> {
+        let id = next_request_id();
         let request = json!({
             "jsonrpc": "2.0",
-            "id": 1,
-            "method": path,
-            "params": params
+            "method": method,
+            "params": params,
+            "id": id
         });
 
-        let response = self.send_request(path, &request).await?;
-        
-        if let Some(error) = response.get("error") {
-            let code = error.get("code")
-                .and_then(|c| c.as_i64())
-                .unwrap_or(-32000);
-            let message = error.get("message")
-                .and_then(|m| m.as_str())
-                .unwrap_or("Unknown error")
-                .to_string();
-            
-            return Err(CommunexError::RpcError { code: code as i32, message });
+        let response = self.post(url, &request, timeout).await?;
+        if !response.status().is_success() {
+            return Err(self.read_http_error(response).await);
         }
-        
-        Ok(response.get("result").cloned().unwrap_or(json!({})))
+
+        let value: Value = self.read_json(response).await?;
+        self.verify_jsonrpc_version(&value)?;
+        verify_response_id(&value, id)?;
+        self.handle_rpc_response(value).await
     }
 
-    pub async fn send_request(&self, path: &str, request: &serde_json::Value) -> Result<serde_json::Value, CommunexError> {
-        let url = if self.url.ends_with('/') {
-            format!("{}{}", self.url, path)
-        } else {
-            format!("{}/{}", self.url, path)
-        };
-
-        match self.client.post(&url)
-            .json(request)
-            .timeout(Duration::from_secs(5))
-            .send()
-            .await {
-                Ok(response) => {
-                    response.json().await.map_err(|e| {
-                        CommunexError::MalformedResponse(e.to_string())
-                    })
-                },
-                Err(e) => Err(CommunexError::ConnectionError(e.to_string()))
-            }
+    /// The code path behind every single request: send `method` to `url`
+    /// with `timeout` per attempt, retrying retryable failures up to
+    /// `max_retries` times. A reply with neither `result` nor `error`
+    /// fails with `ParseError` rather than reading as an empty result.
+    async fn call(&self, url: &str, method: &str, params: Value, timeout: Duration) -> Result<Value, CommunexError> {
+        self.execute_with_retry(|| self.call_once(url, method, &params, timeout)).await
     }
 
+    /// Like `request`, but with `timeout` per attempt instead of the
+    /// configured one
     pub async fn request_with_timeout(
         &self, 
         method: &str, 
         params: Value, 
         timeout: Duration
     ) -> Result<Value, CommunexError> {
-        let request = json!({
-            "jsonrpc": "2.0",
-            "method": method,
-            "params": params,
-            "id": 1
-        });
-
-        let client = Client::builder()
-            .timeout(timeout)
-            .build()?;
-
-        let response = client
-            .post(&self.url)
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| {
-                if e.is_timeout() {
-                    CommunexError::RequestTimeout(format!(
-                        "Request timed out after {} seconds", 
-                        timeout.as_secs()
-                    ))
-                } else {
-                    e.into()
-                }
-            })?;
-
-        let value = response.json::<Value>().await?;
-        self.handle_rpc_response(value).await
+        let url = self.request_url(method, PathStyle::SingleEndpoint);
+        self.call(&url, method, params, timeout).await
     }
 
+    /// Send `method` with `params` and return its `result`. Posts to the
+    /// base URL unless the config sets a path style.
     pub async fn request(&self, method: &str, params: Value) -> Result<Value, CommunexError> {
-        let request = json!({
-            "jsonrpc": "2.0",
-            "method": method,
-            "params": params,
-            "id": 1
-        });
+        let url = self.request_url(method, PathStyle::SingleEndpoint);
+        self.call(&url, method, params, self.config.timeout).await
+    }
 
-        let client = Client::new();
-        
-        // Use tokio's timeout
-        let response = tokio_timeout(
-            self.config.timeout,
-            client
-                .post(&self.url)
-                .json(&request)
-                .send()
-        ).await
-        .map_err(|_| CommunexError::RequestTimeout(
-            format!("Request timed out after {} seconds", self.config.timeout.as_secs())
-        ))??;
+    /// Like `request`, but never retried. For requests that change node
+    /// state: one that timed out may still have been applied, and sending it
+    /// again could apply it twice.
+    pub async fn submit(&self, method: &str, params: Value) -> Result<Value, CommunexError> {
+        self.request_once(method, &params).await
+    }
 
-        if !response.status().is_success() {
-            return Err(CommunexError::RpcError {
-                code: response.status().as_u16() as i32,
-                message: format!("HTTP error: {}", response.status()),
-            });
-        }
+    /// Like `request_with_path`, but never retried; see `submit`
+    pub async fn submit_with_path(&self, path: &str, params: Value) -> Result<Value, CommunexError> {
+        let url = self.request_url(path, PathStyle::PerMethod);
+        self.call_once(&url, path, &params, self.config.timeout).await
+    }
 
-        let value = response.json::<Value>().await?;
-        self.handle_rpc_response(value).await
+    /// A single attempt of `request`, for callers running their own retries
+    pub(crate) async fn request_once(&self, method: &str, params: &Value) -> Result<Value, CommunexError> {
+        let url = self.request_url(method, PathStyle::SingleEndpoint);
+        self.call_once(&url, method, params, self.config.timeout).await
+    }
+
+    /// Like `request`, but serializes `params` and deserializes the result into `R`
+    pub async fn request_typed<P: Serialize, R: DeserializeOwned>(&self, method: &str, params: P) -> Result<R, CommunexError> {
+        let result = self.request(method, params::to_params(&params)?).await?;
+        serde_json::from_value(result)
+            .map_err(|e| CommunexError::ParseError(format!("Failed to parse {} result: {}", method, e)))
     }
 }
 
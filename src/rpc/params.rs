@@ -0,0 +1,208 @@
+//! Typed request parameters for the node RPC methods. Serializing these
+//! instead of building `json!` objects by hand keeps field names checked at
+//! compile time.
+
+use serde::Serialize;
+use serde_json::Value;
+use crate::error::CommunexError;
+use crate::types::Netuid;
+use crate::wallet::TransferRequest;
+
+/// Parameters of methods that take a single account address, such as the
+/// balance, nonce, history and staking queries
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressParams<'a> {
+    pub address: &'a str,
+}
+
+/// Parameters of queries for an account's state as of a past block
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressAtBlockParams<'a> {
+    pub address: &'a str,
+    pub block: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TxHashParams<'a> {
+    pub hash: &'a str,
+}
+
+/// Parameters of `chain/block`; no number selects the latest block
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetuidParams {
+    pub netuid: Netuid,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferParams<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    /// Sent as a decimal string
+    pub amount: String,
+    pub denom: &'a str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchTransferParams<'a> {
+    pub transfers: &'a [TransferRequest],
+}
+
+/// One recipient of a `transfer_multiple` call
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferOutput<'a> {
+    pub to: &'a str,
+    pub amount: u64,
+}
+
+/// Parameters of `transfer_multiple`: one sender paying several recipients
+/// in a single transaction
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferMultipleParams<'a> {
+    pub from: &'a str,
+    pub outputs: Vec<TransferOutput<'a>>,
+    pub denom: &'a str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StakeParams<'a> {
+    pub from: &'a str,
+    pub amount: u64,
+    pub denom: &'a str,
+}
+
+/// Unstake parameters; no amount is sent as `null`
+#[derive(Debug, Clone, Serialize)]
+pub struct UnstakeParams<'a> {
+    pub from: &'a str,
+    pub amount: Option<u64>,
+    pub denom: &'a str,
+}
+
+/// Parameters of `staking/claim`. The node applies at most one claim per
+/// idempotency key within its dedup window.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClaimParams<'a> {
+    pub address: &'a str,
+    pub idempotency_key: &'a str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisterModuleParams<'a> {
+    pub from: &'a str,
+    pub netuid: Netuid,
+    pub name: &'a str,
+    pub address: &'a str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SetWeightsParams<'a> {
+    pub from: &'a str,
+    pub netuid: Netuid,
+    pub uids: &'a [u16],
+    pub weights: &'a [u16],
+}
+
+/// Serialize request parameters into the JSON value sent to the node
+pub fn to_params<P: Serialize>(params: &P) -> Result<Value, CommunexError> {
+    serde_json::to_value(params)
+        .map_err(|e| CommunexError::ParseError(format!("Failed to serialize params: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_params_match_previous_json() {
+        assert_eq!(
+            to_params(&AddressParams { address: "cmx1abcd123" }).unwrap(),
+            json!({ "address": "cmx1abcd123" })
+        );
+        assert_eq!(
+            to_params(&TxHashParams { hash: "0x123" }).unwrap(),
+            json!({ "hash": "0x123" })
+        );
+        assert_eq!(
+            to_params(&ClaimParams { address: "cmx1abcd123", idempotency_key: "key" }).unwrap(),
+            json!({ "address": "cmx1abcd123", "idempotency_key": "key" })
+        );
+        assert_eq!(to_params(&BlockParams { number: None }).unwrap(), json!({}));
+        assert_eq!(
+            to_params(&BlockParams { number: Some(12345) }).unwrap(),
+            json!({ "number": 12345 })
+        );
+        assert_eq!(
+            to_params(&NetuidParams { netuid: Netuid::new(1).unwrap() }).unwrap(),
+            json!({ "netuid": 1 })
+        );
+    }
+
+    #[test]
+    fn test_transaction_params_match_previous_json() {
+        assert_eq!(
+            to_params(&TransferParams {
+                from: "cmx1abcd123",
+                to: "cmx1efgh456",
+                amount: 1000.to_string(),
+                denom: "COMAI",
+            }).unwrap(),
+            json!({ "from": "cmx1abcd123", "to": "cmx1efgh456", "amount": "1000", "denom": "COMAI" })
+        );
+
+        let transfers = [TransferRequest {
+            from: "cmx1abcd123".into(),
+            to: "cmx1efgh456".into(),
+            amount: 100,
+            denom: "COMAI".into(),
+        }];
+        assert_eq!(
+            to_params(&BatchTransferParams { transfers: &transfers }).unwrap(),
+            json!({ "transfers": [{ "from": "cmx1abcd123", "to": "cmx1efgh456", "amount": 100, "denom": "COMAI" }] })
+        );
+
+        assert_eq!(
+            to_params(&TransferMultipleParams {
+                from: "cmx1abcd123",
+                outputs: vec![TransferOutput { to: "cmx1efgh456", amount: 100 }],
+                denom: "COMAI",
+            }).unwrap(),
+            json!({ "from": "cmx1abcd123", "outputs": [{ "to": "cmx1efgh456", "amount": 100 }], "denom": "COMAI" })
+        );
+
+        assert_eq!(
+            to_params(&StakeParams { from: "cmx1abcd123", amount: 500, denom: "COMAI" }).unwrap(),
+            json!({ "from": "cmx1abcd123", "amount": 500, "denom": "COMAI" })
+        );
+        assert_eq!(
+            to_params(&UnstakeParams { from: "cmx1abcd123", amount: None, denom: "COMAI" }).unwrap(),
+            json!({ "from": "cmx1abcd123", "amount": null, "denom": "COMAI" })
+        );
+
+        assert_eq!(
+            to_params(&RegisterModuleParams {
+                from: "cmx1abcd123",
+                netuid: Netuid::new(2).unwrap(),
+                name: "module",
+                address: "127.0.0.1:8000",
+            }).unwrap(),
+            json!({ "from": "cmx1abcd123", "netuid": 2, "name": "module", "address": "127.0.0.1:8000" })
+        );
+
+        assert_eq!(
+            to_params(&SetWeightsParams {
+                from: "cmx1abcd123",
+                netuid: Netuid::new(2).unwrap(),
+                uids: &[0, 1],
+                weights: &[100, 200],
+            }).unwrap(),
+            json!({ "from": "cmx1abcd123", "netuid": 2, "uids": [0, 1], "weights": [100, 200] })
+        );
+    }
+}
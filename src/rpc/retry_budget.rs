@@ -0,0 +1,110 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::runtime::Instant;
+
+/// Limits on how many retries a client may send, relative to the requests it
+/// sends, within a sliding time window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryBudgetConfig {
+    /// Retries allowed per request made in the window, e.g. `0.1` for 10%
+    pub ratio: f64,
+    /// How far back requests and retries are counted
+    pub window: Duration,
+    /// Retries always allowed per window, so a quiet client can still retry
+    pub min_retries: u32,
+}
+
+impl Default for RetryBudgetConfig {
+    fn default() -> Self {
+        Self {
+            ratio: 0.1,
+            window: Duration::from_secs(10),
+            min_retries: 10,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Window {
+    requests: VecDeque<Instant>,
+    retries: VecDeque<Instant>,
+}
+
+impl Window {
+    fn prune(&mut self, window: Duration) {
+        for events in [&mut self.requests, &mut self.retries] {
+            while events.front().is_some_and(|at| at.elapsed() > window) {
+                events.pop_front();
+            }
+        }
+    }
+}
+
+/// Requests and retries made in the current window, shared between client clones
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RetryBudget {
+    window: Arc<Mutex<Window>>,
+}
+
+impl RetryBudget {
+    pub(crate) fn record_request(&self, config: &RetryBudgetConfig) {
+        let mut window = self.window.lock().expect("retry budget lock poisoned");
+        window.prune(config.window);
+        window.requests.push_back(Instant::now());
+    }
+
+    /// Spend one retry if the budget has room for it
+    pub(crate) fn try_retry(&self, config: &RetryBudgetConfig) -> bool {
+        let mut window = self.window.lock().expect("retry budget lock poisoned");
+        window.prune(config.window);
+
+        let allowed = (window.requests.len() as f64 * config.ratio) as usize;
+        if window.retries.len() >= allowed.max(config.min_retries as usize) {
+            return false;
+        }
+        window.retries.push_back(Instant::now());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retries_limited_to_ratio_of_requests() {
+        let budget = RetryBudget::default();
+        let config = RetryBudgetConfig {
+            ratio: 0.5,
+            window: Duration::from_secs(60),
+            min_retries: 1,
+        };
+
+        // The minimum applies before any requests have been made
+        assert!(budget.try_retry(&config));
+        assert!(!budget.try_retry(&config));
+
+        for _ in 0..6 {
+            budget.record_request(&config);
+        }
+        assert!(budget.try_retry(&config));
+        assert!(budget.try_retry(&config));
+        assert!(!budget.try_retry(&config));
+    }
+
+    #[test]
+    fn test_budget_refills_after_window() {
+        let budget = RetryBudget::default();
+        let config = RetryBudgetConfig {
+            ratio: 0.0,
+            window: Duration::from_millis(20),
+            min_retries: 1,
+        };
+
+        assert!(budget.try_retry(&config));
+        assert!(!budget.try_retry(&config));
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(budget.try_retry(&config));
+    }
+}
@@ -1,16 +1,64 @@
-use crate::error::CommunexError;
-use super::{BatchRequest, BatchResponse, RpcClientConfig, RpcErrorDetail};
+use crate::error::{CommunexError, ErrorCause};
+use super::retry_budget::RetryBudget;
+use super::{BatchRequest, BatchResponse, RpcClientConfig, RpcErrorDetail, MAX_BATCH_REQUESTS, deserialize_batch_results};
+use serde::de::DeserializeOwned;
 use reqwest;
 use serde_json::{json, Value};
 use std::time::Duration;
+use crate::runtime::Instant;
 use log::debug;
-use futures::Future;
+use crate::runtime;
+use futures::{future, Future};
+
+/// Longest part of a non-JSON body quoted in errors
+const BODY_SNIPPET_CHARS: usize = 200;
+
+fn body_snippet(body: &[u8]) -> String {
+    let text = String::from_utf8_lossy(body);
+    let text = text.trim();
+    match text.char_indices().nth(BODY_SNIPPET_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+fn content_type(response: &reqwest::Response) -> String {
+    response.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("no content type")
+        .to_string()
+}
+
+fn non_json_message(status: reqwest::StatusCode, content_type: &str, body: &[u8]) -> String {
+    format!("HTTP {} returned a non-JSON body ({}): {}", status, content_type, body_snippet(body))
+}
+
+/// The `RpcError` for a JSON-RPC `error` object
+fn rpc_error(error: &Value) -> CommunexError {
+    let code = error.get("code")
+        .and_then(|c| c.as_i64())
+        .map(|c| c as i32)
+        .unwrap_or(-32603);
+    let message = error.get("message")
+        .and_then(|m| m.as_str())
+        .unwrap_or("Unknown error")
+        .to_string();
+    CommunexError::RpcError { code, message }
+}
 
 #[derive(Debug, Clone)]
 pub struct RpcClient {
     pub url: String,
     pub client: reqwest::Client,
     pub config: RpcClientConfig,
+    retry_budget: RetryBudget,
+}
+
+impl Default for RpcClient {
+    fn default() -> Self {
+        Self::new(super::DEFAULT_RPC_URL)
+    }
 }
 
 impl RpcClient {
@@ -19,47 +67,135 @@ impl RpcClient {
             url: url.into(),
             client: reqwest::Client::new(),
             config: RpcClientConfig::default(),
+            retry_budget: RetryBudget::default(),
         }
     }
 
     pub fn with_timeout(url: impl Into<String>, timeout: Duration) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(timeout)
-            .build()
-            .unwrap_or_default();
+        let builder = reqwest::Client::builder();
+        // reqwest has no client timeout on wasm32; `post` bounds each request
+        // with the runtime instead
+        #[cfg(not(target_arch = "wasm32"))]
+        let builder = builder.timeout(timeout);
+        let client = builder.build().unwrap_or_default();
 
         Self {
             url: url.into(),
             client,
-            config: RpcClientConfig::default(),
+            config: RpcClientConfig { timeout, ..RpcClientConfig::default() },
+            retry_budget: RetryBudget::default(),
         }
     }
 
     pub fn new_with_config(url: impl Into<String>, config: RpcClientConfig) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(config.timeout)
-            .build()
-            .unwrap_or_default();
+        let builder = reqwest::Client::builder();
+        // reqwest has no client timeout on wasm32; `post` bounds each request
+        // with the runtime instead
+        #[cfg(not(target_arch = "wasm32"))]
+        let builder = builder.timeout(config.timeout);
+        let client = builder.build().unwrap_or_default();
 
         Self {
             url: url.into(),
             client,
             config,
+            retry_budget: RetryBudget::default(),
+        }
+    }
+
+    /// Checks the response envelope declares JSON-RPC 2.0, unless the client
+    /// is configured to be lenient.
+    pub(crate) fn verify_jsonrpc_version(&self, response: &Value) -> Result<(), CommunexError> {
+        if !self.config.strict_jsonrpc {
+            return Ok(());
         }
+
+        match response.get("jsonrpc").and_then(|v| v.as_str()) {
+            Some("2.0") => Ok(()),
+            Some(version) => Err(CommunexError::MalformedResponse(
+                format!("Unsupported jsonrpc version: {}", version)
+            )),
+            None => Err(CommunexError::MalformedResponse(
+                "Missing jsonrpc version field".to_string()
+            )),
+        }
+    }
+
+    /// Read a response body, giving up with `MalformedResponse` as soon as it
+    /// exceeds `max_response_bytes`
+    async fn read_body(&self, response: reqwest::Response) -> Result<Vec<u8>, CommunexError> {
+        let limit = self.config.max_response_bytes;
+        let too_large = || CommunexError::MalformedResponse(
+            format!("Response body exceeds {} bytes", limit)
+        );
+        if response.content_length().is_some_and(|len| len > limit as u64) {
+            return Err(too_large());
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let body = {
+            let mut response = response;
+            let mut body = Vec::new();
+            while let Some(chunk) = response.chunk().await? {
+                if body.len() + chunk.len() > limit {
+                    return Err(too_large());
+                }
+                body.extend_from_slice(&chunk);
+            }
+            body
+        };
+        // The browser buffers the body before handing it over
+        #[cfg(target_arch = "wasm32")]
+        let body = {
+            let body = response.bytes().await?;
+            if body.len() > limit {
+                return Err(too_large());
+            }
+            body.to_vec()
+        };
+        Ok(body)
+    }
+
+    /// Read a response body as JSON, giving up with `MalformedResponse` as
+    /// soon as it exceeds `max_response_bytes`. A body that is not JSON, such
+    /// as a proxy's HTML error page, is reported with the HTTP status and the
+    /// start of the body.
+    pub(crate) async fn read_json<T: DeserializeOwned>(&self, response: reqwest::Response) -> Result<T, CommunexError> {
+        let status = response.status();
+        let content_type = content_type(&response);
+        let body = self.read_body(response).await?;
+
+        let Ok(value) = serde_json::from_slice::<Value>(&body) else {
+            return Err(CommunexError::MalformedResponse(non_json_message(status, &content_type, &body)));
+        };
+        serde_json::from_value(value).map_err(|e| CommunexError::MalformedResponse(e.to_string()))
+    }
+
+    /// The error for a single request answered with a non-2xx status. A
+    /// JSON-RPC error in the body keeps the node's code and message;
+    /// otherwise the HTTP status is the code, so 5xx and 429 stay retryable,
+    /// and a non-JSON body is quoted in the message.
+    pub(crate) async fn read_http_error(&self, response: reqwest::Response) -> CommunexError {
+        let status = response.status();
+        let content_type = content_type(&response);
+        let body = match self.read_body(response).await {
+            Ok(body) => body,
+            Err(e) => return e,
+        };
+
+        let message = match serde_json::from_slice::<Value>(&body) {
+            Ok(value) => match value.get("error") {
+                Some(error) => return rpc_error(error),
+                None => format!("HTTP error: {}", status),
+            },
+            Err(_) => non_json_message(status, &content_type, &body),
+        };
+        CommunexError::RpcError { code: status.as_u16() as i32, message }
     }
 
     pub async fn handle_rpc_response(&self, value: Value) -> Result<Value, CommunexError> {
         if let Some(error) = value.get("error") {
-            let code = error.get("code")
-                .and_then(|c| c.as_i64())
-                .map(|c| c as i32)
-                .unwrap_or(-32603);
-            let message = error.get("message")
-                .and_then(|m| m.as_str())
-                .unwrap_or("Unknown error")
-                .to_string();
-            
-            return Err(CommunexError::RpcError { code, message });
+            return Err(rpc_error(error));
         }
 
         value.get("result")
@@ -67,15 +203,41 @@ impl RpcClient {
             .ok_or_else(|| CommunexError::ParseError("Missing result field".to_string()))
     }
 
-    pub async fn batch_request(&self, batch: BatchRequest) -> Result<BatchResponse, CommunexError> {
+    /// Post a batch and return the raw responses ordered by request id, which
+    /// matches the order the requests were added. Batches over
+    /// `MAX_BATCH_REQUESTS` are sent as concurrent calls of that size.
+    async fn send_batch(&self, batch: &BatchRequest) -> Result<Vec<Value>, CommunexError> {
+        let calls = batch.requests
+            .chunks(MAX_BATCH_REQUESTS)
+            .map(|requests| self.post_batch(requests));
+        let mut response: Vec<Value> = future::try_join_all(calls).await?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        // Servers may answer batch entries in any order
+        response.sort_by_key(|resp| resp.get("id").and_then(|id| id.as_u64()).unwrap_or(u64::MAX));
+
+        for resp in &response {
+            self.verify_jsonrpc_version(resp)?;
+        }
+        Ok(response)
+    }
+
+    async fn post_batch(&self, requests: &[Value]) -> Result<Vec<Value>, CommunexError> {
         let response = self.client.post(&self.url)
-            .json(&batch.requests)
+            .json(requests)
             .send()
             .await
-            .map_err(|e| CommunexError::ConnectionError(e.to_string()))?
-            .json::<Vec<Value>>()
-            .await
-            .map_err(|e| CommunexError::ParseError(e.to_string()))?;
+            .map_err(|e| CommunexError::ConnectionError(ErrorCause::new(e)))?;
+        self.read_json(response).await
+    }
+
+    /// Send a batch and split the responses into successes and errors. Both
+    /// are ordered by request id, which matches the order the requests were added.
+    /// Batches over `MAX_BATCH_REQUESTS` are split across several calls.
+    pub async fn batch_request(&self, batch: BatchRequest) -> Result<BatchResponse, CommunexError> {
+        let response = self.send_batch(&batch).await?;
 
         let mut successes = Vec::new();
         let mut errors = Vec::new();
@@ -94,7 +256,9 @@ impl RpcClient {
                     .and_then(|id| id.as_u64())
                     .map(|id| id as u32);
                 
-                errors.push(RpcErrorDetail { code, message, request_id });
+                let data = error.get("data").filter(|data| !data.is_null()).cloned();
+
+                errors.push(RpcErrorDetail { code, message, request_id, data });
             } else if let Some(result) = resp.get("result") {
                 successes.push(result.clone());
             }
@@ -106,6 +270,37 @@ impl RpcClient {
         })
     }
 
+    /// Send a batch and return one entry per request, in request order: the
+    /// `result` of each success or an `RpcError` for each failure
+    pub async fn batch_request_results(&self, batch: BatchRequest) -> Result<Vec<Result<Value, CommunexError>>, CommunexError> {
+        let response = self.send_batch(&batch).await?;
+
+        Ok(response.into_iter()
+            .map(|resp| {
+                if let Some(error) = resp.get("error") {
+                    return Err(CommunexError::RpcError {
+                        code: error.get("code")
+                            .and_then(|c| c.as_i64())
+                            .map(|c| c as i32)
+                            .unwrap_or(-32603),
+                        message: error.get("message")
+                            .and_then(|m| m.as_str())
+                            .unwrap_or("Unknown error")
+                            .to_string(),
+                    });
+                }
+                resp.get("result")
+                    .cloned()
+                    .ok_or_else(|| CommunexError::ParseError("Missing result field in batch response".to_string()))
+            })
+            .collect())
+    }
+
+    /// Like `batch_request_results`, deserializing each success into `R`
+    pub async fn batch_request_typed<R: DeserializeOwned>(&self, batch: BatchRequest) -> Result<Vec<Result<R, CommunexError>>, CommunexError> {
+        Ok(deserialize_batch_results(self.batch_request_results(batch).await?))
+    }
+
     pub async fn batch_balance_request(&self, addresses: &[&str]) -> Result<BatchResponse, CommunexError> {
         let mut batch = BatchRequest::new();
         
@@ -124,6 +319,7 @@ impl RpcClient {
     pub async fn handle_batch_response(&self, responses: Vec<Value>) -> Result<Vec<Value>, CommunexError> {
         let mut results = Vec::new();
         for response in responses {
+            self.verify_jsonrpc_version(&response)?;
             if let Some(error) = response.get("error") {
                 let code = error.get("code")
                     .and_then(|c| c.as_i64())
@@ -166,12 +362,9 @@ impl RpcClient {
             .json(&requests)
             .send()
             .await
-            .map_err(|e| CommunexError::ConnectionError(e.to_string()))?;
+            .map_err(|e| CommunexError::ConnectionError(ErrorCause::new(e)))?;
 
-        let response_body: Value = response
-            .json()
-            .await
-            .map_err(|e| CommunexError::ParseError(e.to_string()))?;
+        let response_body: Value = self.read_json(response).await?;
 
         let responses = response_body.as_array()
             .ok_or_else(|| CommunexError::ParseError("Expected array response for batch request".to_string()))?;
@@ -179,30 +372,79 @@ impl RpcClient {
         self.handle_batch_response(responses.to_vec()).await
     }
 
+    fn record_request(&self) {
+        if let Some(config) = &self.config.retry_budget {
+            self.retry_budget.record_request(config);
+        }
+    }
+
+    /// Take a retry from the budget, or refuse once it is spent
+    fn try_retry(&self) -> bool {
+        match &self.config.retry_budget {
+            Some(config) if !self.retry_budget.try_retry(config) => {
+                debug!("Retry budget exhausted, not retrying");
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Run `f`, retrying it up to `max_retries` times with exponential
+    /// backoff; it always runs at least once. Errors that are not
+    /// `CommunexError::is_retryable` are returned immediately, as is the last
+    /// error once the retry budget, if any, is spent.
     pub async fn execute_with_retry<T, F, Fut>(&self, f: F) -> Result<T, CommunexError>
     where
         F: Fn() -> Fut,
         Fut: Future<Output = Result<T, CommunexError>>,
     {
-        let mut attempts = 0;
-        let mut last_error = None;
+        let mut retries = 0;
+        self.record_request();
 
-        while attempts < self.config.max_retries {
+        loop {
             match f().await {
                 Ok(result) => return Ok(result),
+                Err(e) if !e.is_retryable() => return Err(e),
+                Err(e) if retries >= self.config.max_retries || !self.try_retry() => return Err(e),
                 Err(e) => {
-                    attempts += 1;
-                    last_error = Some(e);
-                    if attempts < self.config.max_retries {
-                        debug!("Request failed, retrying ({}/{})", attempts, self.config.max_retries);
-                        tokio::time::sleep(Duration::from_millis(100 * 2u64.pow(attempts))).await;
-                    }
+                    retries += 1;
+                    debug!("Request failed, retrying ({}/{}): {}", retries, self.config.max_retries, e);
+                    self.config.runtime.sleep(Duration::from_millis(100 * 2u64.pow(retries))).await;
                 }
             }
         }
+    }
 
-        Err(last_error.unwrap_or_else(|| CommunexError::ConnectionError(
-            "Maximum retries exceeded".to_string()
-        )))
+    /// Like `execute_with_retry` around `request`, but with an overall
+    /// `deadline` across all attempts and backoff. Once it passes, the
+    /// in-flight attempt is abandoned and `RequestTimeout` is returned even
+    /// if retries remain.
+    pub async fn request_with_deadline(&self, method: &str, params: Value, deadline: Instant) -> Result<Value, CommunexError> {
+        let deadline_exceeded = |attempts: u32| CommunexError::RequestTimeout(
+            format!("Deadline for {} passed after {} attempt(s)", method, attempts)
+        );
+        let mut attempts = 0;
+        self.record_request();
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(deadline_exceeded(attempts));
+            }
+
+            let attempt = runtime::timeout(self.config.runtime.as_ref(), remaining, self.request_once(method, &params)).await;
+            attempts += 1;
+
+            match attempt {
+                Ok(Ok(result)) => return Ok(result),
+                Ok(Err(e)) if !e.is_retryable() || attempts > self.config.max_retries || !self.try_retry() => return Err(e),
+                Ok(Err(e)) => debug!("Request failed, retrying before deadline ({}): {}", attempts, e),
+                Err(_) => return Err(deadline_exceeded(attempts)),
+            }
+
+            let backoff = Duration::from_millis(100 * 2u64.pow(attempts));
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            self.config.runtime.sleep(backoff.min(remaining)).await;
+        }
     }
 }
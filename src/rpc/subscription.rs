@@ -0,0 +1,112 @@
+use futures::{SinkExt, Stream, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::tungstenite::Message;
+use crate::error::{CommunexError, ErrorCause};
+use crate::types::next_request_id;
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// JSON-RPC client for subscriptions served over a WebSocket
+#[derive(Debug, Clone)]
+pub struct SubscriptionClient {
+    url: String,
+}
+
+impl SubscriptionClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Open a connection, call `method` with `params` and stream the `result`
+    /// of every notification sent for the returned subscription id. The
+    /// stream ends when the server closes the connection.
+    pub async fn subscribe(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<impl Stream<Item = Result<Value, CommunexError>>, CommunexError> {
+        let (mut ws, _) = connect_async(self.url.as_str())
+            .await
+            .map_err(|e| CommunexError::ConnectionError(ErrorCause::with_message(format!("WebSocket connect failed: {}", e), e)))?;
+
+        let id = next_request_id();
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": id
+        });
+        ws.send(Message::Text(request.to_string()))
+            .await
+            .map_err(|e| CommunexError::ConnectionError(ErrorCause::with_message(format!("WebSocket send failed: {}", e), e)))?;
+
+        let subscription = Self::await_subscription_id(&mut ws, id).await?;
+
+        Ok(futures::stream::unfold(Some(ws), move |state| {
+            let subscription = subscription.clone();
+            async move {
+                let mut ws = state?;
+                loop {
+                    match ws.next().await? {
+                        Ok(Message::Text(text)) => {
+                            let value: Value = match serde_json::from_str(&text) {
+                                Ok(value) => value,
+                                Err(e) => {
+                                    let err = CommunexError::ParseError(format!("Invalid notification: {}", e));
+                                    return Some((Err(err), Some(ws)));
+                                }
+                            };
+                            if value["params"]["subscription"] != subscription {
+                                continue;
+                            }
+                            return Some((Ok(value["params"]["result"].clone()), Some(ws)));
+                        }
+                        Ok(Message::Close(_)) => return None,
+                        Ok(_) => continue,
+                        Err(e) => {
+                            let err = CommunexError::ConnectionError(ErrorCause::with_message(format!("WebSocket error: {}", e), e));
+                            return Some((Err(err), None));
+                        }
+                    }
+                }
+            }
+        }))
+    }
+
+    async fn await_subscription_id(ws: &mut WsStream, id: u64) -> Result<Value, CommunexError> {
+        while let Some(message) = ws.next().await {
+            let message = message
+                .map_err(|e| CommunexError::ConnectionError(ErrorCause::with_message(format!("WebSocket error: {}", e), e)))?;
+            let text = match message {
+                Message::Text(text) => text,
+                Message::Close(_) => break,
+                _ => continue,
+            };
+
+            let response: Value = serde_json::from_str(&text)
+                .map_err(|e| CommunexError::ParseError(format!("Invalid subscription response: {}", e)))?;
+            if response["id"].as_u64() != Some(id) {
+                continue;
+            }
+
+            if let Some(error) = response.get("error") {
+                return Err(CommunexError::RpcError {
+                    code: error["code"].as_i64().unwrap_or(-32000) as i32,
+                    message: error["message"].as_str().unwrap_or("Unknown error").to_string(),
+                });
+            }
+
+            return response.get("result")
+                .cloned()
+                .ok_or_else(|| CommunexError::MalformedResponse("Missing subscription id".into()));
+        }
+
+        Err(CommunexError::ConnectionError("WebSocket closed before the subscription was confirmed".into()))
+    }
+}
@@ -0,0 +1,387 @@
+use futures::channel::{mpsc, oneshot};
+use futures::{SinkExt, Stream, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::tungstenite::Message;
+use crate::error::CommunexError;
+use crate::runtime::{self, Runtime};
+use crate::types::next_request_id;
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type Updates = mpsc::UnboundedSender<Result<Value, CommunexError>>;
+
+const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+enum Command {
+    Subscribe {
+        method: String,
+        params: Value,
+        updates: Updates,
+        confirmed: oneshot::Sender<Result<u64, CommunexError>>,
+    },
+    /// Sent when a subscription's stream is dropped
+    Unsubscribe(u64),
+    SubscriptionIds(oneshot::Sender<Vec<Value>>),
+}
+
+/// Runs any number of subscriptions over one WebSocket connection. When the
+/// connection drops it reconnects and subscribes everything again, so the
+/// streams it hands out keep yielding across disconnects.
+///
+/// The connection is driven by a background tokio task that lives as long as
+/// the manager or any of its streams. Dropping a stream unsubscribes it; the
+/// node's unsubscribe method is the subscribe method with `subscribe`
+/// replaced by `unsubscribe`, e.g. `balance_unsubscribe`.
+#[derive(Debug, Clone)]
+pub struct SubscriptionManager {
+    url: String,
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl SubscriptionManager {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::with_reconnect_delay(url, DEFAULT_RECONNECT_DELAY)
+    }
+
+    /// Create a manager that waits `delay` between reconnection attempts
+    pub fn with_reconnect_delay(url: impl Into<String>, delay: Duration) -> Self {
+        Self::with_runtime(url, delay, runtime::default_runtime())
+    }
+
+    /// Create a manager that sleeps between reconnection attempts on `runtime`
+    pub fn with_runtime(url: impl Into<String>, delay: Duration, runtime: Arc<dyn Runtime>) -> Self {
+        let url = url.into();
+        let (commands, receiver) = mpsc::unbounded();
+        tokio::spawn(Connection::new(url.clone(), delay, runtime).run(receiver));
+        Self { url, commands }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Subscribe with `method` and `params` and stream the `result` of each
+    /// notification. Fails if the first subscription attempt is rejected or
+    /// the node cannot be reached; after that, errors are yielded on the stream.
+    pub async fn subscribe(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<impl Stream<Item = Result<Value, CommunexError>>, CommunexError> {
+        let (updates, receiver) = mpsc::unbounded();
+        let (confirmed, confirmation) = oneshot::channel();
+
+        self.send(Command::Subscribe {
+            method: method.to_string(),
+            params,
+            updates,
+            confirmed,
+        })?;
+        let id = confirmation.await.map_err(|_| closed())??;
+
+        Ok(SubscriptionStream {
+            id,
+            receiver,
+            commands: self.commands.clone(),
+        })
+    }
+
+    /// The subscription ids the node assigned on the current connection
+    pub async fn subscription_ids(&self) -> Result<Vec<Value>, CommunexError> {
+        let (reply, ids) = oneshot::channel();
+        self.send(Command::SubscriptionIds(reply))?;
+        ids.await.map_err(|_| closed())
+    }
+
+    fn send(&self, command: Command) -> Result<(), CommunexError> {
+        self.commands.unbounded_send(command).map_err(|_| closed())
+    }
+}
+
+fn closed() -> CommunexError {
+    CommunexError::ConnectionError("Subscription manager has shut down".into())
+}
+
+/// Updates of one subscription, unsubscribed when dropped
+struct SubscriptionStream {
+    id: u64,
+    receiver: mpsc::UnboundedReceiver<Result<Value, CommunexError>>,
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl Stream for SubscriptionStream {
+    type Item = Result<Value, CommunexError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
+
+impl Drop for SubscriptionStream {
+    fn drop(&mut self) {
+        // The connection task may already be gone, in which case so is the subscription
+        let _ = self.commands.unbounded_send(Command::Unsubscribe(self.id));
+    }
+}
+
+/// The unsubscribe method matching `method`: `balance_subscribe` becomes
+/// `balance_unsubscribe` and `chain_subscribeNewHeads` becomes
+/// `chain_unsubscribeNewHeads`
+fn unsubscribe_method(method: &str) -> String {
+    match method.rfind("subscribe") {
+        Some(i) => format!("{}un{}", &method[..i], &method[i..]),
+        None => format!("{}_unsubscribe", method),
+    }
+}
+
+/// What a command needs sent to the node
+enum Outgoing {
+    Subscribe(u64),
+    Unsubscribe(Value),
+}
+
+struct Subscription {
+    method: String,
+    params: Value,
+    updates: Updates,
+    /// Present until the node first confirms the subscription
+    confirmed: Option<oneshot::Sender<Result<u64, CommunexError>>>,
+}
+
+struct Connection {
+    url: String,
+    reconnect_delay: Duration,
+    runtime: Arc<dyn Runtime>,
+    next_local_id: u64,
+    subscriptions: HashMap<u64, Subscription>,
+    /// Subscribe requests awaiting a response, by request id
+    pending: HashMap<u64, u64>,
+    /// Node subscription ids on the current connection, by their JSON text
+    active: HashMap<String, (Value, u64)>,
+    accepting_commands: bool,
+}
+
+impl Connection {
+    fn new(url: String, reconnect_delay: Duration, runtime: Arc<dyn Runtime>) -> Self {
+        Self {
+            url,
+            reconnect_delay,
+            runtime,
+            next_local_id: 0,
+            subscriptions: HashMap::new(),
+            pending: HashMap::new(),
+            active: HashMap::new(),
+            accepting_commands: true,
+        }
+    }
+
+    async fn run(mut self, mut commands: mpsc::UnboundedReceiver<Command>) {
+        loop {
+            self.subscriptions.retain(|_, subscription| !subscription.updates.is_closed());
+
+            // Nothing to connect for until the first subscription arrives
+            if self.subscriptions.is_empty() {
+                if !self.accepting_commands {
+                    return;
+                }
+                match commands.next().await {
+                    Some(command) => {
+                        self.add_command(command);
+                    }
+                    None => return,
+                }
+                continue;
+            }
+
+            let mut ws = match connect_async(self.url.as_str()).await {
+                Ok((ws, _)) => ws,
+                Err(e) => {
+                    warn!("Subscription connection to {} failed: {}", self.url, e);
+                    self.fail_unconfirmed(&format!("WebSocket connect failed: {}", e));
+                    self.runtime.sleep(self.reconnect_delay).await;
+                    continue;
+                }
+            };
+
+            let ids: Vec<u64> = self.subscriptions.keys().copied().collect();
+            for id in ids {
+                if self.send_subscribe(&mut ws, id).await.is_err() {
+                    break;
+                }
+            }
+
+            self.serve(&mut ws, &mut commands).await;
+
+            self.pending.clear();
+            self.active.clear();
+            if !self.subscriptions.is_empty() {
+                debug!("Subscription connection to {} lost, reconnecting", self.url);
+                self.runtime.sleep(self.reconnect_delay).await;
+            }
+        }
+    }
+
+    /// Handle commands and messages until the connection drops or every
+    /// subscriber has gone away
+    async fn serve(&mut self, ws: &mut WsStream, commands: &mut mpsc::UnboundedReceiver<Command>) {
+        loop {
+            if self.subscriptions.is_empty() && !self.accepting_commands {
+                let _ = ws.close(None).await;
+                return;
+            }
+
+            tokio::select! {
+                command = commands.next(), if self.accepting_commands => match command {
+                    Some(command) => {
+                        let sent = match self.add_command(command) {
+                            Some(Outgoing::Subscribe(id)) => self.send_subscribe(ws, id).await,
+                            Some(Outgoing::Unsubscribe(request)) => send_unsubscribe(ws, request).await,
+                            None => Ok(()),
+                        };
+                        if sent.is_err() {
+                            return;
+                        }
+                    }
+                    None => self.accepting_commands = false,
+                },
+                message = ws.next() => match message {
+                    Some(Ok(Message::Text(text))) => self.handle_message(&text),
+                    Some(Ok(Message::Close(_))) | None => return,
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
+                        warn!("Subscription connection error: {}", e);
+                        return;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Apply a command, returning what needs sending to the node
+    fn add_command(&mut self, command: Command) -> Option<Outgoing> {
+        match command {
+            Command::Subscribe { method, params, updates, confirmed } => {
+                let id = self.next_local_id;
+                self.next_local_id += 1;
+                self.subscriptions.insert(id, Subscription {
+                    method,
+                    params,
+                    updates,
+                    confirmed: Some(confirmed),
+                });
+                Some(Outgoing::Subscribe(id))
+            }
+            Command::Unsubscribe(id) => {
+                let subscription = self.subscriptions.remove(&id)?;
+                let key = self.active.iter()
+                    .find(|(_, (_, local_id))| *local_id == id)
+                    .map(|(key, _)| key.clone())?;
+                let (server_id, _) = self.active.remove(&key)?;
+                Some(Outgoing::Unsubscribe(json!({
+                    "jsonrpc": "2.0",
+                    "method": unsubscribe_method(&subscription.method),
+                    "params": [server_id],
+                    "id": next_request_id()
+                })))
+            }
+            Command::SubscriptionIds(reply) => {
+                let _ = reply.send(self.active.values().map(|(id, _)| id.clone()).collect());
+                None
+            }
+        }
+    }
+
+    async fn send_subscribe(&mut self, ws: &mut WsStream, id: u64) -> Result<(), ()> {
+        let Some(subscription) = self.subscriptions.get(&id) else {
+            return Ok(());
+        };
+
+        let request_id = next_request_id();
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": subscription.method,
+            "params": subscription.params,
+            "id": request_id
+        });
+        self.pending.insert(request_id, id);
+
+        ws.send(Message::Text(request.to_string())).await.map_err(|e| {
+            warn!("Failed to send subscription request: {}", e);
+        })
+    }
+
+    fn handle_message(&mut self, text: &str) {
+        let Ok(message) = serde_json::from_str::<Value>(text) else {
+            warn!("Ignoring invalid subscription message: {}", text);
+            return;
+        };
+
+        if let Some(local_id) = message["id"].as_u64().and_then(|id| self.pending.remove(&id)) {
+            self.handle_response(local_id, &message);
+            return;
+        }
+
+        let key = message["params"]["subscription"].to_string();
+        let Some((_, local_id)) = self.active.get(&key) else {
+            return;
+        };
+        // A dropped stream fails to take the update; its Unsubscribe command
+        // is already on the way
+        if let Some(subscription) = self.subscriptions.get(local_id) {
+            let _ = subscription.updates.unbounded_send(Ok(message["params"]["result"].clone()));
+        }
+    }
+
+    fn handle_response(&mut self, local_id: u64, response: &Value) {
+        let Some(subscription) = self.subscriptions.get_mut(&local_id) else {
+            return;
+        };
+
+        let error = match (response.get("error"), response.get("result")) {
+            (Some(error), _) => CommunexError::RpcError {
+                code: error["code"].as_i64().unwrap_or(-32000) as i32,
+                message: error["message"].as_str().unwrap_or("Unknown error").to_string(),
+            },
+            (None, Some(server_id)) => {
+                self.active.insert(server_id.to_string(), (server_id.clone(), local_id));
+                if let Some(confirmed) = subscription.confirmed.take() {
+                    let _ = confirmed.send(Ok(local_id));
+                }
+                return;
+            }
+            (None, None) => CommunexError::MalformedResponse(
+                format!("{} response is missing the subscription id", subscription.method)
+            ),
+        };
+
+        match subscription.confirmed.take() {
+            Some(confirmed) => { let _ = confirmed.send(Err(error)); }
+            None => { let _ = subscription.updates.unbounded_send(Err(error)); }
+        }
+        self.subscriptions.remove(&local_id);
+    }
+
+    /// Fail subscriptions that have never been confirmed; established ones
+    /// wait for the next reconnect
+    fn fail_unconfirmed(&mut self, reason: &str) {
+        self.subscriptions.retain(|_, subscription| match subscription.confirmed.take() {
+            Some(confirmed) => {
+                let _ = confirmed.send(Err(CommunexError::ConnectionError(reason.into())));
+                false
+            }
+            None => true,
+        });
+    }
+}
+
+async fn send_unsubscribe(ws: &mut WsStream, request: Value) -> Result<(), ()> {
+    ws.send(Message::Text(request.to_string())).await.map_err(|e| {
+        warn!("Failed to send unsubscribe request: {}", e);
+    })
+}
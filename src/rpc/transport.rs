@@ -0,0 +1,94 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use crate::error::CommunexError;
+use serde::de::DeserializeOwned;
+use super::{BatchRequest, BatchResponse, RpcClient, deserialize_batch_results};
+
+/// Transport used by higher-level clients to talk to a node.
+///
+/// `RpcClient` is the HTTP implementation; tests can supply their own to
+/// return canned responses without a server.
+#[async_trait]
+pub trait RpcTransport: std::fmt::Debug + Send + Sync {
+    /// Send a JSON-RPC request to the node's root endpoint
+    async fn request(&self, method: &str, params: Value) -> Result<Value, CommunexError>;
+
+    /// Send a batch of JSON-RPC requests in a single call
+    async fn batch_request(&self, batch: BatchRequest) -> Result<BatchResponse, CommunexError>;
+
+    /// Send a JSON-RPC request to the endpoint at `path`, using it as the method name
+    async fn request_with_path(&self, path: &str, params: Value) -> Result<Value, CommunexError>;
+
+    /// Send a request that changes node state, such as a transfer, in a
+    /// single attempt. A request that failed or timed out may still have
+    /// been applied, so retrying it could apply it twice. Transports that
+    /// never retry can keep the default, which calls `request`.
+    async fn submit(&self, method: &str, params: Value) -> Result<Value, CommunexError> {
+        self.request(method, params).await
+    }
+
+    /// `submit` to the endpoint at `path`, using it as the method name
+    async fn submit_with_path(&self, path: &str, params: Value) -> Result<Value, CommunexError> {
+        self.request_with_path(path, params).await
+    }
+
+    /// Send a batch and return one entry per request, in request order.
+    ///
+    /// The default implementation rebuilds the order from `batch_request`,
+    /// placing each error at its reported request id.
+    async fn batch_request_results(&self, batch: BatchRequest) -> Result<Vec<Result<Value, CommunexError>>, CommunexError> {
+        let total = batch.requests.len();
+        let response = self.batch_request(batch).await?;
+        let mut successes = response.successes.into_iter();
+        let mut errors = response.errors;
+
+        let mut results = Vec::with_capacity(total);
+        for id in 0..total {
+            match errors.iter().position(|e| e.request_id == Some(id as u32)) {
+                Some(index) => {
+                    let error = errors.remove(index);
+                    results.push(Err(CommunexError::RpcError { code: error.code, message: error.message }));
+                }
+                None => match successes.next() {
+                    Some(value) => results.push(Ok(value)),
+                    None => break,
+                },
+            }
+        }
+        Ok(results)
+    }
+}
+
+impl dyn RpcTransport {
+    /// Like `batch_request_results`, deserializing each success into `R`
+    pub async fn batch_request_typed<R: DeserializeOwned>(&self, batch: BatchRequest) -> Result<Vec<Result<R, CommunexError>>, CommunexError> {
+        Ok(deserialize_batch_results(self.batch_request_results(batch).await?))
+    }
+}
+
+#[async_trait]
+impl RpcTransport for RpcClient {
+    async fn request(&self, method: &str, params: Value) -> Result<Value, CommunexError> {
+        RpcClient::request(self, method, params).await
+    }
+
+    async fn batch_request(&self, batch: BatchRequest) -> Result<BatchResponse, CommunexError> {
+        RpcClient::batch_request(self, batch).await
+    }
+
+    async fn request_with_path(&self, path: &str, params: Value) -> Result<Value, CommunexError> {
+        RpcClient::request_with_path(self, path, params).await
+    }
+
+    async fn submit(&self, method: &str, params: Value) -> Result<Value, CommunexError> {
+        RpcClient::submit(self, method, params).await
+    }
+
+    async fn submit_with_path(&self, path: &str, params: Value) -> Result<Value, CommunexError> {
+        RpcClient::submit_with_path(self, path, params).await
+    }
+
+    async fn batch_request_results(&self, batch: BatchRequest) -> Result<Vec<Result<Value, CommunexError>>, CommunexError> {
+        RpcClient::batch_request_results(self, batch).await
+    }
+}
@@ -0,0 +1,91 @@
+//! Async runtime abstraction.
+//!
+//! Retry backoff, transaction polling and the cache refresh task sleep and
+//! spawn through a [`Runtime`] instead of calling tokio directly, so the
+//! clients can be driven by another executor. [`TokioRuntime`] is the default;
+//! with the `wasm` feature, [`WasmRuntime`] is the default on wasm32.
+
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use futures::future::{self, BoxFuture, Either};
+use crate::error::CommunexError;
+
+/// Monotonic clock for timeouts, retry windows and circuit cooldowns.
+/// `std::time::Instant` panics on wasm32, where `web_time` reads
+/// `performance.now()` instead.
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+pub use web_time::Instant;
+
+pub trait Runtime: Debug + Send + Sync {
+    /// A future that completes after `duration`
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+
+    /// Run `task` in the background
+    fn spawn(&self, task: BoxFuture<'static, ()>);
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioRuntime;
+
+impl Runtime for TokioRuntime {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        tokio::spawn(task);
+    }
+}
+
+/// Browser runtime: sleeps on `setTimeout` via gloo-timers and spawns onto
+/// the JS microtask queue. Only usable on wasm32.
+#[cfg(feature = "wasm")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WasmRuntime;
+
+#[cfg(feature = "wasm")]
+impl Runtime for WasmRuntime {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        // gloo's timer holds a JS closure and is not Send, so run it locally
+        // and hand back a channel that resolves when it fires
+        let (done, fired) = futures::channel::oneshot::channel();
+        let millis = u32::try_from(duration.as_millis()).unwrap_or(u32::MAX);
+        wasm_bindgen_futures::spawn_local(async move {
+            gloo_timers::future::TimeoutFuture::new(millis).await;
+            let _ = done.send(());
+        });
+        Box::pin(async move {
+            let _ = fired.await;
+        })
+    }
+
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        wasm_bindgen_futures::spawn_local(task);
+    }
+}
+
+/// The runtime used when none is configured
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+pub fn default_runtime() -> Arc<dyn Runtime> {
+    Arc::new(TokioRuntime)
+}
+
+/// The runtime used when none is configured
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub fn default_runtime() -> Arc<dyn Runtime> {
+    Arc::new(WasmRuntime)
+}
+
+/// Resolve `future`, or fail with `RequestTimeout` once `duration` elapses on `runtime`
+pub async fn timeout<F: Future>(runtime: &dyn Runtime, duration: Duration, future: F) -> Result<F::Output, CommunexError> {
+    match future::select(Box::pin(future), runtime.sleep(duration)).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right(_) => Err(CommunexError::RequestTimeout(
+            format!("Timed out after {:?}", duration)
+        )),
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use crate::rpc::RpcClient;
+use crate::wallet::TxStatus;
+
+#[derive(Debug, Default)]
+struct NodeState {
+    balances: HashMap<String, u64>,
+    staked: HashMap<String, u64>,
+    transactions: HashMap<String, TxStatus>,
+    overrides: HashMap<String, Value>,
+    next_tx: u64,
+}
+
+/// In-memory node serving the JSON-RPC surface used by `WalletClient` and
+/// `QueryMap` over a local HTTP listener.
+///
+/// Point an `RpcClient` (or `WalletClient`) at [`MockNode::url`] to drive it.
+/// Balances and transaction states can be seeded, and any method's response can
+/// be replaced with a canned result or error.
+#[derive(Debug)]
+pub struct MockNode {
+    addr: SocketAddr,
+    state: Arc<Mutex<NodeState>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockNode {
+    /// Start a node listening on an ephemeral localhost port
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind mock node listener");
+        let addr = listener.local_addr().expect("Failed to read mock node address");
+        let state = Arc::new(Mutex::new(NodeState::default()));
+
+        let server_state = state.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = server_state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve_connection(stream, state).await {
+                        debug!("Mock node connection error: {}", e);
+                    }
+                });
+            }
+        });
+
+        Self { addr, state, handle }
+    }
+
+    /// Base URL to pass to `RpcClient::new` or `WalletClient::new`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Create an `RpcClient` connected to this node
+    pub fn rpc_client(&self) -> RpcClient {
+        RpcClient::new(self.url())
+    }
+
+    /// Set the free balance of an address
+    pub fn seed_balance(&self, address: &str, amount: u64) {
+        self.lock().balances.insert(address.to_string(), amount);
+    }
+
+    /// Set the state reported for a transaction hash
+    pub fn seed_transaction_state(&self, hash: &str, state: TxStatus) {
+        self.lock().transactions.insert(hash.to_string(), state);
+    }
+
+    /// Always answer `method` with the given result
+    pub fn set_response(&self, method: &str, result: Value) {
+        self.lock().overrides.insert(method.to_string(), json!({ "result": result }));
+    }
+
+    /// Always answer `method` with the given JSON-RPC error
+    pub fn set_error(&self, method: &str, code: i32, message: &str) {
+        self.lock().overrides.insert(
+            method.to_string(),
+            json!({ "error": { "code": code, "message": message } }),
+        );
+    }
+
+    /// Current free balance of an address
+    pub fn balance(&self, address: &str) -> u64 {
+        self.lock().balances.get(address).copied().unwrap_or(0)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, NodeState> {
+        self.state.lock().expect("Mock node state poisoned")
+    }
+}
+
+impl Drop for MockNode {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn serve_connection(mut stream: TcpStream, state: Arc<Mutex<NodeState>>) -> std::io::Result<()> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    // Read headers
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let headers = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let content_length = headers.lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    while buffer.len() < header_end + content_length {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    let body = &buffer[header_end..buffer.len().min(header_end + content_length)];
+    let response = match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Array(requests)) => {
+            Value::Array(requests.iter().map(|r| handle_request(r, &state)).collect())
+        }
+        Ok(request) => handle_request(&request, &state),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": null,
+            "error": { "code": -32700, "message": format!("Parse error: {}", e) }
+        }),
+    };
+
+    let payload = response.to_string();
+    let reply = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        payload.len(),
+        payload
+    );
+    stream.write_all(reply.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+fn handle_request(request: &Value, state: &Arc<Mutex<NodeState>>) -> Value {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request.get("method").and_then(|m| m.as_str()).unwrap_or_default();
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    let mut state = state.lock().expect("Mock node state poisoned");
+    let mut response = match state.overrides.get(method) {
+        Some(canned) => canned.clone(),
+        None => match dispatch(method, &params, &mut state) {
+            Ok(result) => json!({ "result": result }),
+            Err((code, message)) => json!({ "error": { "code": code, "message": message } }),
+        },
+    };
+
+    response["jsonrpc"] = json!("2.0");
+    response["id"] = id;
+    response
+}
+
+fn dispatch(method: &str, params: &Value, state: &mut NodeState) -> Result<Value, (i32, String)> {
+    let str_param = |name: &str| {
+        params.get(name)
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .ok_or_else(|| (-32602, format!("Missing parameter: {}", name)))
+    };
+    // Amounts arrive as numbers or decimal strings depending on the call
+    let amount_param = |name: &str| {
+        params.get(name)
+            .and_then(|v| v.as_u64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+            .ok_or_else(|| (-32602, format!("Missing parameter: {}", name)))
+    };
+
+    match method {
+        "query_balance" => {
+            let address = str_param("address")?;
+            let amount = state.balances.get(&address).copied().unwrap_or(0);
+            Ok(json!({ "amount": amount.to_string(), "denom": "COMAI" }))
+        }
+        "balance/free" => {
+            let address = str_param("address")?;
+            Ok(json!({ "free": state.balances.get(&address).copied().unwrap_or(0) }))
+        }
+        "balance/all" => {
+            let address = str_param("address")?;
+            Ok(json!({
+                "free": state.balances.get(&address).copied().unwrap_or(0),
+                "reserved": 0,
+                "miscFrozen": 0,
+                "feeFrozen": 0
+            }))
+        }
+        "balance/staked" => {
+            let address = str_param("address")?;
+            Ok(json!({ "staked": state.staked.get(&address).copied().unwrap_or(0) }))
+        }
+        "transfer" => {
+            let from = str_param("from")?;
+            let to = str_param("to")?;
+            let amount = amount_param("amount")?;
+            debit(state, &from, amount)?;
+            *state.balances.entry(to).or_insert(0) += amount;
+            let hash = record_transaction(state);
+            Ok(json!({ "state": "success", "hash": hash }))
+        }
+        "staking/stake" => {
+            let from = str_param("from")?;
+            let amount = amount_param("amount")?;
+            debit(state, &from, amount)?;
+            *state.staked.entry(from).or_insert(0) += amount;
+            let hash = record_transaction(state);
+            Ok(json!({ "hash": hash }))
+        }
+        "transaction/state" => {
+            let hash = str_param("hash")?;
+            match state.transactions.get(&hash) {
+                Some(tx_state) => Ok(json!({
+                    "state": tx_state,
+                    "confirmations": 1,
+                    "block_num": 1,
+                    "timestamp": chrono::Utc::now().timestamp()
+                })),
+                None => Ok(json!({ "state": "notfound" })),
+            }
+        }
+        _ => Err((-32601, "Method not found".to_string())),
+    }
+}
+
+fn debit(state: &mut NodeState, address: &str, amount: u64) -> Result<(), (i32, String)> {
+    let balance = state.balances.entry(address.to_string()).or_insert(0);
+    if *balance < amount {
+        return Err((-32000, "insufficient funds".to_string()));
+    }
+    *balance -= amount;
+    Ok(())
+}
+
+fn record_transaction(state: &mut NodeState) -> String {
+    state.next_tx += 1;
+    let hash = format!("0x{:064x}", state.next_tx);
+    state.transactions.insert(hash.clone(), TxStatus::Success);
+    hash
+}
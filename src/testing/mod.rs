@@ -0,0 +1,4 @@
+// Test utilities for crates that depend on comx-api
+mod mock_node;
+
+pub use mock_node::MockNode;
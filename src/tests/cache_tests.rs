@@ -1,15 +1,17 @@
 use tokio::time::sleep;
 use std::time::Duration;
 use std::sync::Arc;
-use crate::cache::{QueryMapCache, CacheConfig, QueryResult};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use async_trait::async_trait;
+use crate::cache::{QueryMapCache, CacheBackend, CacheConfig, QueryResult, FileBackend, PersistedEntry};
+use crate::error::CommunexError;
+use crate::types::Balance;
+use serde_json::json;
 
 #[tokio::test]
 async fn test_cache_basic_operations() {
-    let config = CacheConfig {
-        ttl: Duration::from_secs(60),
-        refresh_interval: Duration::from_secs(300),
-        max_entries: 1000,
-    };
+    let config = CacheConfig::default();
     
     let cache = QueryMapCache::new(config);
     
@@ -27,8 +29,7 @@ async fn test_cache_basic_operations() {
 async fn test_cache_ttl_expiration() {
     let config = CacheConfig {
         ttl: Duration::from_secs(1),
-        refresh_interval: Duration::from_secs(300),
-        max_entries: 1000,
+        ..CacheConfig::default()
     };
     
     let cache = QueryMapCache::new(config);
@@ -47,9 +48,8 @@ async fn test_cache_ttl_expiration() {
 #[tokio::test]
 async fn test_cache_memory_limits() {
     let config = CacheConfig {
-        ttl: Duration::from_secs(60),
-        refresh_interval: Duration::from_secs(300),
         max_entries: 5,
+        ..CacheConfig::default()
     };
     
     let cache = QueryMapCache::new(config);
@@ -71,11 +71,7 @@ async fn test_cache_memory_limits() {
 
 #[tokio::test]
 async fn test_cache_metrics() {
-    let config = CacheConfig {
-        ttl: Duration::from_secs(60),
-        refresh_interval: Duration::from_secs(300),
-        max_entries: 1000,
-    };
+    let config = CacheConfig::default();
     
     let cache = QueryMapCache::new(config);
     
@@ -98,7 +94,7 @@ async fn test_background_refresh() {
     let config = CacheConfig {
         ttl: Duration::from_secs(1),
         refresh_interval: Duration::from_millis(100),
-        max_entries: 1000,
+        ..CacheConfig::default()
     };
     
     let cache = Arc::new(QueryMapCache::new(config));
@@ -144,4 +140,331 @@ async fn test_background_refresh() {
     let refreshed_data = refreshed_data.expect("Should have refreshed data");
     assert_eq!(refreshed_data.data, format!("refreshed_{}", query_key), 
         "Data should have been refreshed with new value");
-} 
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_file_backend_restores_unexpired_entries() {
+    let path = std::env::temp_dir().join(format!("comx_cache_test_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let config = CacheConfig::default();
+
+    let backend = Arc::new(FileBackend::new(&path));
+    let cache = QueryMapCache::with_backend(config.clone(), backend.clone()).unwrap();
+    cache.set("fresh", QueryResult::new("fresh_value")).await;
+    cache.set("stale", QueryResult::new("stale_value")).await;
+    cache.force_expire("stale").await;
+    cache.persist().await.unwrap();
+    drop(cache);
+
+    // Recreate the cache from disk
+    let restored = QueryMapCache::with_backend(config, backend).unwrap();
+    assert_eq!(restored.get("fresh").await, Some(QueryResult::new("fresh_value")));
+    assert!(restored.get("stale").await.is_none());
+    assert_eq!(restored.get_metrics().await.current_entries, 1);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[derive(Default)]
+struct CountingBackend {
+    saves: AtomicUsize,
+}
+
+#[async_trait]
+impl CacheBackend for CountingBackend {
+    fn load(&self) -> Result<HashMap<String, PersistedEntry>, CommunexError> {
+        Ok(HashMap::new())
+    }
+
+    async fn save(&self, _entries: &HashMap<String, PersistedEntry>) -> Result<(), CommunexError> {
+        self.saves.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_inserts_are_persisted_in_batches() {
+    let config = CacheConfig::default().with_persist_delay(Duration::from_millis(50));
+    let backend = Arc::new(CountingBackend::default());
+    let cache = QueryMapCache::with_backend(config, backend.clone()).unwrap();
+
+    for i in 0..10 {
+        cache.set(&format!("key_{}", i), QueryResult::new("value")).await;
+    }
+    assert_eq!(backend.saves.load(Ordering::SeqCst), 0);
+
+    sleep(Duration::from_millis(150)).await;
+    assert_eq!(backend.saves.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_cache_byte_limit() {
+    let config = CacheConfig {
+        max_bytes: Some(4096),
+        ..CacheConfig::default()
+    };
+
+    let cache = QueryMapCache::new(config);
+    let large_value = "x".repeat(1000);
+
+    for i in 0..10 {
+        cache.set(&format!("key_{}", i), QueryResult::new(&large_value)).await;
+        let metrics = cache.get_metrics().await;
+        assert!(metrics.current_bytes <= 4096, "cache grew to {} bytes", metrics.current_bytes);
+    }
+
+    // Oldest entries are evicted to make room, newest are kept
+    assert!(cache.get("key_0").await.is_none());
+    assert!(cache.get("key_9").await.is_some());
+
+    let metrics = cache.get_metrics().await;
+    assert!(metrics.current_entries < 10);
+    assert!(metrics.current_bytes > 0);
+
+    // Values larger than the whole budget are not cached at all
+    cache.set("huge", QueryResult::new(&"y".repeat(5000))).await;
+    assert!(cache.get("huge").await.is_none());
+    assert!(cache.get("key_9").await.is_some());
+}
+
+#[tokio::test]
+async fn test_cached_value_deserializes_to_balance() {
+    let cache = QueryMapCache::new(CacheConfig::default());
+    cache.set("balance:cmx1abc", QueryResult::from_value(json!({
+        "amount": "1000000",
+        "denom": "COMAI"
+    }))).await;
+
+    let cached = cache.get("balance:cmx1abc").await.unwrap();
+    let balance: Balance = cached.deserialize().unwrap();
+    assert_eq!(balance.amount(), Ok(1000000));
+    assert_eq!(balance.denom(), "COMAI");
+
+    // String results keep working through the compatibility constructor
+    assert_eq!(QueryResult::new("plain").as_str(), Some("plain"));
+    assert!(QueryResult::new("plain").deserialize::<Balance>().is_err());
+}
+
+#[tokio::test]
+async fn test_concurrent_misses_share_one_refresh() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let cache = Arc::new(QueryMapCache::new(CacheConfig::default()));
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let handler_calls = calls.clone();
+    cache.set_refresh_handler(Box::new(move |key: &str| {
+        let key = key.to_string();
+        let calls = handler_calls.clone();
+        Box::pin(async move {
+            calls.fetch_add(1, Ordering::SeqCst);
+            sleep(Duration::from_millis(100)).await;
+            Ok(QueryResult::new(&format!("refreshed_{}", key)))
+        })
+    })).await;
+
+    cache.set("hot_key", QueryResult::new("initial_value")).await;
+    cache.force_expire("hot_key").await;
+
+    let tasks: Vec<_> = (0..20)
+        .map(|_| {
+            let cache = cache.clone();
+            tokio::spawn(async move { cache.get("hot_key").await })
+        })
+        .collect();
+
+    for task in tasks {
+        let value = task.await.unwrap();
+        assert_eq!(value, Some(QueryResult::new("refreshed_hot_key")));
+    }
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(cache.get_metrics().await.refresh_success_count, 1);
+}
+
+#[tokio::test]
+async fn test_stop_background_refresh() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let config = CacheConfig {
+        ttl: Duration::from_millis(10),
+        refresh_interval: Duration::from_millis(20),
+        ..CacheConfig::default()
+    };
+    let cache = QueryMapCache::new(config);
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let handler_calls = calls.clone();
+    cache.set_refresh_handler(Box::new(move |_key: &str| {
+        let calls = handler_calls.clone();
+        Box::pin(async move {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(QueryResult::new("refreshed"))
+        })
+    })).await;
+    cache.set("ticking", QueryResult::new("initial")).await;
+
+    cache.start_background_refresh().await;
+    sleep(Duration::from_millis(150)).await;
+    assert!(calls.load(Ordering::SeqCst) > 0, "refresh task should be running");
+
+    cache.stop_background_refresh().await;
+    let stopped_at = calls.load(Ordering::SeqCst);
+    sleep(Duration::from_millis(150)).await;
+    assert_eq!(calls.load(Ordering::SeqCst), stopped_at);
+}
+
+#[tokio::test]
+async fn test_refresh_duration_metrics() {
+    let cache = QueryMapCache::new(CacheConfig::default());
+
+    cache.set_refresh_handler(Box::new(|_key: &str| {
+        Box::pin(async move {
+            sleep(Duration::from_millis(50)).await;
+            Ok(QueryResult::new("refreshed"))
+        })
+    })).await;
+
+    for key in ["slow_a", "slow_b"] {
+        cache.set(key, QueryResult::new("initial")).await;
+        cache.force_expire(key).await;
+        assert!(cache.get(key).await.is_some());
+    }
+
+    let metrics = cache.get_metrics().await;
+    assert_eq!(metrics.refresh_success_count, 2);
+    assert!(metrics.last_refresh_duration >= Duration::from_millis(50));
+    assert!(metrics.avg_refresh_duration >= Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn test_get_or_insert_with_hit() {
+    let cache = QueryMapCache::new(CacheConfig::default());
+    cache.set("cached", QueryResult::new("cached_value")).await;
+
+    let value = cache.get_or_insert_with("cached", || async {
+        panic!("closure must not run on a hit")
+    }).await.unwrap();
+
+    assert_eq!(value, QueryResult::new("cached_value"));
+    let metrics = cache.get_metrics().await;
+    assert_eq!(metrics.hits, 1);
+    assert_eq!(metrics.misses, 0);
+}
+
+#[tokio::test]
+async fn test_get_or_insert_with_miss() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let cache = QueryMapCache::new(CacheConfig::default());
+    let calls = AtomicUsize::new(0);
+
+    let value = cache.get_or_insert_with("computed", || async {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Ok(QueryResult::new("computed_value"))
+    }).await.unwrap();
+
+    assert_eq!(value, QueryResult::new("computed_value"));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(cache.get("computed").await, Some(QueryResult::new("computed_value")));
+
+    let metrics = cache.get_metrics().await;
+    assert_eq!(metrics.misses, 1);
+    assert_eq!(metrics.hits, 1);
+}
+
+#[tokio::test]
+async fn test_not_found_results_use_negative_ttl() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let config = CacheConfig {
+        negative_ttl: Duration::from_millis(200),
+        ..CacheConfig::default()
+    };
+    let cache = QueryMapCache::new(config);
+    let calls = AtomicUsize::new(0);
+    let lookup = || async {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Ok(QueryResult::not_found())
+    };
+
+    let first = cache.get_or_insert_with("balance:cmx1missing", lookup).await.unwrap();
+    assert!(first.is_not_found());
+
+    // Served from the cache within the negative TTL
+    let second = cache.get_or_insert_with("balance:cmx1missing", lookup).await.unwrap();
+    assert!(second.is_not_found());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    // Re-queried once it expires
+    sleep(Duration::from_millis(300)).await;
+    cache.get_or_insert_with("balance:cmx1missing", lookup).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_immutable_entries_outlive_ttl() {
+    let config = CacheConfig {
+        ttl: Duration::from_millis(100),
+        negative_ttl: Duration::from_millis(100),
+        max_entries: 3,
+        ..CacheConfig::default()
+    };
+    let cache = QueryMapCache::new(config);
+
+    cache.set_immutable("balance:cmx1abc@100", QueryResult::new("historical")).await;
+    cache.set("balance:cmx1abc", QueryResult::new("latest")).await;
+    sleep(Duration::from_millis(200)).await;
+
+    assert!(cache.get("balance:cmx1abc").await.is_none());
+    assert_eq!(cache.get("balance:cmx1abc@100").await, Some(QueryResult::new("historical")));
+
+    // The entry limit still applies, evicting expiring entries first
+    cache.set("a", QueryResult::new("a")).await;
+    cache.set("b", QueryResult::new("b")).await;
+    cache.set("c", QueryResult::new("c")).await;
+    assert!(cache.get("balance:cmx1abc@100").await.is_some());
+    assert_eq!(cache.get_metrics().await.current_entries, 3);
+
+    cache.set_immutable("balance:cmx1abc@101", QueryResult::new("newer")).await;
+    cache.set_immutable("balance:cmx1abc@102", QueryResult::new("newest")).await;
+    cache.set_immutable("balance:cmx1abc@103", QueryResult::new("latest")).await;
+    assert!(cache.get("balance:cmx1abc@100").await.is_none());
+    assert!(cache.get("balance:cmx1abc@103").await.is_some());
+    assert_eq!(cache.get_metrics().await.current_entries, 3);
+}
+
+#[tokio::test]
+async fn test_file_backend_restores_immutable_entries() {
+    let path = std::env::temp_dir().join(format!("comx_immutable_cache_test_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let backend = Arc::new(FileBackend::new(&path));
+    let cache = QueryMapCache::with_backend(CacheConfig::default(), backend.clone()).unwrap();
+    cache.set_immutable("historical", QueryResult::new("value")).await;
+    cache.persist().await.unwrap();
+    drop(cache);
+
+    let restored = QueryMapCache::with_backend(CacheConfig::default(), backend).unwrap();
+    assert_eq!(restored.get("historical").await, Some(QueryResult::new("value")));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_cache_config_builder() {
+    let config = CacheConfig::default()
+        .with_ttl(Duration::from_secs(30))
+        .with_negative_ttl(Duration::from_secs(5))
+        .with_refresh_interval(Duration::from_secs(60))
+        .with_max_entries(10)
+        .with_max_bytes(4096);
+
+    assert_eq!(config.ttl, Duration::from_secs(30));
+    assert_eq!(config.negative_ttl, Duration::from_secs(5));
+    assert_eq!(config.refresh_interval, Duration::from_secs(60));
+    assert_eq!(config.max_entries, 10);
+    assert_eq!(config.max_bytes, Some(4096));
+    assert_eq!(CacheConfig::default().max_bytes, None);
+}
@@ -3,15 +3,23 @@ use crate::error::CommunexError;
 use crate::crypto::{KeyPair, serde::hex_bytes};
 use sp_core::sr25519::{Public, Signature, Pair};
 use sp_core::sr25519::{PUBLIC_KEY_SERIALIZED_SIZE, SIGNATURE_SERIALIZED_SIZE};
+use std::collections::HashMap;
 use std::fmt::Display;
+use std::str::FromStr;
 use std::string::String;
 use serde_json::Value;
+use parity_scale_codec::{Decode, DecodeAll, Encode};
 use std::sync::atomic::{AtomicU64, Ordering};
 use bs58;
 
 static REQUEST_ID: AtomicU64 = AtomicU64::new(0);
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Returns the next JSON-RPC request id, unique for the lifetime of the process
+pub(crate) fn next_request_id() -> u64 {
+    REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct Address(String);
 
 impl Address {
@@ -21,17 +29,148 @@ impl Address {
             return Err(CommunexError::InvalidAddress(address));
         }
         // Validate base58 format
-        if let Err(_) = bs58::decode(&address[4..]).into_vec() {
+        if bs58::decode(&address[4..]).into_vec().is_err() {
             return Err(CommunexError::InvalidAddress(address));
         }
         Ok(Self(address))
     }
+
+    /// The 32-byte public key (account id) the address encodes
+    pub fn public_key_bytes(&self) -> Result<[u8; PUBLIC_KEY_SERIALIZED_SIZE], CommunexError> {
+        let payload = bs58::decode(&self.0[4..])
+            .into_vec()
+            .map_err(|_| CommunexError::InvalidAddress(self.0.clone()))?;
+        payload.try_into().map_err(|payload: Vec<u8>| CommunexError::InvalidAddress(format!(
+            "{}: payload is {} bytes, expected {}",
+            self.0,
+            payload.len(),
+            PUBLIC_KEY_SERIALIZED_SIZE
+        )))
+    }
+}
+
+impl FromStr for Address {
+    type Err = CommunexError;
+
+    fn from_str(address: &str) -> Result<Self, Self::Err> {
+        Self::new(address)
+    }
+}
+
+impl TryFrom<&str> for Address {
+    type Error = CommunexError;
+
+    fn try_from(address: &str) -> Result<Self, Self::Error> {
+        Self::new(address)
+    }
+}
+
+impl TryFrom<String> for Address {
+    type Error = CommunexError;
+
+    fn try_from(address: String) -> Result<Self, Self::Error> {
+        Self::new(address)
+    }
+}
+
+/// Maximum number of subnets the chain allows; valid netuids are below this.
+pub const MAX_SUBNETS: u16 = 256;
+
+/// Identifier of a Communex subnet
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(try_from = "u16", into = "u16")]
+pub struct Netuid(u16);
+
+impl Netuid {
+    pub fn new(netuid: u16) -> Result<Self, CommunexError> {
+        if netuid >= MAX_SUBNETS {
+            return Err(CommunexError::ValidationError(
+                format!("Invalid netuid {}: must be less than {}", netuid, MAX_SUBNETS)
+            ));
+        }
+        Ok(Self(netuid))
+    }
+
+    pub fn value(&self) -> u16 {
+        self.0
+    }
 }
+
+impl TryFrom<u16> for Netuid {
+    type Error = CommunexError;
+
+    fn try_from(netuid: u16) -> Result<Self, Self::Error> {
+        Self::new(netuid)
+    }
+}
+
+impl From<Netuid> for u16 {
+    fn from(netuid: Netuid) -> Self {
+        netuid.0
+    }
+}
+
+impl Display for Netuid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BigUint(pub [u8; 32], pub u64);
 impl std::fmt::Display for BigUint {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&hex::encode(&self.0))
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+/// Denomination accepted when no registry is configured
+pub const DEFAULT_DENOM: &str = "COMAI";
+/// Decimal places of `DEFAULT_DENOM`
+pub const DEFAULT_DENOM_DECIMALS: u8 = 9;
+
+/// The denominations balances and transfers may use, with the number of
+/// decimal places of each. The default registry holds only COMAI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DenomRegistry {
+    denoms: HashMap<String, u8>,
+}
+
+impl DenomRegistry {
+    /// A registry with no denominations
+    pub fn empty() -> Self {
+        Self { denoms: HashMap::new() }
+    }
+
+    /// Add `denom` with `decimals` places, replacing any earlier entry
+    pub fn with_denom(mut self, denom: impl Into<String>, decimals: u8) -> Self {
+        self.register(denom, decimals);
+        self
+    }
+
+    pub fn register(&mut self, denom: impl Into<String>, decimals: u8) {
+        self.denoms.insert(denom.into(), decimals);
+    }
+
+    pub fn contains(&self, denom: &str) -> bool {
+        self.denoms.contains_key(denom)
+    }
+
+    pub fn decimals(&self, denom: &str) -> Option<u8> {
+        self.denoms.get(denom).copied()
+    }
+
+    /// The registered denominations, sorted
+    pub fn denoms(&self) -> Vec<&str> {
+        let mut denoms: Vec<&str> = self.denoms.keys().map(String::as_str).collect();
+        denoms.sort_unstable();
+        denoms
+    }
+}
+
+impl Default for DenomRegistry {
+    fn default() -> Self {
+        Self::empty().with_denom(DEFAULT_DENOM, DEFAULT_DENOM_DECIMALS)
     }
 }
 
@@ -43,6 +182,15 @@ pub struct Balance {
 
 impl Balance {
     pub fn new(amount: impl Into<String>, denom: impl Into<String>) -> Result<Self, CommunexError> {
+        Self::new_with_registry(amount, denom, &DenomRegistry::default())
+    }
+
+    /// Like `new`, accepting any denomination in `denoms`
+    pub fn new_with_registry(
+        amount: impl Into<String>,
+        denom: impl Into<String>,
+        denoms: &DenomRegistry,
+    ) -> Result<Self, CommunexError> {
         let amount = amount.into();
         let denom = denom.into();
         
@@ -51,7 +199,7 @@ impl Balance {
             .map_err(|_| CommunexError::InvalidAmount("Invalid amount format".into()))?;
             
         // Validate denomination
-        if !is_valid_denom(&denom) {
+        if !denoms.contains(&denom) {
             return Err(CommunexError::InvalidDenom(denom));
         }
 
@@ -69,6 +217,11 @@ impl Balance {
     }
 
     pub fn from_rpc(value: &Value) -> Result<Self, CommunexError> {
+        Self::from_rpc_with_registry(value, &DenomRegistry::default())
+    }
+
+    /// Like `from_rpc`, accepting any denomination in `denoms`
+    pub fn from_rpc_with_registry(value: &Value, denoms: &DenomRegistry) -> Result<Self, CommunexError> {
         let amount = value.get("amount")
             .and_then(|v| v.as_str())
             .ok_or_else(|| CommunexError::MalformedResponse("Missing amount field".into()))?;
@@ -82,7 +235,7 @@ impl Balance {
             .map_err(|_| CommunexError::InvalidAmount("Invalid amount format".into()))?;
             
         // Validate denomination
-        if !is_valid_denom(denom) {
+        if !denoms.contains(denom) {
             return Err(CommunexError::InvalidDenom(denom.to_string()));
         }
 
@@ -93,12 +246,6 @@ impl Balance {
     }
 }
 
-// Remove the parse() call on denom since we're not parsing it anymore
-fn is_valid_denom(denom: &str) -> bool {
-    const VALID_DENOMS: &[&str] = &["COMAI"];
-    VALID_DENOMS.contains(&denom)
-}
-
 impl Display for Balance {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{} {}", self.amount, self.denom)
@@ -120,8 +267,13 @@ impl FromRpcResponse for Balance {
     }
 }
 
+/// Version of the transaction signing payload. Bumped whenever the payload
+/// format changes, so signatures from incompatible clients fail to verify.
+pub const SIGNING_PAYLOAD_VERSION: u8 = 1;
+
 #[derive(Serialize)]
 struct SigningData<'a> {
+    version: u8,
     from: &'a str,
     to: &'a str,
     amount: &'a str,
@@ -129,7 +281,7 @@ struct SigningData<'a> {
     memo: &'a str,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
 pub struct Transaction {
     from: String,
     to: String,
@@ -160,6 +312,11 @@ impl Transaction {
     }
 
     pub fn validate(&self) -> Result<(), CommunexError> {
+        self.validate_with_registry(&DenomRegistry::default())
+    }
+
+    /// Like `validate`, accepting any denomination in `denoms`
+    pub fn validate_with_registry(&self, denoms: &DenomRegistry) -> Result<(), CommunexError> {
         // Validate addresses
         if !self.from.starts_with("cmx1") || !self.to.starts_with("cmx1") {
             return Err(CommunexError::InvalidAddress("Invalid address format".into()));
@@ -167,7 +324,7 @@ impl Transaction {
 
         // Validate amount is not zero
         match self.amount.parse::<u64>() {
-            Ok(amount) if amount == 0 => {
+            Ok(0) => {
                 return Err(CommunexError::InvalidAmount("Amount cannot be zero".into()));
             }
             Err(_) => {
@@ -177,7 +334,7 @@ impl Transaction {
         }
 
         // Validate denomination
-        if !is_valid_denom(&self.denom) {
+        if !denoms.contains(&self.denom) {
             return Err(CommunexError::InvalidDenom(self.denom.clone()));
         }
 
@@ -192,9 +349,21 @@ impl Transaction {
         &self.denom
     }
 
+    /// SCALE-encode the transaction's fields in declaration order, strings
+    /// as length-prefixed UTF-8. This encodes the struct itself; it is not an
+    /// extrinsic and cannot be submitted to a chain as is.
+    pub fn to_scale_bytes(&self) -> Vec<u8> {
+        self.encode()
+    }
+
+    /// Decode a transaction produced by `to_scale_bytes`. Trailing bytes are rejected.
+    pub fn from_scale_bytes(bytes: &[u8]) -> Result<Self, CommunexError> {
+        Self::decode_all(&mut &bytes[..])
+            .map_err(|e| CommunexError::ParseError(format!("Invalid SCALE transaction: {}", e)))
+    }
+
     pub fn sign(&self, keypair: &KeyPair) -> Result<SignedTransaction, CommunexError> {
-        let message = self.serialize_for_signing()
-            .map_err(|e| CommunexError::SigningError(e.to_string()))?;
+        let message = self.signing_payload()?;
         
         let signature = keypair.sign(&message);
         let public_key = keypair.public_key();
@@ -206,8 +375,11 @@ impl Transaction {
         })
     }
     
-    fn serialize_for_signing(&self) -> Result<Vec<u8>, serde_json::Error> {
+    /// The bytes a signature over this transaction covers, at the current
+    /// `SIGNING_PAYLOAD_VERSION`
+    pub fn signing_payload(&self) -> Result<Vec<u8>, CommunexError> {
         let signing_data = SigningData {
+            version: SIGNING_PAYLOAD_VERSION,
             from: &self.from,
             to: &self.to,
             amount: &self.amount,
@@ -215,10 +387,11 @@ impl Transaction {
             memo: &self.memo,
         };
         serde_json::to_vec(&signing_data)
+            .map_err(|e| CommunexError::SigningError(e.to_string()))
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
 pub struct SignedTransaction {
     pub transaction: Transaction,
     #[serde(with = "hex_bytes")]
@@ -228,16 +401,28 @@ pub struct SignedTransaction {
 }
 
 impl SignedTransaction {
+    /// Hex encoding of the SCALE-encoded transaction, signature and public key
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.encode())
+    }
+
+    /// Parse a signed transaction produced by `to_hex`. A `0x` prefix is accepted.
+    pub fn from_hex(s: &str) -> Result<Self, CommunexError> {
+        let bytes = hex::decode(s.strip_prefix("0x").unwrap_or(s))
+            .map_err(|e| CommunexError::ParseError(format!("Invalid hex: {}", e)))?;
+        Self::decode_all(&mut &bytes[..])
+            .map_err(|e| CommunexError::ParseError(format!("Invalid signed transaction: {}", e)))
+    }
+
     pub fn verify_signature(&self) -> Result<(), CommunexError> {
         self.verify_signature_with_key(&self.public_key)
     }
     
-    pub fn verify_signature_with_key(&self, public_key: &[u8; 32]) -> Result<(), CommunexError> {
+    pub fn verify_signature_with_key(&self, public_key: &[u8; PUBLIC_KEY_SERIALIZED_SIZE]) -> Result<(), CommunexError> {
         let public = Public::from_raw(*public_key);
         let signature = Signature::from_raw(self.signature);
         
-        let message = self.transaction.serialize_for_signing()
-            .map_err(|e| CommunexError::SigningError(e.to_string()))?;
+        let message = self.transaction.signing_payload()?;
             
         if <Pair as sp_core::Pair>::verify(&signature, &message, &public) {
             Ok(())
@@ -261,7 +446,7 @@ impl RpcRequest {
             jsonrpc: "2.0".to_string(),
             method: method.into(),
             params,
-            id: REQUEST_ID.fetch_add(1, Ordering::Relaxed),
+            id: next_request_id(),
         }
     }
 }
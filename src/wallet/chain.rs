@@ -0,0 +1,44 @@
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, Utc};
+use crate::error::CommunexError;
+use crate::wallet::{WalletClient, parse_unix_ts};
+use crate::rpc::params::{to_params, BlockParams};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockInfo {
+    pub number: u64,
+    pub hash: String,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub timestamp: DateTime<Utc>,
+    pub parent_hash: String,
+}
+
+impl WalletClient {
+    /// Most recent block known to the node
+    pub async fn get_latest_block(&self) -> Result<BlockInfo, CommunexError> {
+        self.query_block(BlockParams { number: None }).await
+    }
+
+    pub async fn get_block(&self, number: u64) -> Result<BlockInfo, CommunexError> {
+        self.query_block(BlockParams { number: Some(number) }).await
+    }
+
+    async fn query_block(&self, params: BlockParams) -> Result<BlockInfo, CommunexError> {
+        let response = self.rpc_client.request_with_path("chain/block", to_params(&params)?).await?;
+
+        Ok(BlockInfo {
+            number: response.get("number")
+                .and_then(|v| v.as_u64())
+                .ok_or(CommunexError::MalformedResponse("Missing block number".into()))?,
+            hash: response.get("hash")
+                .and_then(|v| v.as_str())
+                .ok_or(CommunexError::MalformedResponse("Missing block hash".into()))?
+                .to_string(),
+            timestamp: parse_unix_ts(response.get("timestamp").and_then(|v| v.as_i64()))?,
+            parent_hash: response.get("parent_hash")
+                .and_then(|v| v.as_str())
+                .ok_or(CommunexError::MalformedResponse("Missing parent hash".into()))?
+                .to_string(),
+        })
+    }
+}
@@ -1,11 +1,28 @@
-use crate::{CommunexError, rpc::RpcClient};
+use crate::error::ErrorCause;
+use crate::{CommunexError, rpc::{parse_response, BatchRequest, RpcClient, RpcClientConfig, RpcTransport, DEFAULT_RPC_URL}};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::rpc::SubscriptionClient;
+use crate::rpc::params::{to_params, AddressParams, BatchTransferParams, TransferMultipleParams, TransferOutput, TransferParams, TxHashParams};
+use crate::runtime::{self, Instant, Runtime};
+use crate::types::{Balance, DenomRegistry};
+use futures::Stream;
+use futures::future::{self, Either};
+use futures::stream::{self, BoxStream, StreamExt};
 use serde::{Serialize, Deserialize};
-use serde_json::json;
+use serde_json::Value;
 use chrono::{DateTime, Utc};
-use std::time::{Duration, Instant};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 pub mod staking;
+pub mod subnet;
+pub mod chain;
+pub mod monitor;
+pub mod portfolio;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
 pub struct TransferRequest {
     pub from: String,
     pub to: String,
@@ -13,17 +30,50 @@ pub struct TransferRequest {
     pub denom: String,
 }
 
+impl TransferRequest {
+    /// A transfer of `balance`, taking the amount and denomination from it
+    /// so the two cannot disagree
+    pub fn from_balance(
+        from: impl Into<String>,
+        to: impl Into<String>,
+        balance: Balance,
+    ) -> Result<Self, CommunexError> {
+        Ok(Self {
+            from: from.into(),
+            to: to.into(),
+            amount: balance.amount()?,
+            denom: balance.denom().to_string(),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
 pub struct TransferResponse {
     pub state: String,
 }
 
+/// Free balance of an address, as reported by the server and CLI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
+pub struct FreeBalance {
+    pub address: String,
+    pub free: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(schemars::JsonSchema))]
 pub struct BalanceInfo {
     pub free: u64,
     pub reserved: u64,
     pub misc_frozen: u64,
     pub fee_frozen: u64,
+    /// Block the balances were read at, when the node reports it
+    #[serde(default)]
+    pub block_num: Option<u64>,
+    /// When the balances were received
+    #[serde(default = "Utc::now")]
+    pub fetched_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,15 +86,7 @@ pub struct TransactionHistory {
     pub to: String,
     pub amount: u64,
     pub denom: String,
-    pub state: TransactionStatus,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "lowercase")]
-pub enum TransactionStatus {
-    Success,
-    Failed,
-    Pending,
+    pub state: TxStatus,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,21 +94,51 @@ pub struct TransactionState {
     pub hash: String,
     pub block_num: Option<u64>,
     pub confirmations: u64,
-    pub state: Txstate,
-    #[serde(with = "chrono::serde::ts_seconds")]
-    pub timestamp: DateTime<Utc>,
+    pub state: TxStatus,
+    /// When the transaction was included, if the node reports it. Unknown
+    /// transactions have no timestamp.
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    pub timestamp: Option<DateTime<Utc>>,
     pub error: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Status of a submitted transaction
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
-pub enum Txstate {
+#[non_exhaustive]
+pub enum TxStatus {
     Pending,
     Success,
     Failed,
+    #[serde(alias = "not_found", alias = "notFound")]
     NotFound,
 }
 
+impl TxStatus {
+    /// Whether the transaction has reached a final outcome
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, TxStatus::Success | TxStatus::Failed)
+    }
+}
+
+impl std::fmt::Display for TxStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            TxStatus::Pending => "pending",
+            TxStatus::Success => "success",
+            TxStatus::Failed => "failed",
+            TxStatus::NotFound => "notfound",
+        };
+        f.write_str(s)
+    }
+}
+
+#[deprecated(note = "use `TxStatus`")]
+pub type TransactionStatus = TxStatus;
+
+#[deprecated(note = "use `TxStatus`")]
+pub type Txstate = TxStatus;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BatchTransferResult {
     pub batch_id: String,
@@ -76,31 +148,170 @@ pub struct BatchTransferResult {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BatchTransactionStatus {
     pub hash: String,
-    pub status: TransactionStatus,
+    pub status: TxStatus,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
+/// Client for wallet operations against a node.
+///
+/// Reads (`get_*` methods) are independent requests and safe to run
+/// concurrently, including on clones of one client; `get_portfolio` joins
+/// three of them. Submissions (`transfer`, `stake`, `claim_rewards` and the
+/// like) from one sender should run one at a time, as each depends on the
+/// account nonce the previous one advanced, and each waits for its own
+/// transaction before returning.
+#[derive(Clone)]
 pub struct WalletClient {
-    pub rpc_client: RpcClient,
+    pub rpc_client: Arc<dyn RpcTransport>,
+    /// WebSocket endpoint used for subscriptions, if any
+    pub ws_url: Option<String>,
+    /// Delay between polls while waiting for transactions
+    pub poll_backoff: PollBackoff,
+    /// Runtime used to sleep between polls
+    pub runtime: Arc<dyn Runtime>,
+    /// Denominations transfers may use
+    pub denoms: DenomRegistry,
+    /// Check the sender's free balance before sending a transfer
+    pub balance_precheck: bool,
+    /// Reject balance, staking and transaction state responses with fields
+    /// this client does not read
+    pub strict_parsing: bool,
+    /// Idempotency key of each address's unsettled `claim_rewards`, reused
+    /// until the claim is confirmed or rejected
+    pending_claim_keys: Arc<Mutex<HashMap<String, String>>>,
+}
+
+/// Fields of a `balance/all` response
+const BALANCE_INFO_FIELDS: &[&str] = &["free", "reserved", "miscFrozen", "feeFrozen", "block_num"];
+/// Fields of a `transaction/state` response
+const TRANSACTION_STATE_FIELDS: &[&str] = &["state", "block_num", "confirmations", "timestamp", "error"];
+
+/// Exponential delay between transaction state polls, doubling from
+/// `initial` on each attempt up to `max`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollBackoff {
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+impl PollBackoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self { initial, max }
+    }
+
+    /// Delay to wait after the given zero-based poll attempt
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.initial
+            .checked_mul(2u32.saturating_pow(attempt))
+            .map_or(self.max, |delay| delay.min(self.max))
+    }
+}
+
+impl Default for PollBackoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(500),
+            max: Duration::from_secs(8),
+        }
+    }
 }
 
 // Constants for validation
 const MAX_BATCH_SIZE: usize = 100;
-const VALID_DENOMS: [&str; 1] = ["COMAI"];
 const MIN_AMOUNT: u64 = 1;
 
+impl Default for WalletClient {
+    /// A client for a node on this machine at `DEFAULT_RPC_URL`
+    fn default() -> Self {
+        Self::new(DEFAULT_RPC_URL)
+    }
+}
+
 impl WalletClient {
     pub fn new(url: &str) -> Self {
-        Self {
-            rpc_client: RpcClient::new(url),
-        }
+        Self::with_transport(Arc::new(RpcClient::new(url)))
+            .with_ws_url(&ws_url_for(url))
     }
 
     pub fn with_timeout(url: &str, timeout: Duration) -> Self {
+        Self::with_transport(Arc::new(RpcClient::with_timeout(url, timeout)))
+            .with_ws_url(&ws_url_for(url))
+    }
+
+    /// Like `new`, with `config` for the underlying `RpcClient`
+    pub fn with_config(url: &str, config: RpcClientConfig) -> Self {
+        Self::with_transport(Arc::new(RpcClient::new_with_config(url, config)))
+            .with_ws_url(&ws_url_for(url))
+    }
+
+    /// Create a wallet client on top of any RPC transport. Subscriptions are
+    /// unavailable until a WebSocket URL is set with `with_ws_url`.
+    pub fn with_transport(transport: Arc<dyn RpcTransport>) -> Self {
         Self {
-            rpc_client: RpcClient::with_timeout(url, timeout),
+            rpc_client: transport,
+            ws_url: None,
+            poll_backoff: PollBackoff::default(),
+            runtime: runtime::default_runtime(),
+            denoms: DenomRegistry::default(),
+            balance_precheck: false,
+            strict_parsing: false,
+            pending_claim_keys: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn with_runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+        self.runtime = runtime;
+        self
+    }
+
+    /// Poll transaction state starting `initial` apart, backing off to `max`
+    pub fn with_poll_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.poll_backoff = PollBackoff::new(initial, max);
+        self
+    }
+
+    /// Accept transfers in the denominations of `denoms` instead of COMAI only
+    pub fn with_denoms(mut self, denoms: DenomRegistry) -> Self {
+        self.denoms = denoms;
+        self
+    }
+
+    /// Have `transfer` fetch the sender's free balance first and fail with
+    /// `InsufficientFunds` without sending a transfer the node would reject.
+    /// Costs an extra request per transfer, so it is off by default.
+    pub fn with_balance_precheck(mut self, enabled: bool) -> Self {
+        self.balance_precheck = enabled;
+        self
+    }
+
+    /// Have balance, staking info and transaction state queries fail with a
+    /// `ParseError` when the node sends fields this client does not know, to
+    /// catch API drift. Off by default, ignoring such fields.
+    pub fn with_strict_parsing(mut self, enabled: bool) -> Self {
+        self.strict_parsing = enabled;
+        self
+    }
+
+    /// In strict mode, fail if `response` has a field outside `known`
+    fn check_fields(&self, method: &str, response: &Value, known: &[&str]) -> Result<(), CommunexError> {
+        if !self.strict_parsing {
+            return Ok(());
         }
+        let unknown = response.as_object()
+            .and_then(|fields| fields.keys().find(|field| !known.contains(&field.as_str())));
+        match unknown {
+            Some(field) => Err(CommunexError::ParseError(
+                format!("Unexpected field '{}' in {} response", field, method)
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Use `url` as the WebSocket endpoint for subscriptions
+    pub fn with_ws_url(mut self, url: &str) -> Self {
+        self.ws_url = Some(url.to_string());
+        self
     }
 
     pub async fn transfer(&self, request: TransferRequest) -> Result<TransferResponse, CommunexError> {
@@ -112,7 +323,7 @@ impl WalletClient {
             });
         }
 
-        if !request.denom.eq("COMAI") {
+        if !self.denoms.contains(&request.denom) {
             return Err(CommunexError::RpcError {
                 code: -32003,
                 message: "Unsupported denomination".into(),
@@ -126,16 +337,23 @@ impl WalletClient {
             });
         }
 
+        if self.balance_precheck {
+            let available = self.get_free_balance(&request.from).await?;
+            if request.amount > available {
+                return Err(CommunexError::InsufficientFunds { required: request.amount, available });
+            }
+        }
+
         // Prepare RPC request
-        let params = json!({
-            "from": request.from,
-            "to": request.to,
-            "amount": request.amount.to_string(),
-            "denom": request.denom,
-        });
+        let params = to_params(&TransferParams {
+            from: &request.from,
+            to: &request.to,
+            amount: request.amount.to_string(),
+            denom: &request.denom,
+        })?;
 
         // Send RPC request
-        match self.rpc_client.request_with_path("transfer", params).await {
+        match self.rpc_client.submit_with_path("transfer", params).await {
             Ok(response) => {
                 Ok(TransferResponse {
                     state: response.get("state")
@@ -153,8 +371,8 @@ impl WalletClient {
                     _ => Err(CommunexError::RpcError { code, message })
                 }
             },
-            Err(_) => {
-                Err(CommunexError::ConnectionError("Failed to connect to server".into()))
+            Err(e) => {
+                Err(CommunexError::ConnectionError(ErrorCause::with_message("Failed to connect to server", e)))
             }
         }
     }
@@ -167,9 +385,7 @@ impl WalletClient {
             });
         }
 
-        let params = json!({
-            "address": address,
-        });
+        let params = to_params(&AddressParams { address })?;
 
         match self.rpc_client.request_with_path("balance/free", params).await {
             Ok(response) => {
@@ -181,6 +397,48 @@ impl WalletClient {
         }
     }
 
+    /// Free balances of several addresses in one batch request, in the order given
+    pub async fn get_free_balances(&self, addresses: &[&str]) -> Result<Vec<u64>, CommunexError> {
+        let mut batch = BatchRequest::new();
+        for address in addresses {
+            if !address.starts_with("cmx1") {
+                return Err(CommunexError::RpcError {
+                    code: -32001,
+                    message: "Invalid address".into(),
+                });
+            }
+            batch.add_request("balance/free", to_params(&AddressParams { address })?);
+        }
+        batch.validate()?;
+
+        let response = self.rpc_client.batch_request(batch).await?;
+        if let Some(error) = response.errors.into_iter().next() {
+            return Err(CommunexError::RpcError {
+                code: error.code,
+                message: error.message,
+            });
+        }
+        if response.successes.len() != addresses.len() {
+            return Err(CommunexError::MalformedResponse(format!(
+                "Expected {} balances, got {}",
+                addresses.len(),
+                response.successes.len()
+            )));
+        }
+
+        addresses.iter()
+            .zip(&response.successes)
+            .map(|(address, result)| {
+                result.get("free")
+                    .and_then(|v| v.as_u64())
+                    .ok_or_else(|| CommunexError::MalformedResponse(format!(
+                        "balance/free response for {} is missing 'free'",
+                        address
+                    )))
+            })
+            .collect()
+    }
+
     pub async fn get_all_balances(&self, address: &str) -> Result<BalanceInfo, CommunexError> {
         if !address.starts_with("cmx1") {
             return Err(CommunexError::RpcError {
@@ -189,24 +447,48 @@ impl WalletClient {
             });
         }
 
-        let params = json!({
-            "address": address,
-        });
+        let params = to_params(&AddressParams { address })?;
 
         match self.rpc_client.request_with_path("balance/all", params).await {
             Ok(response) => {
+                self.check_fields("balance/all", &response, BALANCE_INFO_FIELDS)?;
                 Ok(BalanceInfo {
                     free: response.get("free").and_then(|v| v.as_u64()).unwrap_or(0),
                     reserved: response.get("reserved").and_then(|v| v.as_u64()).unwrap_or(0),
                     misc_frozen: response.get("miscFrozen").and_then(|v| v.as_u64()).unwrap_or(0),
                     fee_frozen: response.get("feeFrozen").and_then(|v| v.as_u64()).unwrap_or(0),
+                    block_num: response.get("block_num").and_then(|v| v.as_u64()),
+                    fetched_at: Utc::now(),
                 })
             },
             Err(e) => Err(e)
         }
     }
 
-    pub async fn get_staked_balance(&self, address: &str) -> Result<u64, CommunexError> {
+    /// Current transaction nonce of `address`
+    pub async fn get_nonce(&self, address: &str) -> Result<u64, CommunexError> {
+        if !address.starts_with("cmx1") {
+            return Err(CommunexError::RpcError {
+                code: -32001,
+                message: "Invalid address".into(),
+            });
+        }
+
+        let params = to_params(&AddressParams { address })?;
+
+        let response = self.rpc_client.request_with_path("account/nonce", params).await?;
+        response.get("nonce")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| CommunexError::MalformedResponse("Missing nonce in response".into()))
+    }
+
+    /// Stream the free balance of `address` each time it changes on chain.
+    /// Not available on wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn subscribe_balance(
+        &self,
+        address: &str,
+    ) -> Result<impl Stream<Item = Result<u64, CommunexError>>, CommunexError> {
         if !address.starts_with("cmx1") {
             return Err(CommunexError::RpcError {
                 code: -32001,
@@ -214,10 +496,76 @@ impl WalletClient {
             });
         }
 
-        let params = json!({
-            "address": address,
+        let ws_url = self.ws_url.as_deref()
+            .ok_or_else(|| CommunexError::ConfigError("No WebSocket URL configured for subscriptions".into()))?;
+
+        let params = to_params(&AddressParams { address })?;
+
+        let updates = SubscriptionClient::new(ws_url)
+            .subscribe("balance_subscribe", params)
+            .await?;
+
+        Ok(free_balance_updates(updates))
+    }
+
+    /// Like `subscribe_balance`, but if no subscription can be opened (no
+    /// WebSocket URL, connection refused, or the node rejects it) the stream
+    /// polls `get_free_balance` every `poll_interval` instead, yielding the
+    /// first balance and then each change.
+    pub async fn subscribe_or_poll(
+        &self,
+        address: &str,
+        poll_interval: Duration,
+    ) -> Result<BoxStream<'static, Result<u64, CommunexError>>, CommunexError> {
+        if !address.starts_with("cmx1") {
+            return Err(CommunexError::RpcError {
+                code: -32001,
+                message: "Invalid address".into(),
+            });
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(ws_url) = self.ws_url.as_deref() {
+            let params = to_params(&AddressParams { address })?;
+            match SubscriptionClient::new(ws_url).subscribe("balance_subscribe", params).await {
+                Ok(updates) => return Ok(free_balance_updates(updates).boxed()),
+                Err(e) => warn!("Balance subscription unavailable, polling instead: {}", e),
+            }
+        }
+
+        let client = self.clone();
+        let address = address.to_string();
+        let polls = stream::unfold((client, address, None, false), move |(client, address, mut last, mut started)| async move {
+            loop {
+                if started {
+                    client.runtime.sleep(poll_interval).await;
+                }
+                started = true;
+
+                match client.get_free_balance(&address).await {
+                    Ok(balance) if last == Some(balance) => continue,
+                    Ok(balance) => {
+                        last = Some(balance);
+                        return Some((Ok(balance), (client, address, last, started)));
+                    }
+                    Err(e) => return Some((Err(e), (client, address, last, started))),
+                }
+            }
         });
 
+        Ok(polls.boxed())
+    }
+
+    pub async fn get_staked_balance(&self, address: &str) -> Result<u64, CommunexError> {
+        if !address.starts_with("cmx1") {
+            return Err(CommunexError::RpcError {
+                code: -32001,
+                message: "Invalid address".into(),
+            });
+        }
+
+        let params = to_params(&AddressParams { address })?;
+
         match self.rpc_client.request_with_path("balance/staked", params).await {
             Ok(response) => {
                 Ok(response.get("staked")
@@ -236,9 +584,7 @@ impl WalletClient {
             });
         }
 
-        let params = json!({
-            "address": address,
-        });
+        let params = to_params(&AddressParams { address })?;
 
         match self.rpc_client.request_with_path("transaction/history", params).await {
             Ok(response) => {
@@ -256,11 +602,7 @@ impl WalletClient {
                             block_num: tx.get("block_num")
                                 .and_then(|v| v.as_u64())
                                 .ok_or(CommunexError::MalformedResponse("Missing block number".into()))?,
-                            timestamp: tx.get("timestamp")
-                                .and_then(|v| v.as_i64())
-                                .map(|ts| DateTime::<Utc>::from_timestamp(ts, 0))
-                                .flatten()
-                                .ok_or(CommunexError::MalformedResponse("Invalid timestamp".into()))?,
+                            timestamp: parse_unix_ts(tx.get("timestamp").and_then(|v| v.as_i64()))?,
                             from: tx.get("from")
                                 .and_then(|v| v.as_str())
                                 .ok_or(CommunexError::MalformedResponse("Missing from address".into()))?
@@ -276,12 +618,7 @@ impl WalletClient {
                                 .and_then(|v| v.as_str())
                                 .ok_or(CommunexError::MalformedResponse("Missing denomination".into()))?
                                 .to_string(),
-                            state: match tx.get("state").and_then(|v| v.as_str()) {
-                                Some("success") => TransactionStatus::Success,
-                                Some("failed") => TransactionStatus::Failed,
-                                Some("pending") => TransactionStatus::Pending,
-                                _ => TransactionStatus::Failed,
-                            },
+                            state: parse_tx_status(tx.get("state"))?,
                         })
                     })
                     .collect::<Result<Vec<_>, _>>()
@@ -291,50 +628,108 @@ impl WalletClient {
     }
 
     pub async fn get_transaction_state(&self, tx_hash: &str) -> Result<TransactionState, CommunexError> {
-        let params = json!({
-            "hash": tx_hash,
-        });
+        let params = to_params(&TxHashParams { hash: tx_hash })?;
 
         match self.rpc_client.request_with_path("transaction/state", params).await {
             Ok(response) => {
-                Ok(TransactionState {
-                    hash: tx_hash.to_string(),
-                    block_num: response.get("block_num")
-                        .and_then(|v| v.as_u64()),
-                    confirmations: response.get("confirmations")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0),
-                    state: match response.get("state").and_then(|v| v.as_str()) {
-                        Some("success") => Txstate::Success,
-                        Some("failed") => Txstate::Failed,
-                        Some("pending") => Txstate::Pending,
-                        _ => Txstate::NotFound,
-                    },
-                    timestamp: response.get("timestamp")
-                        .and_then(|v| v.as_i64())
-                        .map(|ts| DateTime::<Utc>::from_timestamp(ts, 0))
-                        .flatten()
-                        .unwrap_or_else(|| Utc::now()),
-                    error: response.get("error")
-                        .and_then(|v| v.as_str())
-                        .map(String::from),
-                })
-            },
+                self.check_fields("transaction/state", &response, TRANSACTION_STATE_FIELDS)?;
+                parse_transaction_state(tx_hash, &response)
+            }
             Err(e) => Err(e)
         }
     }
 
+    /// Look up the state of several transactions in one batch call. States
+    /// are returned in the same order as `hashes`.
+    pub async fn get_transaction_states(&self, hashes: &[&str]) -> Result<Vec<TransactionState>, CommunexError> {
+        let mut batch = BatchRequest::new();
+        for hash in hashes {
+            batch.add_request("transaction/state", to_params(&TxHashParams { hash })?);
+        }
+        batch.validate()?;
+
+        let response = self.rpc_client.batch_request(batch).await?;
+        if let Some(error) = response.errors.into_iter().next() {
+            return Err(CommunexError::RpcError {
+                code: error.code,
+                message: error.message,
+            });
+        }
+        if response.successes.len() != hashes.len() {
+            return Err(CommunexError::MalformedResponse(format!(
+                "Expected {} transaction states, got {}",
+                hashes.len(),
+                response.successes.len()
+            )));
+        }
+
+        hashes.iter()
+            .zip(response.successes.iter())
+            .map(|(hash, state)| {
+                self.check_fields("transaction/state", state, TRANSACTION_STATE_FIELDS)?;
+                parse_transaction_state(hash, state)
+            })
+            .collect()
+    }
+
     pub async fn wait_for_transaction(&self, tx_hash: &str, timeout: Duration) -> Result<TransactionState, CommunexError> {
+        self.wait_for_confirmations(tx_hash, 1, timeout).await
+    }
+
+    /// Like `wait_for_transaction`, but returns `CommunexError::Cancelled` as
+    /// soon as `cancel` is triggered, abandoning any in-flight poll
+    pub async fn wait_for_transaction_cancellable(
+        &self,
+        tx_hash: &str,
+        timeout: Duration,
+        cancel: Option<CancellationToken>,
+    ) -> Result<TransactionState, CommunexError> {
+        let Some(cancel) = cancel else {
+            return self.wait_for_transaction(tx_hash, timeout).await;
+        };
+
+        let wait = Box::pin(self.wait_for_transaction(tx_hash, timeout));
+        let result = match future::select(Box::pin(cancel.cancelled()), wait).await {
+            Either::Left(_) => Err(CommunexError::Cancelled),
+            Either::Right((result, _)) => result,
+        };
+        result
+    }
+
+    /// Poll until the transaction fails or succeeds with at least
+    /// `required_confirmations` confirmations. Returns
+    /// `CommunexError::TransactionReorged` if the transaction drops out of a
+    /// block it was previously seen in.
+    pub async fn wait_for_confirmations(
+        &self,
+        tx_hash: &str,
+        required_confirmations: u64,
+        timeout: Duration,
+    ) -> Result<TransactionState, CommunexError> {
         let start_time = Instant::now();
+        let mut seen_block = None;
+        let mut attempt = 0;
         
         while start_time.elapsed() < timeout {
             let state = self.get_transaction_state(tx_hash).await?;
+
+            match (seen_block, state.block_num) {
+                (Some(block), None) => {
+                    warn!("Transaction {} was removed from block {}", tx_hash, block);
+                    return Err(CommunexError::TransactionReorged(
+                        format!("{} is no longer included in block {}", tx_hash, block)
+                    ));
+                }
+                (_, Some(block)) => seen_block = Some(block),
+                (None, None) => {}
+            }
             
             match state.state {
-                Txstate::Success | Txstate::Failed => return Ok(state),
+                TxStatus::Failed => return Ok(state),
+                TxStatus::Success if state.confirmations >= required_confirmations => return Ok(state),
                 _ => {
-                    tokio::time::sleep(Duration::from_secs(2)).await;
-                    continue;
+                    self.runtime.sleep(self.poll_backoff.delay(attempt)).await;
+                    attempt += 1;
                 }
             }
         }
@@ -342,7 +737,77 @@ impl WalletClient {
         Err(CommunexError::RequestTimeout("Transaction wait timeout".into()))
     }
 
-    pub async fn batch_transfer(&self, transfers: Vec<TransferRequest>) -> Result<BatchTransferResult, CommunexError> {
+    /// Stream the state of a transaction each time its status, block or
+    /// confirmations change, polling like `wait_for_transaction`. The stream
+    /// ends after the first terminal state, or after yielding an error if a
+    /// poll fails or `timeout` passes first.
+    pub fn transaction_updates(
+        &self,
+        tx_hash: &str,
+        timeout: Duration,
+    ) -> BoxStream<'static, Result<TransactionState, CommunexError>> {
+        struct Polling {
+            client: WalletClient,
+            tx_hash: String,
+            deadline: Instant,
+            attempt: u32,
+            last: Option<(TxStatus, Option<u64>, u64)>,
+        }
+
+        let state = Some(Polling {
+            client: self.clone(),
+            tx_hash: tx_hash.to_string(),
+            deadline: Instant::now() + timeout,
+            attempt: 0,
+            last: None,
+        });
+        stream::unfold(state, |state| async move {
+            let mut polling = state?;
+            loop {
+                if polling.attempt > 0 {
+                    if Instant::now() >= polling.deadline {
+                        return Some((Err(CommunexError::RequestTimeout("Transaction wait timeout".into())), None));
+                    }
+                    polling.client.runtime.sleep(polling.client.poll_backoff.delay(polling.attempt - 1)).await;
+                }
+                polling.attempt += 1;
+
+                let tx = match polling.client.get_transaction_state(&polling.tx_hash).await {
+                    Ok(tx) => tx,
+                    Err(e) => return Some((Err(e), None)),
+                };
+                let current = (tx.state, tx.block_num, tx.confirmations);
+                if polling.last == Some(current) {
+                    continue;
+                }
+                polling.last = Some(current);
+
+                let next = (!tx.state.is_terminal()).then_some(polling);
+                return Some((Ok(tx), next));
+            }
+        })
+        .boxed()
+    }
+
+    /// Poll until every transaction in `hashes` has succeeded or failed
+    pub async fn wait_for_transactions(&self, hashes: &[&str], timeout: Duration) -> Result<Vec<TransactionState>, CommunexError> {
+        let start_time = Instant::now();
+        let mut attempt = 0;
+
+        while start_time.elapsed() < timeout {
+            let states = self.get_transaction_states(hashes).await?;
+
+            if states.iter().all(|s| s.state.is_terminal()) {
+                return Ok(states);
+            }
+            self.runtime.sleep(self.poll_backoff.delay(attempt)).await;
+            attempt += 1;
+        }
+
+        Err(CommunexError::RequestTimeout("Transaction wait timeout".into()))
+    }
+
+    fn validate_batch(&self, transfers: &[TransferRequest]) -> Result<(), CommunexError> {
         // Validate batch size
         if transfers.is_empty() {
             return Err(CommunexError::ValidationError("Transfer list cannot be empty".into()));
@@ -357,13 +822,42 @@ impl WalletClient {
         for transfer in transfers.iter() {
             self.validate_transfer(transfer)?;
         }
+        Ok(())
+    }
 
-        let params = json!({
-            "transfers": transfers
-        });
+    /// Total fee the node estimates for submitting `transfers` as one batch
+    pub async fn estimate_batch_fee(&self, transfers: &[TransferRequest]) -> Result<u64, CommunexError> {
+        self.validate_batch(transfers)?;
+
+        let params = to_params(&BatchTransferParams { transfers })?;
+        let response = self.rpc_client.request_with_path("batch_transfer/estimate_fee", params).await?;
+
+        let fees = response.get("fees")
+            .and_then(|fees| fees.as_array())
+            .ok_or_else(|| CommunexError::MalformedResponse("Fee estimate is missing 'fees'".into()))?;
+        if fees.len() != transfers.len() {
+            return Err(CommunexError::MalformedResponse(format!(
+                "Expected {} fees, got {}",
+                transfers.len(),
+                fees.len()
+            )));
+        }
+
+        fees.iter().try_fold(0u64, |total, fee| {
+            let fee = fee.as_u64()
+                .ok_or_else(|| CommunexError::MalformedResponse(format!("Invalid fee: {}", fee)))?;
+            total.checked_add(fee)
+                .ok_or_else(|| CommunexError::InvalidAmount("Total batch fee overflows u64".into()))
+        })
+    }
+
+    pub async fn batch_transfer(&self, transfers: Vec<TransferRequest>) -> Result<BatchTransferResult, CommunexError> {
+        self.validate_batch(&transfers)?;
+
+        let params = to_params(&BatchTransferParams { transfers: &transfers })?;
 
         let response = self.rpc_client
-            .request("batch_transfer", params)
+            .submit("batch_transfer", params)
             .await
             .map_err(|e| match e {
                 CommunexError::RequestTimeout(_) => 
@@ -371,10 +865,50 @@ impl WalletClient {
                 _ => e
             })?;
 
-        serde_json::from_value(response)
-            .map_err(|e| CommunexError::ParseError(
-                format!("Failed to parse batch transfer response: {}", e)
-            ))
+        parse_response("batch_transfer", response)
+    }
+
+    /// Pay several recipients from `from` in a single transaction, so either
+    /// every output is applied or none is, and wait for it to be confirmed
+    pub async fn transfer_multi(
+        &self,
+        from: &str,
+        outputs: Vec<(String, u64)>,
+        denom: &str,
+    ) -> Result<TransactionState, CommunexError> {
+        if outputs.is_empty() {
+            return Err(CommunexError::ValidationError("Output list cannot be empty".into()));
+        }
+        if outputs.len() > MAX_BATCH_SIZE {
+            return Err(CommunexError::ValidationError(
+                format!("Output count exceeds maximum limit of {}", MAX_BATCH_SIZE)
+            ));
+        }
+
+        for (to, amount) in &outputs {
+            self.validate_transfer(&TransferRequest {
+                from: from.to_string(),
+                to: to.clone(),
+                amount: *amount,
+                denom: denom.to_string(),
+            })?;
+        }
+
+        let params = to_params(&TransferMultipleParams {
+            from,
+            outputs: outputs.iter()
+                .map(|(to, amount)| TransferOutput { to, amount: *amount })
+                .collect(),
+            denom,
+        })?;
+
+        let response = self.rpc_client.submit_with_path("transfer_multiple", params).await?;
+
+        let tx_hash = response.get("hash")
+            .and_then(|v| v.as_str())
+            .ok_or(CommunexError::MalformedResponse("Missing transaction hash".into()))?;
+
+        self.wait_for_transaction(tx_hash, Duration::from_secs(30)).await
     }
 
     fn validate_transfer(&self, transfer: &TransferRequest) -> Result<(), CommunexError> {
@@ -398,10 +932,10 @@ impl WalletClient {
         }
 
         // Validate denomination
-        if !VALID_DENOMS.contains(&transfer.denom.as_str()) {
+        if !self.denoms.contains(&transfer.denom) {
             return Err(CommunexError::ValidationError(
                 format!("Invalid denomination: {}. Valid options are: {:?}", 
-                    transfer.denom, VALID_DENOMS)
+                    transfer.denom, self.denoms.denoms())
             ));
         }
 
@@ -409,9 +943,75 @@ impl WalletClient {
     }
 }
 
+/// Convert a unix timestamp in seconds from a node response. Missing and
+/// out-of-range values are reported as `MalformedResponse`.
+pub(crate) fn parse_unix_ts(value: Option<i64>) -> Result<DateTime<Utc>, CommunexError> {
+    parse_optional_unix_ts(value)?
+        .ok_or_else(|| CommunexError::MalformedResponse("Missing timestamp".into()))
+}
+
+/// Like `parse_unix_ts` for fields a node may omit: a missing value is
+/// `None`, and only an out-of-range value is an error.
+pub(crate) fn parse_optional_unix_ts(value: Option<i64>) -> Result<Option<DateTime<Utc>>, CommunexError> {
+    value.map(|ts| {
+        DateTime::<Utc>::from_timestamp(ts, 0)
+            .ok_or_else(|| CommunexError::MalformedResponse(format!("Invalid timestamp: {}", ts)))
+    })
+    .transpose()
+}
+
+fn parse_transaction_state(tx_hash: &str, response: &Value) -> Result<TransactionState, CommunexError> {
+    Ok(TransactionState {
+        hash: tx_hash.to_string(),
+        block_num: response.get("block_num")
+            .and_then(|v| v.as_u64()),
+        confirmations: response.get("confirmations")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0),
+        state: parse_tx_status(response.get("state"))?,
+        timestamp: parse_optional_unix_ts(response.get("timestamp").and_then(|v| v.as_i64()))?,
+        error: response.get("error")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+    })
+}
+
+/// Parse a transaction `state`, accepting the legacy spellings `TxStatus`
+/// aliases. A missing or unknown state is a `MalformedResponse`, so a new
+/// state added by the node is never mistaken for a failure or a miss.
+fn parse_tx_status(state: Option<&Value>) -> Result<TxStatus, CommunexError> {
+    let state = state.ok_or_else(|| CommunexError::MalformedResponse("Missing transaction state".into()))?;
+    serde_json::from_value(state.clone())
+        .map_err(|_| CommunexError::MalformedResponse(format!("Unknown transaction state: {}", state)))
+}
+
+/// Map raw `balance_subscribe` updates to free balances
+#[cfg(not(target_arch = "wasm32"))]
+fn free_balance_updates(
+    updates: impl Stream<Item = Result<Value, CommunexError>> + Send + 'static,
+) -> impl Stream<Item = Result<u64, CommunexError>> + Send + 'static {
+    updates.map(|update| {
+        update?.get("free")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| CommunexError::ParseError("balance_subscribe update is missing 'free'".into()))
+    })
+}
+
+/// Derive the WebSocket endpoint served alongside an HTTP RPC endpoint
+fn ws_url_for(url: &str) -> String {
+    if let Some(rest) = url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        url.to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
 
     #[test]
     fn test_transfer_request_new() {
@@ -426,4 +1026,73 @@ mod tests {
         assert_eq!(request.amount, 1000);
         assert_eq!(request.denom, "COMAI");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_transfer_request_from_balance() {
+        let denoms = DenomRegistry::default().with_denom("STAKE", 6);
+        let balance = Balance::new_with_registry("2500", "STAKE", &denoms).unwrap();
+        let request = TransferRequest::from_balance("cmx1abcd123", "cmx1efgh456", balance).unwrap();
+
+        assert_eq!(request.from, "cmx1abcd123");
+        assert_eq!(request.to, "cmx1efgh456");
+        assert_eq!(request.amount, 2500);
+        assert_eq!(request.denom, "STAKE");
+    }
+
+    #[test]
+    fn test_tx_status_accepts_legacy_values() {
+        let cases = [
+            ("success", TxStatus::Success),
+            ("failed", TxStatus::Failed),
+            ("pending", TxStatus::Pending),
+            ("notfound", TxStatus::NotFound),
+            ("not_found", TxStatus::NotFound),
+        ];
+
+        for (wire, expected) in cases {
+            let status: TxStatus = serde_json::from_value(json!(wire)).unwrap();
+            assert_eq!(status, expected, "decoding {:?}", wire);
+        }
+
+        assert_eq!(serde_json::to_value(TxStatus::NotFound).unwrap(), json!("notfound"));
+        assert!(serde_json::from_value::<TxStatus>(json!("confirmed")).is_err());
+    }
+
+    #[test]
+    fn test_transaction_parsers_share_tx_status_decoding() {
+        let state = parse_transaction_state("0xabc", &json!({ "state": "not_found" })).unwrap();
+        assert_eq!(state.state, TxStatus::NotFound);
+        assert_eq!(parse_tx_status(Some(&json!("notFound"))).unwrap(), TxStatus::NotFound);
+
+        for state in [Some(json!("confirmed")), Some(json!(3)), None] {
+            assert!(matches!(
+                parse_tx_status(state.as_ref()),
+                Err(CommunexError::MalformedResponse(_))
+            ), "parsing {:?}", state);
+        }
+    }
+
+    #[test]
+    fn test_tx_status_is_terminal() {
+        assert!(TxStatus::Success.is_terminal());
+        assert!(TxStatus::Failed.is_terminal());
+        assert!(!TxStatus::Pending.is_terminal());
+        assert!(!TxStatus::NotFound.is_terminal());
+    }
+
+    #[test]
+    fn test_tx_status_display() {
+        assert_eq!(TxStatus::Pending.to_string(), "pending");
+        assert_eq!(TxStatus::Success.to_string(), "success");
+        assert_eq!(TxStatus::Failed.to_string(), "failed");
+        assert_eq!(TxStatus::NotFound.to_string(), "notfound");
+    }
+
+    #[test]
+    fn test_poll_backoff_doubles_up_to_cap() {
+        let backoff = PollBackoff::default();
+        let delays: Vec<_> = (0..7).map(|attempt| backoff.delay(attempt).as_millis()).collect();
+        assert_eq!(delays, vec![500, 1000, 2000, 4000, 8000, 8000, 8000]);
+        assert_eq!(backoff.delay(u32::MAX), Duration::from_secs(8));
+    }
+}
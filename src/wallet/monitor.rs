@@ -0,0 +1,121 @@
+//! Balance threshold alerts for monitoring bots.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use crate::error::CommunexError;
+use super::WalletClient;
+
+/// Which way a balance crossed its threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CrossingDirection {
+    /// The balance dropped below the threshold
+    Below,
+    /// The balance rose back to or above the threshold
+    Above,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceAlert {
+    pub address: String,
+    pub balance: u64,
+    pub threshold: u64,
+    pub direction: CrossingDirection,
+}
+
+/// Polls the free balances of a set of addresses and raises an alert each
+/// time one crosses its threshold. An address that is already below its
+/// threshold on the first poll alerts immediately.
+pub struct BalanceMonitor {
+    client: WalletClient,
+    thresholds: HashMap<String, u64>,
+    poll_interval: Duration,
+}
+
+impl BalanceMonitor {
+    pub fn new(client: WalletClient, poll_interval: Duration) -> Self {
+        Self {
+            client,
+            thresholds: HashMap::new(),
+            poll_interval,
+        }
+    }
+
+    /// Alert when the balance of `address` crosses `threshold`
+    pub fn with_threshold(mut self, address: impl Into<String>, threshold: u64) -> Self {
+        self.thresholds.insert(address.into(), threshold);
+        self
+    }
+
+    /// Stream alerts until dropped. Failed polls are yielded as errors and
+    /// polling continues.
+    pub fn alerts(self) -> BoxStream<'static, Result<BalanceAlert, CommunexError>> {
+        let mut addresses: Vec<String> = self.thresholds.keys().cloned().collect();
+        addresses.sort();
+        let state = MonitorState {
+            monitor: self,
+            addresses,
+            below: HashMap::new(),
+            pending: VecDeque::new(),
+            started: false,
+        };
+
+        stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(item) = state.pending.pop_front() {
+                    return Some((item, state));
+                }
+                if state.addresses.is_empty() {
+                    return None;
+                }
+                if state.started {
+                    state.monitor.client.runtime.sleep(state.monitor.poll_interval).await;
+                }
+                state.started = true;
+                state.poll().await;
+            }
+        })
+        .boxed()
+    }
+}
+
+struct MonitorState {
+    monitor: BalanceMonitor,
+    addresses: Vec<String>,
+    /// Whether each address was below its threshold at the last poll
+    below: HashMap<String, bool>,
+    pending: VecDeque<Result<BalanceAlert, CommunexError>>,
+    started: bool,
+}
+
+impl MonitorState {
+    async fn poll(&mut self) {
+        let addresses: Vec<&str> = self.addresses.iter().map(String::as_str).collect();
+        let balances = match self.monitor.client.get_free_balances(&addresses).await {
+            Ok(balances) => balances,
+            Err(e) => {
+                self.pending.push_back(Err(e));
+                return;
+            }
+        };
+
+        for (address, balance) in self.addresses.iter().zip(balances) {
+            let threshold = self.monitor.thresholds[address];
+            let is_below = balance < threshold;
+            // An unknown previous state counts as above, so a low first reading alerts
+            let was_below = self.below.insert(address.clone(), is_below).unwrap_or(false);
+            if is_below == was_below {
+                continue;
+            }
+
+            self.pending.push_back(Ok(BalanceAlert {
+                address: address.clone(),
+                balance,
+                threshold,
+                direction: if is_below { CrossingDirection::Below } else { CrossingDirection::Above },
+            }));
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! Everything an address holds, gathered in one call.
+
+use serde::{Deserialize, Serialize};
+use crate::error::CommunexError;
+use super::WalletClient;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Portfolio {
+    pub address: String,
+    pub free: u64,
+    pub reserved: u64,
+    pub staked: u64,
+    /// Staking rewards accrued but not yet claimed
+    pub pending_rewards: u64,
+}
+
+impl Portfolio {
+    /// Sum of all holdings, saturating at `u64::MAX`
+    pub fn total(&self) -> u64 {
+        self.free
+            .saturating_add(self.reserved)
+            .saturating_add(self.staked)
+            .saturating_add(self.pending_rewards)
+    }
+}
+
+impl WalletClient {
+    /// Fetch the balances, stake and pending rewards of `address`
+    /// concurrently. Fails if any of the three requests fails.
+    pub async fn get_portfolio(&self, address: &str) -> Result<Portfolio, CommunexError> {
+        let (balances, staked, staking) = tokio::try_join!(
+            self.get_all_balances(address),
+            self.get_staked_balance(address),
+            self.get_staking_info(address),
+        )?;
+
+        Ok(Portfolio {
+            address: address.to_string(),
+            free: balances.free,
+            reserved: balances.reserved,
+            staked,
+            pending_rewards: staking.rewards_available,
+        })
+    }
+}
@@ -1,8 +1,8 @@
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
 use crate::error::CommunexError;
-use crate::wallet::{WalletClient, TransactionState};
-use serde_json::json;
+use crate::wallet::{WalletClient, TransactionState, parse_optional_unix_ts};
+use crate::rpc::params::{to_params, AddressParams, ClaimParams, StakeParams, UnstakeParams};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StakeRequest {
@@ -23,11 +23,39 @@ pub struct StakingInfo {
     pub address: String,
     pub total_staked: u64,
     pub rewards_available: u64,
-    #[serde(with = "chrono::serde::ts_seconds")]
-    pub last_claim_time: DateTime<Utc>,
+    /// When rewards were last claimed; `None` if they never have been or the
+    /// node does not report it
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    pub last_claim_time: Option<DateTime<Utc>>,
     pub denom: String,
 }
 
+/// Fields of a `staking/info` response
+const STAKING_INFO_FIELDS: &[&str] = &["total_staked", "rewards_available", "last_claim_time", "denom"];
+
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+
+impl StakingInfo {
+    /// Simple annual rate implied by the rewards accrued since the last
+    /// claim, as a fraction of the stake (`0.12` is 12%). `None` when nothing
+    /// is staked, no rewards have accrued, the last claim time is unknown or
+    /// `now` is not after the last claim.
+    pub fn estimated_apr(&self, now: DateTime<Utc>) -> Option<f64> {
+        let elapsed = (now - self.last_claim_time?).num_milliseconds();
+        if self.total_staked == 0 || self.rewards_available == 0 || elapsed <= 0 {
+            return None;
+        }
+
+        let rate = self.rewards_available as f64 / self.total_staked as f64;
+        Some(rate * SECONDS_PER_YEAR * 1000.0 / elapsed as f64)
+    }
+}
+
+/// A random key identifying one logical operation across retries
+pub fn new_idempotency_key() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
+
 impl WalletClient {
     pub async fn stake(&self, request: StakeRequest) -> Result<TransactionState, CommunexError> {
         if !request.from.starts_with("cmx1") {
@@ -37,13 +65,13 @@ impl WalletClient {
             });
         }
 
-        let params = json!({
-            "from": request.from,
-            "amount": request.amount,
-            "denom": request.denom,
-        });
+        let params = to_params(&StakeParams {
+            from: &request.from,
+            amount: request.amount,
+            denom: &request.denom,
+        })?;
 
-        let response = self.rpc_client.request_with_path("staking/stake", params).await?;
+        let response = self.rpc_client.submit_with_path("staking/stake", params).await?;
         
         // Get transaction hash from response
         let tx_hash = response.get("hash")
@@ -62,13 +90,13 @@ impl WalletClient {
             });
         }
 
-        let params = json!({
-            "from": request.from,
-            "amount": request.amount,
-            "denom": request.denom,
-        });
+        let params = to_params(&UnstakeParams {
+            from: &request.from,
+            amount: request.amount,
+            denom: &request.denom,
+        })?;
 
-        let response = self.rpc_client.request_with_path("staking/unstake", params).await?;
+        let response = self.rpc_client.submit_with_path("staking/unstake", params).await?;
         
         let tx_hash = response.get("hash")
             .and_then(|v| v.as_str())
@@ -77,7 +105,27 @@ impl WalletClient {
         self.wait_for_transaction(tx_hash, std::time::Duration::from_secs(30)).await
     }
 
+    /// Claim the staking rewards of `address`. The idempotency key is kept
+    /// per address until the claim settles: if a call fails in a way that
+    /// may have left the claim applied, the next call for the same address
+    /// on this client (or its clones) sends the same key, so the node pays
+    /// out at most once.
     pub async fn claim_rewards(&self, address: &str) -> Result<TransactionState, CommunexError> {
+        let key = self.pending_claim_keys.lock().unwrap()
+            .entry(address.to_string())
+            .or_insert_with(new_idempotency_key)
+            .clone();
+
+        let result = self.claim_rewards_with_key(address, &key).await;
+        if !matches!(&result, Err(e) if e.is_retryable()) {
+            self.pending_claim_keys.lock().unwrap().remove(address);
+        }
+        result
+    }
+
+    /// Claim the staking rewards of `address`. Claims sent with the same
+    /// `idempotency_key` within the node's dedup window are applied once.
+    pub async fn claim_rewards_with_key(&self, address: &str, idempotency_key: &str) -> Result<TransactionState, CommunexError> {
         if !address.starts_with("cmx1") {
             return Err(CommunexError::RpcError {
                 code: -32001,
@@ -85,9 +133,7 @@ impl WalletClient {
             });
         }
 
-        let params = json!({
-            "address": address,
-        });
+        let params = to_params(&ClaimParams { address, idempotency_key })?;
 
         let response = self.rpc_client.request_with_path("staking/claim", params).await?;
         
@@ -106,12 +152,11 @@ impl WalletClient {
             });
         }
 
-        let params = json!({
-            "address": address,
-        });
+        let params = to_params(&AddressParams { address })?;
 
         match self.rpc_client.request_with_path("staking/info", params).await {
             Ok(response) => {
+                self.check_fields("staking/info", &response, STAKING_INFO_FIELDS)?;
                 Ok(StakingInfo {
                     address: address.to_string(),
                     total_staked: response.get("total_staked")
@@ -120,11 +165,7 @@ impl WalletClient {
                     rewards_available: response.get("rewards_available")
                         .and_then(|v| v.as_u64())
                         .unwrap_or(0),
-                    last_claim_time: response.get("last_claim_time")
-                        .and_then(|v| v.as_i64())
-                        .map(|ts| DateTime::<Utc>::from_timestamp(ts, 0))
-                        .flatten()
-                        .unwrap_or_else(|| Utc::now()),
+                    last_claim_time: parse_optional_unix_ts(response.get("last_claim_time").and_then(|v| v.as_i64()))?,
                     denom: response.get("denom")
                         .and_then(|v| v.as_str())
                         .unwrap_or("COMAI")
@@ -134,4 +175,47 @@ impl WalletClient {
             Err(e) => Err(e)
         }
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn staking_info(total_staked: u64, rewards_available: u64, last_claim_time: DateTime<Utc>) -> StakingInfo {
+        StakingInfo {
+            address: "cmx1abcd123".into(),
+            total_staked,
+            rewards_available,
+            last_claim_time: Some(last_claim_time),
+            denom: "COMAI".into(),
+        }
+    }
+
+    #[test]
+    fn test_estimated_apr() {
+        let now = Utc::now();
+
+        // 1% over a quarter of a year is 4% a year
+        let info = staking_info(10_000, 100, now - Duration::seconds((SECONDS_PER_YEAR / 4.0) as i64));
+        let apr = info.estimated_apr(now).unwrap();
+        assert!((apr - 0.04).abs() < 1e-9, "{}", apr);
+
+        let info = staking_info(1_000, 1_000, now - Duration::days(365));
+        assert!((info.estimated_apr(now).unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_estimated_apr_degenerate_inputs() {
+        let now = Utc::now();
+        let last_month = now - Duration::days(30);
+
+        assert_eq!(staking_info(0, 100, last_month).estimated_apr(now), None);
+        assert_eq!(staking_info(10_000, 0, last_month).estimated_apr(now), None);
+        assert_eq!(staking_info(10_000, 100, now).estimated_apr(now), None);
+        assert_eq!(staking_info(10_000, 100, now + Duration::days(1)).estimated_apr(now), None);
+
+        let never_claimed = StakingInfo { last_claim_time: None, ..staking_info(10_000, 100, last_month) };
+        assert_eq!(never_claimed.estimated_apr(now), None);
+    }
+}
@@ -0,0 +1,115 @@
+use serde::{Serialize, Deserialize};
+use crate::error::CommunexError;
+use crate::types::Netuid;
+use crate::wallet::{WalletClient, TransactionState};
+use crate::rpc::params::{to_params, RegisterModuleParams, SetWeightsParams};
+
+/// Maximum length of a module name accepted by the chain
+const MAX_MODULE_NAME_LENGTH: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterModuleRequest {
+    /// Address of the account signing the registration
+    pub from: String,
+    pub netuid: u16,
+    pub name: String,
+    /// Network address the module is served on, as `host:port`
+    pub address: String,
+}
+
+impl WalletClient {
+    pub async fn register_module(&self, request: RegisterModuleRequest) -> Result<TransactionState, CommunexError> {
+        if !request.from.starts_with("cmx1") {
+            return Err(CommunexError::RpcError {
+                code: -32001,
+                message: "Invalid address".into(),
+            });
+        }
+
+        let netuid = Netuid::new(request.netuid)?;
+        validate_module_name(&request.name)?;
+        validate_module_address(&request.address)?;
+
+        let params = to_params(&RegisterModuleParams {
+            from: &request.from,
+            netuid,
+            name: &request.name,
+            address: &request.address,
+        })?;
+
+        let response = self.rpc_client.submit_with_path("subnet/register", params).await?;
+
+        let tx_hash = response.get("hash")
+            .and_then(|v| v.as_str())
+            .ok_or(CommunexError::MalformedResponse("Missing transaction hash".into()))?;
+
+        self.wait_for_transaction(tx_hash, std::time::Duration::from_secs(30)).await
+    }
+
+    pub async fn set_weights(
+        &self,
+        from: &str,
+        netuid: u16,
+        uids: Vec<u16>,
+        weights: Vec<u16>,
+    ) -> Result<TransactionState, CommunexError> {
+        if !from.starts_with("cmx1") {
+            return Err(CommunexError::RpcError {
+                code: -32001,
+                message: "Invalid address".into(),
+            });
+        }
+
+        let netuid = Netuid::new(netuid)?;
+
+        if weights.is_empty() {
+            return Err(CommunexError::ValidationError("Weights cannot be empty".into()));
+        }
+        if uids.len() != weights.len() {
+            return Err(CommunexError::ValidationError(
+                format!("Mismatched uids and weights: {} uids, {} weights", uids.len(), weights.len())
+            ));
+        }
+
+        let params = to_params(&SetWeightsParams {
+            from,
+            netuid,
+            uids: &uids,
+            weights: &weights,
+        })?;
+
+        let response = self.rpc_client.submit_with_path("subnet/set_weights", params).await?;
+
+        let tx_hash = response.get("hash")
+            .and_then(|v| v.as_str())
+            .ok_or(CommunexError::MalformedResponse("Missing transaction hash".into()))?;
+
+        self.wait_for_transaction(tx_hash, std::time::Duration::from_secs(30)).await
+    }
+}
+
+fn validate_module_name(name: &str) -> Result<(), CommunexError> {
+    if name.is_empty() {
+        return Err(CommunexError::ValidationError("Module name cannot be empty".into()));
+    }
+    if name.len() > MAX_MODULE_NAME_LENGTH {
+        return Err(CommunexError::ValidationError(
+            format!("Module name exceeds maximum length of {}", MAX_MODULE_NAME_LENGTH)
+        ));
+    }
+    Ok(())
+}
+
+fn validate_module_address(address: &str) -> Result<(), CommunexError> {
+    let valid = match address.rsplit_once(':') {
+        Some((host, port)) => !host.is_empty() && port.parse::<u16>().is_ok(),
+        None => false,
+    };
+
+    if !valid {
+        return Err(CommunexError::ValidationError(
+            format!("Invalid module address format: {}. Expected host:port", address)
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use comx_api::CommunexError;
+use comx_api::wallet::{
+    monitor::{BalanceAlert, BalanceMonitor, CrossingDirection},
+    WalletClient,
+};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::time::Duration;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, Request, ResponseTemplate,
+};
+
+/// Answer a batch of `balance/free` requests with `free` for every address
+fn balances_response(free: &'static [(&'static str, u64)]) -> impl Fn(&Request) -> ResponseTemplate {
+    move |request: &Request| {
+        let batch: Vec<Value> = request.body_json().unwrap();
+        let results: Vec<Value> = batch.iter()
+            .map(|item| {
+                let address = item["params"]["address"].as_str().unwrap();
+                let (_, balance) = free.iter().find(|(a, _)| *a == address).unwrap();
+                json!({ "jsonrpc": "2.0", "id": item["id"], "result": { "free": balance } })
+            })
+            .collect();
+        ResponseTemplate::new(200).set_body_json(results)
+    }
+}
+
+#[tokio::test]
+async fn test_balance_monitor_alerts_on_crossing() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(balances_response(&[("cmx1low", 500), ("cmx1high", 5000)]))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(balances_response(&[("cmx1low", 1500), ("cmx1high", 5000)]))
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    let mut alerts = BalanceMonitor::new(client, Duration::from_millis(20))
+        .with_threshold("cmx1low", 1000)
+        .with_threshold("cmx1high", 1000)
+        .alerts();
+
+    // Already below on the first poll
+    let alert = tokio::time::timeout(Duration::from_secs(2), alerts.next()).await.unwrap().unwrap().unwrap();
+    assert_eq!(alert, BalanceAlert {
+        address: "cmx1low".into(),
+        balance: 500,
+        threshold: 1000,
+        direction: CrossingDirection::Below,
+    });
+
+    // Topped up on the next poll; the address that stayed above never alerts
+    let alert = tokio::time::timeout(Duration::from_secs(2), alerts.next()).await.unwrap().unwrap().unwrap();
+    assert_eq!(alert.address, "cmx1low");
+    assert_eq!(alert.balance, 1500);
+    assert_eq!(alert.direction, CrossingDirection::Above);
+
+    assert!(tokio::time::timeout(Duration::from_millis(200), alerts.next()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_free_balances_missing_free_is_malformed() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(|request: &Request| {
+            let batch: Vec<Value> = request.body_json().unwrap();
+            let results: Vec<Value> = batch.iter()
+                .map(|item| match item["params"]["address"].as_str().unwrap() {
+                    "cmx1good" => json!({ "jsonrpc": "2.0", "id": item["id"], "result": { "free": 10 } }),
+                    _ => json!({ "jsonrpc": "2.0", "id": item["id"], "result": {} }),
+                })
+                .collect();
+            ResponseTemplate::new(200).set_body_json(results)
+        })
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    let result = client.get_free_balances(&["cmx1good", "cmx1bad"]).await;
+    match result {
+        Err(CommunexError::MalformedResponse(message)) => assert!(message.contains("cmx1bad"), "{}", message),
+        other => panic!("expected MalformedResponse, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_free_balances_over_batch_limit() -> Result<(), CommunexError> {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(|request: &Request| {
+            let batch: Vec<Value> = request.body_json().unwrap();
+            let results: Vec<Value> = batch.iter()
+                .map(|item| {
+                    let address = item["params"]["address"].as_str().unwrap();
+                    let free: u64 = address.trim_start_matches("cmx1addr").parse().unwrap();
+                    json!({ "jsonrpc": "2.0", "id": item["id"], "result": { "free": free } })
+                })
+                .collect();
+            ResponseTemplate::new(200).set_body_json(results)
+        })
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let addresses: Vec<String> = (0..150).map(|i| format!("cmx1addr{}", i)).collect();
+    let addresses: Vec<&str> = addresses.iter().map(String::as_str).collect();
+
+    let client = WalletClient::new(&mock_server.uri());
+    let balances = client.get_free_balances(&addresses).await?;
+    assert_eq!(balances, (0..150).collect::<Vec<u64>>());
+    Ok(())
+}
@@ -1,26 +1,29 @@
 use comx_api::{
-    wallet::{WalletClient, TransferRequest, TransactionStatus},
+    wallet::{WalletClient, TransferRequest, TxStatus},
     error::CommunexError,
 };
 use wiremock::{
     Mock, 
     MockServer,
     ResponseTemplate,
-    matchers::{method, path, body_json}
+    matchers::{method, path, body_partial_json}
 };
 use serde_json::json;
 use tokio::time::timeout as tokio_timeout;
 use std::time::Duration;
 
+#[path = "common/mod.rs"]
+mod common;
+use common::rpc_response;
+
 #[tokio::test]
 async fn test_batch_transfer_success() {
     let mock_server = MockServer::start().await;
     
     Mock::given(method("POST"))
         .and(path("/"))
-        .and(body_json(json!({
+        .and(body_partial_json(json!({
             "jsonrpc": "2.0",
-            "id": 1,
             "method": "batch_transfer",
             "params": {
                 "transfers": [
@@ -39,8 +42,7 @@ async fn test_batch_transfer_success() {
                 ]
             }
         })))
-        .respond_with(ResponseTemplate::new(200)
-            .set_body_json(json!({
+        .respond_with(rpc_response(json!({
                 "jsonrpc": "2.0",
                 "id": 1,
                 "result": {
@@ -82,8 +84,7 @@ async fn test_batch_transfer_server_error() {
     
     Mock::given(method("POST"))
         .and(path("/"))
-        .respond_with(ResponseTemplate::new(200)
-            .set_body_json(json!({
+        .respond_with(rpc_response(json!({
                 "jsonrpc": "2.0",
                 "id": 1,
                 "result": {
@@ -119,8 +120,8 @@ async fn test_batch_transfer_server_error() {
     assert!(result.is_ok());
     
     let batch_result = result.unwrap();
-    assert_eq!(batch_result.transactions[0].status, TransactionStatus::Success);
-    assert_eq!(batch_result.transactions[1].status, TransactionStatus::Failed);
+    assert_eq!(batch_result.transactions[0].status, TxStatus::Success);
+    assert_eq!(batch_result.transactions[1].status, TxStatus::Failed);
 }
 
 #[tokio::test]
@@ -257,8 +258,7 @@ async fn test_batch_transfer_malformed_response() {
     
     Mock::given(method("POST"))
         .and(path("/"))
-        .respond_with(ResponseTemplate::new(200)
-            .set_body_json(json!({
+        .respond_with(rpc_response(json!({
                 "jsonrpc": "2.0",
                 "id": 1,
                 "result": "invalid_result"
@@ -281,3 +281,124 @@ async fn test_batch_transfer_malformed_response() {
     let result = client.batch_transfer(transfers).await;
     assert!(matches!(result, Err(CommunexError::ParseError(_))));
 }
+
+#[tokio::test]
+async fn test_transfer_multi_single_transaction() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/transfer_multiple"))
+        .and(body_partial_json(json!({
+            "method": "transfer_multiple",
+            "params": {
+                "from": "cmx1sender",
+                "outputs": [
+                    { "to": "cmx1alice", "amount": 100 },
+                    { "to": "cmx1bob", "amount": 200 },
+                    { "to": "cmx1carol", "amount": 300 }
+                ],
+                "denom": "COMAI"
+            }
+        })))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": { "hash": "0xmulti" }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/transaction/state"))
+        .and(body_partial_json(json!({ "params": { "hash": "0xmulti" } })))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "hash": "0xmulti",
+                "state": "success",
+                "confirmations": 1,
+                "block_num": 12345,
+                "timestamp": 1704067200,
+                "error": null
+            }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    let outputs = vec![
+        ("cmx1alice".to_string(), 100),
+        ("cmx1bob".to_string(), 200),
+        ("cmx1carol".to_string(), 300),
+    ];
+
+    let state = client.transfer_multi("cmx1sender", outputs, "COMAI").await.unwrap();
+    assert_eq!(state.hash, "0xmulti");
+    assert_eq!(state.state, TxStatus::Success);
+}
+
+#[tokio::test]
+async fn test_transfer_multi_validates_every_output() {
+    let client = WalletClient::new("http://localhost:1");
+
+    let outputs = vec![
+        ("cmx1alice".to_string(), 100),
+        ("invalid".to_string(), 200),
+    ];
+    let result = client.transfer_multi("cmx1sender", outputs, "COMAI").await;
+    assert!(matches!(result, Err(CommunexError::ValidationError(_))));
+
+    let outputs = vec![("cmx1alice".to_string(), 0)];
+    let result = client.transfer_multi("cmx1sender", outputs, "COMAI").await;
+    assert!(matches!(result, Err(CommunexError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_estimate_batch_fee() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/batch_transfer/estimate_fee"))
+        .and(body_partial_json(json!({
+            "method": "batch_transfer/estimate_fee",
+            "params": {
+                "transfers": [
+                    {"from": "cmx1sender", "to": "cmx1receiver1", "amount": 100},
+                    {"from": "cmx1sender", "to": "cmx1receiver2", "amount": 200}
+                ]
+            }
+        })))
+        .respond_with(rpc_response(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": { "fees": [12, 30] }
+            })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    let transfers = vec![
+        TransferRequest {
+            from: "cmx1sender".into(),
+            to: "cmx1receiver1".into(),
+            amount: 100,
+            denom: "COMAI".into(),
+        },
+        TransferRequest {
+            from: "cmx1sender".into(),
+            to: "cmx1receiver2".into(),
+            amount: 200,
+            denom: "COMAI".into(),
+        },
+    ];
+
+    assert_eq!(client.estimate_batch_fee(&transfers).await.unwrap(), 42);
+    assert!(matches!(
+        client.estimate_batch_fee(&[]).await,
+        Err(CommunexError::ValidationError(_))
+    ));
+}
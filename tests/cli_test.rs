@@ -0,0 +1,85 @@
+#![cfg(feature = "cli")]
+
+use serde_json::json;
+use tokio::process::Command;
+use wiremock::{
+    matchers::{body_partial_json, method, path},
+    Mock, MockServer,
+};
+
+#[path = "common/mod.rs"]
+mod common;
+use common::rpc_response;
+
+#[tokio::test]
+async fn test_cli_balance() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/balance/free"))
+        .and(body_partial_json(json!({ "params": { "address": "cmx1abcd123" } })))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": { "free": 1000000 }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_comx"))
+        .args(["--node", &mock_server.uri(), "balance", "cmx1abcd123"])
+        .output()
+        .await
+        .unwrap();
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), "Balance: 1000000");
+}
+
+#[tokio::test]
+async fn test_cli_reports_errors() {
+    let output = Command::new(env!("CARGO_BIN_EXE_comx"))
+        .args(["--node", "http://localhost:1", "balance", "not-an-address"])
+        .output()
+        .await
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().starts_with("Error:"));
+}
+
+#[tokio::test]
+async fn test_cli_balance_json_output() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/balance/free"))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": { "free": 1000000 }
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_comx"))
+        .args(["--node", &mock_server.uri(), "--output", "json", "balance", "cmx1abcd123"])
+        .output()
+        .await
+        .unwrap();
+
+    assert!(output.status.success());
+    let printed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(printed, json!({ "address": "cmx1abcd123", "free": 1000000 }));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_comx"))
+        .args(["--node", &mock_server.uri(), "--output", "json", "balance", "not-an-address"])
+        .output()
+        .await
+        .unwrap();
+
+    assert!(!output.status.success());
+    let printed: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert!(printed["error"].is_string());
+}
@@ -0,0 +1,14 @@
+// Shared helpers for integration tests
+use serde_json::Value;
+use wiremock::{Request, ResponseTemplate};
+
+/// Responds with the given JSON-RPC body, echoing the id of the incoming request
+pub fn rpc_response(body: Value) -> impl Fn(&Request) -> ResponseTemplate + Send + Sync + 'static {
+    move |request: &Request| {
+        let mut body = body.clone();
+        if let Ok(incoming) = request.body_json::<Value>() {
+            body["id"] = incoming["id"].clone();
+        }
+        ResponseTemplate::new(200).set_body_json(body)
+    }
+}
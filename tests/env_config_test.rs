@@ -0,0 +1,81 @@
+use comx_api::{
+    env::{COMX_HOST, COMX_MAX_RETRIES, COMX_PORT, COMX_TIMEOUT_SECS},
+    error::CommunexError,
+    modules::client::ModuleClientConfig,
+    rpc::RpcClientConfig,
+};
+use serial_test::serial;
+use std::time::Duration;
+
+const VARS: [&str; 4] = [COMX_HOST, COMX_PORT, COMX_TIMEOUT_SECS, COMX_MAX_RETRIES];
+
+fn clear_env() {
+    for var in VARS {
+        std::env::remove_var(var);
+    }
+}
+
+#[test]
+#[serial]
+fn test_config_from_env() -> Result<(), CommunexError> {
+    clear_env();
+    std::env::set_var(COMX_HOST, "http://module.example");
+    std::env::set_var(COMX_PORT, "9000");
+    std::env::set_var(COMX_TIMEOUT_SECS, "12");
+    std::env::set_var(COMX_MAX_RETRIES, "5");
+
+    let module = ModuleClientConfig::from_env()?;
+    let rpc = RpcClientConfig::from_env()?;
+    clear_env();
+
+    assert_eq!(module.host, "http://module.example");
+    assert_eq!(module.port, 9000);
+    assert_eq!(module.timeout, Duration::from_secs(12));
+    assert_eq!(module.max_retries, 5);
+    assert_eq!(rpc.timeout, Duration::from_secs(12));
+    assert_eq!(rpc.max_retries, 5);
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn test_config_from_env_defaults() -> Result<(), CommunexError> {
+    clear_env();
+
+    let module = ModuleClientConfig::from_env()?;
+    let defaults = ModuleClientConfig::default();
+    assert_eq!(module.host, defaults.host);
+    assert_eq!(module.port, defaults.port);
+    assert_eq!(module.timeout, defaults.timeout);
+    assert_eq!(RpcClientConfig::from_env()?.max_retries, RpcClientConfig::default().max_retries);
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn test_config_from_env_adds_scheme_to_bare_host() -> Result<(), CommunexError> {
+    clear_env();
+    std::env::set_var(COMX_HOST, "10.0.0.5");
+
+    let module = ModuleClientConfig::from_env()?;
+    clear_env();
+
+    assert_eq!(module.host, "http://10.0.0.5");
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn test_config_from_env_rejects_bad_values() {
+    for (var, value) in [(COMX_PORT, "70000"), (COMX_TIMEOUT_SECS, "soon"), (COMX_TIMEOUT_SECS, "0"), (COMX_MAX_RETRIES, "-1")] {
+        clear_env();
+        std::env::set_var(var, value);
+        assert!(
+            matches!(ModuleClientConfig::from_env(), Err(CommunexError::ConfigError(_))),
+            "{}={} should be rejected",
+            var,
+            value
+        );
+    }
+    clear_env();
+}
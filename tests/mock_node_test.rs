@@ -0,0 +1,56 @@
+use comx_api::{
+    testing::MockNode,
+    wallet::{WalletClient, TransferRequest, TxStatus},
+    error::CommunexError,
+};
+
+#[tokio::test]
+async fn test_transfer_against_mock_node() -> Result<(), CommunexError> {
+    let node = MockNode::start().await;
+    node.seed_balance("cmx1sender", 5000);
+
+    let client = WalletClient::new(&node.url());
+    let response = client.transfer(TransferRequest {
+        from: "cmx1sender".into(),
+        to: "cmx1receiver".into(),
+        amount: 1200,
+        denom: "COMAI".into(),
+    }).await?;
+
+    assert_eq!(response.state, "success");
+    assert_eq!(client.get_free_balance("cmx1sender").await?, 3800);
+    assert_eq!(node.balance("cmx1receiver"), 1200);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mock_node_insufficient_funds() {
+    let node = MockNode::start().await;
+    node.seed_balance("cmx1sender", 10);
+
+    let client = WalletClient::new(&node.url());
+    let result = client.transfer(TransferRequest {
+        from: "cmx1sender".into(),
+        to: "cmx1receiver".into(),
+        amount: 1000,
+        denom: "COMAI".into(),
+    }).await;
+
+    assert!(matches!(result, Err(CommunexError::RpcError { code: -32000, .. })));
+    assert_eq!(node.balance("cmx1sender"), 10);
+}
+
+#[tokio::test]
+async fn test_mock_node_seeded_transaction_state() -> Result<(), CommunexError> {
+    let node = MockNode::start().await;
+    node.seed_transaction_state("0xabc", TxStatus::Failed);
+    node.set_error("balance/free", -32603, "node unavailable");
+
+    let client = WalletClient::new(&node.url());
+    let state = client.get_transaction_state("0xabc").await?;
+    assert!(matches!(state.state, TxStatus::Failed));
+
+    let result = client.get_free_balance("cmx1sender").await;
+    assert!(matches!(result, Err(CommunexError::RpcError { code: -32603, .. })));
+    Ok(())
+}
@@ -1,6 +1,17 @@
+// Each test file also builds as its own crate, so they share helpers via #[path]
+#![allow(clippy::duplicate_mod)]
+
 mod batch_transfer_test;
 mod query_map_test;
 mod rpc_client_test;
 mod types_test;
 mod wallet_operations_test;
+mod mock_node_test;
+mod wallet_subscription_test;
+mod runtime_test;
+mod subscription_manager_test;
+mod cli_test;
+mod balance_monitor_test;
+mod send_sync_test;
+mod env_config_test;
 mod modules;
@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 
 use communex::{
     crypto::sr25519::Keypair,
-    modules::client::{ModuleClient, ModuleClientConfig, ClientError},
+    modules::client::{ModuleClient, ModuleClientConfig, ClientError, CircuitBreakerConfig, ContentType},
 };
 
 // Test request/response structures
@@ -32,6 +32,9 @@ async fn setup_test_client() -> (MockServer, ModuleClient, Keypair) {
         port: mock_server.uri().port().unwrap(),
         timeout: Duration::from_secs(1),
         max_retries: 1,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: None,
+        content_type: ContentType::Json,
     };
     
     let client = ModuleClient::with_config(config, keypair.clone());
@@ -153,7 +156,7 @@ async fn test_client_rate_limit() {
         )
         .await;
 
-    assert!(matches!(result, Err(ClientError::RateLimitExceeded)));
+    assert!(matches!(result, Err(ClientError::RateLimitExceeded { .. })));
 }
 
 #[tokio::test]
@@ -1,13 +1,17 @@
 use comx_api::{
     crypto::KeyPair,
-    modules::client::{ModuleClient, ModuleClientConfig, ClientError},
+    modules::client::{ModuleClient, ModuleClientConfig, ClientError, EndpointConfig, AccessLevel, CircuitBreakerConfig, ContentType},
 };
 use wiremock::{
-    matchers::{method, path},
-    Mock, MockServer, ResponseTemplate,
+    matchers::{header, method, path},
+    Mock, MockServer, Request, ResponseTemplate,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::AtomicUsize;
+use std::collections::HashMap;
+use std::time::Duration;
+use futures::StreamExt;
+use serde_json::json;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct TestParams {
@@ -33,6 +37,9 @@ async fn test_module_client_successful_call() {
         port: 0, // Not needed for mock
         timeout: std::time::Duration::from_secs(1),
         max_retries: 1,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: None,
+        content_type: ContentType::Json,
     };
     
     let client = ModuleClient::with_config(config, keypair.clone());
@@ -60,7 +67,7 @@ async fn test_module_client_successful_call() {
     };
     
     let result: TestResponse = client
-        .call("test_method", &keypair.address(), params)
+        .call("test_method", keypair.address(), params)
         .await
         .unwrap();
     
@@ -77,6 +84,9 @@ async fn test_module_client_unauthorized() {
         port: 0,
         timeout: std::time::Duration::from_secs(1),
         max_retries: 1,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: None,
+        content_type: ContentType::Json,
     };
     
     let client = ModuleClient::with_config(config, keypair.clone());
@@ -92,7 +102,7 @@ async fn test_module_client_unauthorized() {
     };
     
     let result = client
-        .call::<_, TestResponse>("test_method", &keypair.address(), params)
+        .call::<_, TestResponse>("test_method", keypair.address(), params)
         .await;
     
     assert!(matches!(result, Err(ClientError::Unauthorized)));
@@ -108,6 +118,9 @@ async fn test_module_client_retry_success() {
         port: 0,
         timeout: std::time::Duration::from_secs(1),
         max_retries: 2,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: None,
+        content_type: ContentType::Json,
     };
     
     let client = ModuleClient::with_config(config, keypair.clone());
@@ -135,13 +148,65 @@ async fn test_module_client_retry_success() {
     };
 
     let result: TestResponse = client
-        .call("test_method", &keypair.address(), params)
+        .call("test_method", keypair.address(), params)
         .await
         .unwrap();
 
     assert_eq!(result.result, "success");
 }
 
+#[tokio::test]
+async fn test_module_client_signs_each_attempt_afresh() {
+    let mock_server = MockServer::start().await;
+    let keypair = KeyPair::generate();
+
+    let config = ModuleClientConfig {
+        host: mock_server.uri(),
+        port: 0,
+        timeout: Duration::from_secs(1),
+        max_retries: 2,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: None,
+        content_type: ContentType::Json,
+    };
+
+    let client = ModuleClient::with_config(config, keypair.clone());
+
+    Mock::given(method("POST"))
+        .and(path("/test_method"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(2)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/test_method"))
+        .respond_with(ResponseTemplate::new(200)
+            .set_body_json(TestResponse {
+                result: "success".to_string(),
+            }))
+        .mount(&mock_server)
+        .await;
+
+    let _: TestResponse = client
+        .call("test_method", keypair.address(), TestParams { value: "test".to_string() })
+        .await
+        .unwrap();
+
+    let requests = mock_server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3);
+    let header = |request: &wiremock::Request, name: &str| {
+        request.headers.get(&name.into()).map(|values| values.to_string())
+    };
+    let mut nonces: Vec<_> = requests.iter().map(|request| header(request, "x-nonce").unwrap()).collect();
+    nonces.dedup();
+    assert_eq!(nonces.len(), 3, "every attempt needs its own nonce");
+    for request in &requests {
+        assert!(header(request, "x-signature").is_some());
+        assert!(header(request, "x-timestamp").is_some());
+    }
+}
+
 #[tokio::test]
 async fn test_module_client_rate_limit() {
     let mock_server = MockServer::start().await;
@@ -152,6 +217,9 @@ async fn test_module_client_rate_limit() {
         port: 0,
         timeout: std::time::Duration::from_secs(1),
         max_retries: 1,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: None,
+        content_type: ContentType::Json,
     };
     
     let client = ModuleClient::with_config(config, keypair.clone());
@@ -167,8 +235,449 @@ async fn test_module_client_rate_limit() {
     };
     
     let result = client
-        .call::<_, TestResponse>("test_method", &keypair.address(), params)
+        .call::<_, TestResponse>("test_method", keypair.address(), params)
         .await;
     
-    assert!(matches!(result, Err(ClientError::RateLimitExceeded)));
+    assert!(matches!(result, Err(ClientError::RateLimitExceeded { retry_after: None })));
+}
+
+fn rate_limited_client(mock_server: &MockServer) -> ModuleClient {
+    let config = ModuleClientConfig {
+        host: mock_server.uri(),
+        port: 0,
+        max_retries: 1,
+        ..ModuleClientConfig::default()
+    };
+    ModuleClient::with_config(config, KeyPair::generate())
+}
+
+#[tokio::test]
+async fn test_module_client_honors_retry_after() {
+    let mock_server = MockServer::start().await;
+    let client = rate_limited_client(&mock_server);
+
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(TestResponse {
+            result: "success".to_string(),
+        }))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let started = std::time::Instant::now();
+    let result = client
+        .call::<_, TestResponse>("test_method", "target", TestParams { value: "test".to_string() })
+        .await
+        .unwrap();
+
+    assert_eq!(result.result, "success");
+    assert!(started.elapsed() >= Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn test_module_client_retry_after_http_date() {
+    let mock_server = MockServer::start().await;
+    let client = rate_limited_client(&mock_server);
+
+    // A date already passed means retry straight away
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "Wed, 21 Oct 2015 07:28:00 GMT"))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(TestResponse {
+            result: "success".to_string(),
+        }))
+        .mount(&mock_server)
+        .await;
+
+    let started = std::time::Instant::now();
+    let result = client
+        .call::<_, TestResponse>("test_method", "target", TestParams { value: "test".to_string() })
+        .await;
+
+    assert!(result.is_ok());
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn test_module_client_rate_limit_without_retries() {
+    let mock_server = MockServer::start().await;
+    let mut client = rate_limited_client(&mock_server);
+    client.max_retries = 0;
+
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let result = client
+        .call::<_, TestResponse>("test_method", "target", TestParams { value: "test".to_string() })
+        .await;
+    assert!(matches!(
+        result,
+        Err(ClientError::RateLimitExceeded { retry_after: Some(delay) }) if delay == Duration::from_secs(1)
+    ));
+}
+
+#[tokio::test]
+async fn test_module_client_caches_cacheable_endpoint() {
+    let mock_server = MockServer::start().await;
+    let keypair = KeyPair::generate();
+
+    let config = ModuleClientConfig {
+        host: mock_server.uri(),
+        port: 0,
+        timeout: Duration::from_secs(1),
+        max_retries: 1,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: None,
+        content_type: ContentType::Json,
+    };
+
+    let client = ModuleClient::with_config(config, keypair.clone());
+    client.register_endpoint(EndpointConfig {
+        name: "get_info".to_string(),
+        path: "/get_info".to_string(),
+        access_level: AccessLevel::Public,
+        rate_limit: None,
+        timeout: None,
+        allow_retries: true,
+        cacheable: true,
+        cache_ttl: Some(Duration::from_secs(30)),
+        metadata: HashMap::new(),
+    });
+
+    Mock::given(method("POST"))
+        .and(path("/get_info"))
+        .respond_with(ResponseTemplate::new(200)
+            .set_body_json(TestResponse {
+                result: "cached".to_string(),
+            }))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    for _ in 0..2 {
+        let result: TestResponse = client
+            .call("get_info", keypair.address(), TestParams { value: "test".to_string() })
+            .await
+            .unwrap();
+        assert_eq!(result.result, "cached");
+    }
+
+    let metrics = client.response_cache.get_metrics().await;
+    assert_eq!(metrics.hits, 1);
+    assert_eq!(metrics.misses, 1);
+}
+
+#[tokio::test]
+async fn test_module_client_cached_parse_error_matches_fresh() {
+    let mock_server = MockServer::start().await;
+    let keypair = KeyPair::generate();
+
+    let config = ModuleClientConfig {
+        host: mock_server.uri(),
+        port: 0,
+        timeout: Duration::from_secs(1),
+        max_retries: 1,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: None,
+        content_type: ContentType::Json,
+    };
+
+    let client = ModuleClient::with_config(config, keypair.clone());
+    client.register_endpoint(EndpointConfig {
+        name: "get_info".to_string(),
+        path: "/get_info".to_string(),
+        access_level: AccessLevel::Public,
+        rate_limit: None,
+        timeout: None,
+        allow_retries: true,
+        cacheable: true,
+        cache_ttl: Some(Duration::from_secs(30)),
+        metadata: HashMap::new(),
+    });
+
+    Mock::given(method("POST"))
+        .and(path("/get_info"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"unexpected": true})))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    // The first call decodes the fresh response, the second the cached one
+    for _ in 0..2 {
+        let result = client
+            .call::<_, TestResponse>("get_info", keypair.address(), TestParams { value: "test".to_string() })
+            .await;
+        assert!(matches!(result, Err(ClientError::RequestFailed(_))), "{:?}", result);
+    }
+}
+
+#[tokio::test]
+async fn test_module_client_circuit_opens_after_failures() {
+    let mock_server = MockServer::start().await;
+    let keypair = KeyPair::generate();
+
+    let config = ModuleClientConfig {
+        host: mock_server.uri(),
+        port: 0,
+        timeout: Duration::from_secs(1),
+        max_retries: 1,
+        circuit_breaker: CircuitBreakerConfig {
+            failure_threshold: 2,
+            cooldown: Duration::from_secs(60),
+        },
+        max_request_bytes: None,
+        content_type: ContentType::Json,
+    };
+
+    let client = ModuleClient::with_config(config, keypair.clone());
+
+    // Only the first call's two attempts should reach the server
+    Mock::given(method("POST"))
+        .and(path("/test_method"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let result = client
+        .call::<_, TestResponse>("test_method", keypair.address(), TestParams { value: "test".to_string() })
+        .await;
+    assert!(matches!(result, Err(ClientError::ServerError(ref msg)) if msg != "circuit open"));
+
+    for _ in 0..3 {
+        let result = client
+            .call::<_, TestResponse>("test_method", keypair.address(), TestParams { value: "test".to_string() })
+            .await;
+        assert!(matches!(result, Err(ClientError::ServerError(ref msg)) if msg == "circuit open"));
+    }
+}
+
+#[tokio::test]
+async fn test_module_client_rejects_oversized_request() {
+    let mock_server = MockServer::start().await;
+    let keypair = KeyPair::generate();
+
+    let config = ModuleClientConfig {
+        host: mock_server.uri(),
+        port: 0,
+        timeout: Duration::from_secs(1),
+        max_retries: 1,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: Some(256),
+        content_type: ContentType::Json,
+    };
+
+    let client = ModuleClient::with_config(config, keypair.clone());
+
+    Mock::given(method("POST"))
+        .and(path("/test_method"))
+        .respond_with(ResponseTemplate::new(200)
+            .set_body_json(TestResponse {
+                result: "success".to_string(),
+            }))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let params = TestParams {
+        value: "x".repeat(1024),
+    };
+    let result = client
+        .call::<_, TestResponse>("test_method", keypair.address(), params)
+        .await;
+
+    match result {
+        Err(ClientError::RequestFailed(msg)) => assert!(msg.contains("256 byte limit")),
+        other => panic!("Expected a local RequestFailed, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_module_client_msgpack_round_trip() {
+    #[derive(Deserialize)]
+    struct ReceivedRequest {
+        params: TestParams,
+    }
+
+    let mock_server = MockServer::start().await;
+    let keypair = KeyPair::generate();
+
+    let config = ModuleClientConfig {
+        host: mock_server.uri(),
+        port: 0,
+        timeout: Duration::from_secs(1),
+        max_retries: 1,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: None,
+        content_type: ContentType::Msgpack,
+    };
+
+    let client = ModuleClient::with_config(config, keypair.clone());
+
+    // Echo the msgpack-encoded param back in a msgpack response
+    Mock::given(method("POST"))
+        .and(path("/test_method"))
+        .and(header("content-type", "application/msgpack"))
+        .respond_with(|request: &Request| {
+            let received: ReceivedRequest = rmp_serde::from_slice(&request.body).unwrap();
+            let response = TestResponse { result: received.params.value };
+            ResponseTemplate::new(200)
+                .set_body_raw(rmp_serde::to_vec_named(&response).unwrap(), "application/msgpack")
+        })
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let result: TestResponse = client
+        .call("test_method", keypair.address(), TestParams { value: "packed".to_string() })
+        .await
+        .unwrap();
+
+    assert_eq!(result.result, "packed");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_module_client_concurrent_calls() {
+    let mock_server = MockServer::start().await;
+    let keypair = KeyPair::generate();
+
+    let config = ModuleClientConfig {
+        host: mock_server.uri(),
+        port: 0,
+        timeout: Duration::from_secs(1),
+        max_retries: 0,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: None,
+        content_type: ContentType::Json,
+    };
+
+    Mock::given(method("POST"))
+        .and(path("/test_method"))
+        .respond_with(ResponseTemplate::new(200)
+            .set_body_json(TestResponse {
+                result: "success".to_string(),
+            })
+            .set_delay(Duration::from_millis(50)))
+        .mount(&mock_server)
+        .await;
+
+    let client = ModuleClient::with_config(config, keypair.clone());
+    let mut tasks = Vec::new();
+    for i in 0..16 {
+        let client = client.clone();
+        let address = keypair.address().to_string();
+        tasks.push(tokio::spawn(async move {
+            // Registry writes from other clones interleave with in-flight calls
+            client.register_endpoint(EndpointConfig {
+                name: format!("endpoint_{}", i),
+                path: format!("/endpoint_{}", i),
+                access_level: AccessLevel::Public,
+                rate_limit: None,
+                timeout: None,
+                allow_retries: false,
+                cacheable: false,
+                cache_ttl: None,
+                metadata: HashMap::new(),
+            });
+            let result: TestResponse = client
+                .call("test_method", &address, TestParams { value: i.to_string() })
+                .await
+                .unwrap();
+            assert_eq!(result.result, "success");
+        }));
+    }
+
+    tokio::time::timeout(Duration::from_secs(5), futures::future::join_all(tasks))
+        .await
+        .expect("concurrent calls deadlocked")
+        .into_iter()
+        .for_each(|task| task.unwrap());
+
+    assert_eq!(client.list_endpoints().len(), 16);
+}
+
+#[tokio::test]
+async fn test_module_client_call_stream_yields_each_line() {
+    let mock_server = MockServer::start().await;
+    let keypair = KeyPair::generate();
+
+    let config = ModuleClientConfig {
+        host: mock_server.uri(),
+        port: 0,
+        timeout: Duration::from_secs(1),
+        max_retries: 0,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: None,
+        content_type: ContentType::Json,
+    };
+
+    Mock::given(method("POST"))
+        .and(path("/generate"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "{\"result\":\"first\"}\n{\"result\":\"second\"}\n",
+            "application/x-ndjson",
+        ))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = ModuleClient::with_config(config, keypair.clone());
+    let stream = client
+        .call_stream::<_, TestResponse>("generate", keypair.address(), TestParams { value: "prompt".to_string() })
+        .await
+        .unwrap();
+    let results: Vec<String> = stream
+        .map(|item| item.unwrap().result)
+        .collect()
+        .await;
+
+    assert_eq!(results, vec!["first", "second"]);
+}
+
+#[tokio::test]
+async fn test_module_client_call_stream_reads_server_sent_events() {
+    let mock_server = MockServer::start().await;
+    let keypair = KeyPair::generate();
+
+    let config = ModuleClientConfig {
+        host: mock_server.uri(),
+        port: 0,
+        timeout: Duration::from_secs(1),
+        max_retries: 0,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: None,
+        content_type: ContentType::Json,
+    };
+
+    Mock::given(method("POST"))
+        .and(path("/generate"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            ": keep-alive\r\nevent: token\r\ndata: {\"result\":\"first\"}\r\n\r\ndata: {\"result\":\"second\"}\r\n\r\ndata: [DONE]\r\n\r\n",
+            "text/event-stream",
+        ))
+        .mount(&mock_server)
+        .await;
+
+    let client = ModuleClient::with_config(config, keypair.clone());
+    let results: Vec<String> = client
+        .call_stream::<_, TestResponse>("generate", keypair.address(), TestParams { value: "prompt".to_string() })
+        .await
+        .unwrap()
+        .map(|item| item.unwrap().result)
+        .collect()
+        .await;
+
+    assert_eq!(results, vec!["first", "second"]);
 }
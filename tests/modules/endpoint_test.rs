@@ -1,5 +1,5 @@
 use comx_api::modules::client::{
-    ModuleClient, ModuleClientConfig, EndpointConfig,
+    ModuleClient, ModuleClientConfig, EndpointConfig, CircuitBreakerConfig, ContentType,
     AccessLevel, RateLimit,
 };
 use comx_api::crypto::KeyPair;
@@ -42,9 +42,12 @@ async fn test_endpoint_configuration() {
         port: 0,
         timeout: Duration::from_secs(5),
         max_retries: 3,
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: None,
+        content_type: ContentType::Json,
     };
     
-    let mut client = ModuleClient::with_config(config, keypair);
+    let client = ModuleClient::with_config(config, keypair);
 
     // Register test endpoint
     let endpoint_config = EndpointConfig {
@@ -57,6 +60,8 @@ async fn test_endpoint_configuration() {
         }),
         timeout: Some(Duration::from_secs(30)),
         allow_retries: true,
+        cacheable: false,
+        cache_ttl: None,
         metadata: HashMap::new(),
     };
     client.register_endpoint(endpoint_config.clone());
@@ -109,9 +114,12 @@ async fn test_endpoint_retry_disabled() {
         port: 0,
         timeout: Duration::from_secs(5),
         max_retries: 3, // Client allows retries but endpoint disables them
+        circuit_breaker: CircuitBreakerConfig::default(),
+        max_request_bytes: None,
+        content_type: ContentType::Json,
     };
     
-    let mut client = ModuleClient::with_config(config, keypair);
+    let client = ModuleClient::with_config(config, keypair);
 
     // Register test endpoint with retries disabled
     let endpoint_config = EndpointConfig {
@@ -121,6 +129,8 @@ async fn test_endpoint_retry_disabled() {
         rate_limit: None,
         timeout: None,
         allow_retries: false, // Disable retries for this endpoint
+        cacheable: false,
+        cache_ttl: None,
         metadata: HashMap::new(),
     };
     client.register_endpoint(endpoint_config);
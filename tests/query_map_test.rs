@@ -1,7 +1,7 @@
 use comx_api::{
     rpc::RpcClient,
-    types::Address,
-    query_map::{QueryMap, QueryMapConfig},
+    types::{Address, Netuid},
+    query_map::{QueryMap, QueryMapConfig, ModuleInfo, ValidatorQueryOptions, ValidatorSort},
     error::CommunexError,
 };
 use tokio::time::{Duration, sleep};
@@ -9,15 +9,31 @@ use serde_json::json;
 use mockito::{Server, ServerOpts};
 use serial_test::serial;
 
+#[path = "common/mod.rs"]
+mod common;
+use common::rpc_response;
+
 const TEST_ADDRESS: &str = "cmx1abc123def456";
 
+/// Copies the JSON-RPC id of the incoming request into a single response body
+fn echo_request_id(response: &serde_json::Value, request: &mockito::Request) -> Vec<u8> {
+    let mut response = response.clone();
+    if response.is_object() {
+        if let Some(id) = request.body().ok()
+            .and_then(|body| serde_json::from_slice::<serde_json::Value>(body).ok())
+            .and_then(|body| body.get("id").cloned())
+        {
+            response["id"] = id;
+        }
+    }
+    response.to_string().into_bytes()
+}
+
 async fn setup_test_server(response: serde_json::Value) -> (Server, RpcClient) {
     let opts = ServerOpts::default();
     let mut server = Server::new_with_opts_async(opts).await;
     
-    let rpc_response = if response.is_array() {
-        response
-    } else if response.get("error").is_some() {
+    let rpc_response = if response.is_array() || response.get("error").is_some() {
         response
     } else {
         json!({
@@ -30,7 +46,7 @@ async fn setup_test_server(response: serde_json::Value) -> (Server, RpcClient) {
     let _m = server.mock("POST", "/")
         .with_status(200)
         .with_header("content-type", "application/json")
-        .with_body(rpc_response.to_string())
+        .with_body_from_request(move |request| echo_request_id(&rpc_response, request))
         .create();
 
     let client = RpcClient::new(server.url());
@@ -121,6 +137,54 @@ async fn test_cache_refresh() -> Result<(), CommunexError> {
     Ok(())
 }
 
+#[tokio::test]
+#[serial]
+async fn test_refresh_balance_bypasses_cache() -> Result<(), CommunexError> {
+    let mut server = Server::new_async().await;
+    let mock = server.mock("POST", "/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body_from_request(|request| echo_request_id(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "amount": "1000000",
+                "denom": "COMAI"
+            }
+        }), request))
+        .expect(2)
+        .create_async()
+        .await;
+
+    let query_map = QueryMap::new(RpcClient::new(server.url()), QueryMapConfig::default())?;
+
+    // Prime the cache, then hit it
+    query_map.get_balance(TEST_ADDRESS).await?;
+    query_map.get_balance(TEST_ADDRESS).await?;
+
+    // Force a fresh query despite the cached value
+    let balance = query_map.refresh_balance(TEST_ADDRESS).await?;
+    assert_eq!(balance.amount()?, 1000000);
+    assert_eq!(query_map.cache_stats().refresh_count, 2);
+
+    mock.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_subnet_emission_query() -> Result<(), CommunexError> {
+    let (_server, client) = setup_test_server(json!({
+        "emission": [100, 200, 300]
+    })).await;
+
+    let query_map = QueryMap::new(client, QueryMapConfig::default())?;
+    let emission = query_map.get_subnet_emission(Netuid::new(1)?).await?;
+
+    assert_eq!(emission, vec![100, 200, 300]);
+    Ok(())
+}
+
 #[tokio::test]
 #[serial]
 async fn test_batch_balance_queries() -> Result<(), CommunexError> {
@@ -262,6 +326,42 @@ async fn test_malformed_stake_response() -> Result<(), CommunexError> {
     Ok(())
 }
 
+#[tokio::test]
+#[serial]
+async fn test_balance_parse_error_names_method() -> Result<(), CommunexError> {
+    let (_server, client) = setup_test_server(json!({
+        "amount": "1000000"
+    })).await;
+
+    let query_map = QueryMap::new(client, QueryMapConfig::default())?;
+    match query_map.get_balance(TEST_ADDRESS).await {
+        Err(CommunexError::ParseError(message)) => {
+            assert!(message.contains("query_balance"), "{}", message);
+            assert!(message.contains("denom"), "{}", message);
+        }
+        other => panic!("expected ParseError, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_parse_error_names_failing_field() -> Result<(), CommunexError> {
+    let (_server, client) = setup_test_server(json!({
+        "stake_from": ["cmx1addr1", 42]
+    })).await;
+
+    let query_map = QueryMap::new(client, QueryMapConfig::default())?;
+    match query_map.get_stake_from(TEST_ADDRESS).await {
+        Err(CommunexError::ParseError(message)) => {
+            assert!(message.contains("query_stakefrom"), "{}", message);
+            assert!(message.contains("'stake_from'"), "{}", message);
+        }
+        other => panic!("expected ParseError, got {:?}", other),
+    }
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_batch_request_partial_failure() -> Result<(), CommunexError> {
     let batch_response = json!([
@@ -292,4 +392,140 @@ async fn test_batch_request_partial_failure() -> Result<(), CommunexError> {
     assert_eq!(response.len(), 1);
     assert_eq!(response[0].amount()?, 1000000);
     Ok(())
-} 
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_get_registered_modules() -> Result<(), CommunexError> {
+    use wiremock::{Mock, MockServer, matchers::{method, path}};
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": [
+                {
+                    "uid": 0,
+                    "key": "5EA6Dd3vejQco2FZomoAQgxacsTp7ZPFuR25TwxTiUKbkep1",
+                    "name": "validator",
+                    "address": "127.0.0.1:8000"
+                },
+                {
+                    "uid": 7,
+                    "key": "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+                }
+            ]
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let query_map = QueryMap::new(RpcClient::new(mock_server.uri()), QueryMapConfig::default())?;
+    let modules = query_map.get_registered_modules(1).await?;
+
+    assert_eq!(modules.len(), 2);
+    assert_eq!(modules[0].uid, 0);
+    assert_eq!(modules[0].name.as_deref(), Some("validator"));
+    assert_eq!(modules[1], ModuleInfo {
+        uid: 7,
+        key: "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".to_string(),
+        name: None,
+        address: None,
+    });
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_subnet_parse_error_names_subnet() -> Result<(), CommunexError> {
+    let (_server, client) = setup_test_server(json!({ "modules": [] })).await;
+
+    let query_map = QueryMap::new(client, QueryMapConfig::default())?;
+    match query_map.get_registered_modules(3).await {
+        Err(CommunexError::ParseError(message)) => {
+            assert!(message.contains("query_modules response for subnet 3"), "{}", message);
+        }
+        other => panic!("expected ParseError, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_validators_filters_sorts_and_limits() -> Result<(), CommunexError> {
+    use wiremock::{Mock, MockServer, matchers::{body_partial_json, method, path}};
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .and(body_partial_json(json!({ "method": "query_validators", "params": { "netuid": 1 } })))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": [
+                { "uid": 0, "key": "5EA6Dd3vejQco2FZomoAQgxacsTp7ZPFuR25TwxTiUKbkep1", "stake": 500 },
+                { "uid": 1, "key": "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY", "stake": 50 },
+                { "uid": 2, "key": "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty", "stake": 2000, "name": "top" },
+                { "uid": 3, "key": "5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y", "stake": 900 }
+            ]
+        })))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let query_map = QueryMap::new(RpcClient::new(mock_server.uri()), QueryMapConfig::default())?;
+
+    let all = query_map.get_validators(1, ValidatorQueryOptions::default()).await?;
+    assert_eq!(all.iter().map(|v| v.uid).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+
+    let options = ValidatorQueryOptions {
+        min_stake: Some(100),
+        sort_by: Some(ValidatorSort::StakeDescending),
+        limit: Some(2),
+    };
+    let top = query_map.get_validators(1, options).await?;
+    assert_eq!(top.iter().map(|v| (v.uid, v.stake)).collect::<Vec<_>>(), vec![(2, 2000), (3, 900)]);
+    assert_eq!(top[0].name.as_deref(), Some("top"));
+    Ok(())
+}
+
+#[test]
+fn test_query_map_config_builder() {
+    let config = QueryMapConfig::default()
+        .with_refresh_interval(Duration::from_secs(30))
+        .with_cache_duration(Duration::from_secs(120));
+
+    assert_eq!(config.refresh_interval, Duration::from_secs(30));
+    assert_eq!(config.cache_duration, Duration::from_secs(120));
+    assert!(config.validate().is_ok());
+    assert!(QueryMapConfig::default().validate().is_ok());
+}
+
+#[tokio::test]
+async fn test_get_balance_at_block() -> Result<(), CommunexError> {
+    use wiremock::{matchers::{body_partial_json, method}, Mock, MockServer};
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({
+            "method": "query_balance",
+            "params": { "address": TEST_ADDRESS, "block": 1200 }
+        })))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "result": { "amount": "750000", "denom": "COMAI" }
+        })))
+        // Later lookups of the same block come from the cache
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let query_map = QueryMap::new(RpcClient::new(mock_server.uri()), QueryMapConfig::default())?;
+    let balance = query_map.get_balance_at(TEST_ADDRESS, 1200).await?;
+    assert_eq!(balance.amount()?, 750000);
+    assert_eq!(balance.denom(), "COMAI");
+
+    let cached = query_map.get_balance_at(TEST_ADDRESS, 1200).await?;
+    assert_eq!(cached.amount()?, 750000);
+    Ok(())
+}
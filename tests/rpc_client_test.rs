@@ -1,5 +1,5 @@
 use comx_api::{
-    rpc::{RpcClient, RpcClientConfig, BatchRequest},
+    rpc::{RpcClient, RpcClientConfig, BatchRequest, PathStyle, RetryBudgetConfig, MAX_BATCH_REQUESTS},
     error::CommunexError,
 };
 use wiremock::{
@@ -9,13 +9,17 @@ use wiremock::{
 use serde_json::json;
 use std::time::Duration;
 
+#[path = "common/mod.rs"]
+mod common;
+use common::rpc_response;
+
 #[tokio::test]
 async fn test_single_request_success() -> Result<(), CommunexError> {
     let mock_server = MockServer::start().await;
     
     Mock::given(method("POST"))
         .and(path("/"))
-        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        .respond_with(rpc_response(json!({
             "jsonrpc": "2.0",
             "id": 1,
             "result": {"balance": "1000"}
@@ -70,7 +74,7 @@ async fn test_rpc_error_response() -> Result<(), CommunexError> {
     
     Mock::given(method("POST"))
         .and(path("/"))
-        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        .respond_with(rpc_response(json!({
             "jsonrpc": "2.0",
             "id": 1,
             "error": {
@@ -87,6 +91,7 @@ async fn test_rpc_error_response() -> Result<(), CommunexError> {
         RpcClientConfig {
             timeout: Duration::from_secs(1),
             max_retries: 2,
+            ..RpcClientConfig::default()
         }
     );
     
@@ -100,6 +105,7 @@ async fn test_connection_timeout() -> Result<(), CommunexError> {
     let config = RpcClientConfig {
         timeout: Duration::from_millis(100),
         max_retries: 1,
+        ..RpcClientConfig::default()
     };
     
     let client = RpcClient::new_with_config("http://invalid-url", config);
@@ -109,6 +115,30 @@ async fn test_connection_timeout() -> Result<(), CommunexError> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_connection_error_keeps_reqwest_source() {
+    use std::error::Error;
+
+    let config = RpcClientConfig {
+        max_retries: 1,
+        ..RpcClientConfig::default()
+    };
+    // Nothing listens on port 1, so the connection is refused
+    let client = RpcClient::new_with_config("http://127.0.0.1:1", config);
+    let error = client.request("test", json!({})).await.unwrap_err();
+    assert!(matches!(error, CommunexError::ConnectionError(_)));
+
+    let cause = error.source().expect("connection error has no source");
+    let reqwest_error = cause.source()
+        .and_then(|source| source.downcast_ref::<reqwest::Error>())
+        .expect("cause is not a reqwest error");
+    assert!(reqwest_error.is_connect());
+
+    // Errors built from a message alone have no underlying cause
+    let error = CommunexError::ConnectionError("refused".into());
+    assert!(error.source().unwrap().source().is_none());
+}
+
 #[tokio::test]
 async fn test_batch_request_partial_failure() -> Result<(), CommunexError> {
     let mock_server = MockServer::start().await;
@@ -146,6 +176,121 @@ async fn test_batch_request_partial_failure() -> Result<(), CommunexError> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_batch_request_captures_error_data() -> Result<(), CommunexError> {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {
+                "jsonrpc": "2.0",
+                "id": 0,
+                "error": {
+                    "code": -32602,
+                    "message": "Invalid params",
+                    "data": {"param": "address", "reason": "not ss58"}
+                }
+            },
+            {
+                "jsonrpc": "2.0",
+                "id": 1,
+                "error": {"code": -32601, "message": "Method not found"}
+            }
+        ])))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = RpcClient::new(mock_server.uri());
+    let mut batch = BatchRequest::new();
+    batch.add_request("query_balance", json!({"address": "invalid"}));
+    batch.add_request("unknown_method", json!({}));
+
+    let response = client.batch_request(batch).await?;
+    assert_eq!(response.errors.len(), 2);
+    assert_eq!(response.errors[0].data, Some(json!({"param": "address", "reason": "not ss58"})));
+    assert_eq!(response.errors[0].request_id, Some(0));
+    assert_eq!(response.errors[1].data, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_request_splits_large_batches() -> Result<(), CommunexError> {
+    let mock_server = MockServer::start().await;
+
+    // Answer each call in reverse order to check the merged ordering
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(|request: &wiremock::Request| {
+            let requests: Vec<serde_json::Value> = request.body_json().unwrap();
+            assert!(requests.len() <= MAX_BATCH_REQUESTS);
+            let responses: Vec<_> = requests.iter().rev()
+                .map(|req| json!({ "jsonrpc": "2.0", "id": req["id"], "result": req["id"] }))
+                .collect();
+            ResponseTemplate::new(200).set_body_json(responses)
+        })
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let client = RpcClient::new(mock_server.uri());
+    let mut batch = BatchRequest::new();
+    for i in 0..150 {
+        batch.add_request("query_balance", json!({"address": format!("cmx1addr{}", i)}));
+    }
+
+    let response = client.batch_request(batch).await?;
+    assert!(response.errors.is_empty());
+    let ids: Vec<u64> = response.successes.iter().map(|id| id.as_u64().unwrap()).collect();
+    assert_eq!(ids, (0..150).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_response_into_result() -> Result<(), CommunexError> {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"jsonrpc": "2.0", "id": 0, "result": {"balance": "1000"}},
+            {"jsonrpc": "2.0", "id": 1, "result": {"balance": "2000"}}
+        ])))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {"jsonrpc": "2.0", "id": 0, "result": {"balance": "1000"}},
+            {"jsonrpc": "2.0", "id": 1, "error": {"code": -32602, "message": "Invalid params"}}
+        ])))
+        .mount(&mock_server)
+        .await;
+
+    let client = RpcClient::new(mock_server.uri());
+    let batch = || {
+        let mut batch = BatchRequest::new();
+        batch.add_request("query_balance", json!({"address": "first"}));
+        batch.add_request("query_balance", json!({"address": "second"}));
+        batch
+    };
+
+    let results = client.batch_request(batch()).await?.into_result()?;
+    assert_eq!(results, vec![json!({"balance": "1000"}), json!({"balance": "2000"})]);
+
+    match client.batch_request(batch()).await?.into_result() {
+        Err(CommunexError::BatchRpcError(errors)) => {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].code, -32602);
+            assert_eq!(errors[0].request_id, Some(1));
+        }
+        other => panic!("expected BatchRpcError, got {:?}", other),
+    }
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_retry_mechanism() -> Result<(), CommunexError> {
     let mock_server = MockServer::start().await;
@@ -161,7 +306,7 @@ async fn test_retry_mechanism() -> Result<(), CommunexError> {
     // Final attempt succeeds
     Mock::given(method("POST"))
         .and(path("/"))
-        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        .respond_with(rpc_response(json!({
             "jsonrpc": "2.0",
             "id": 1,
             "result": {"success": true}
@@ -175,6 +320,7 @@ async fn test_retry_mechanism() -> Result<(), CommunexError> {
         RpcClientConfig {
             timeout: Duration::from_secs(1),
             max_retries: 2,
+            ..RpcClientConfig::default()
         }
     );
     
@@ -191,4 +337,499 @@ async fn test_retry_mechanism() -> Result<(), CommunexError> {
             Ok(())
         }
     }
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_execute_with_retry_skips_non_retryable_errors() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": { "code": -32601, "message": "Method not found" }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = RpcClient::new_with_config(
+        mock_server.uri(),
+        RpcClientConfig {
+            max_retries: 3,
+            ..RpcClientConfig::default()
+        }
+    );
+
+    let result = client.execute_with_retry(|| client.request("unknown_method", json!({}))).await;
+    assert!(matches!(result, Err(CommunexError::RpcError { code: -32601, .. })));
+}
+
+#[test]
+fn test_error_retry_classification() {
+    assert!(CommunexError::ConnectionError("refused".into()).is_retryable());
+    assert!(CommunexError::RequestTimeout("slow".into()).is_retryable());
+    assert!(CommunexError::RpcError { code: -32603, message: "Internal error".into() }.is_retryable());
+    assert!(CommunexError::RpcError { code: 503, message: "HTTP error".into() }.is_retryable());
+
+    assert!(!CommunexError::RpcError { code: -32601, message: "Method not found".into() }.is_retryable());
+    assert!(!CommunexError::RpcError { code: -32602, message: "Invalid params".into() }.is_retryable());
+    assert!(!CommunexError::ValidationError("bad amount".into()).is_retryable());
+}
+
+#[tokio::test]
+async fn test_request_with_deadline_times_out() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200)
+            .set_delay(Duration::from_secs(5))
+            .set_body_json(json!({ "jsonrpc": "2.0", "id": 1, "result": {} })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = RpcClient::new_with_config(
+        mock_server.uri(),
+        RpcClientConfig {
+            timeout: Duration::from_secs(10),
+            max_retries: 5,
+            ..RpcClientConfig::default()
+        }
+    );
+
+    let started = std::time::Instant::now();
+    let deadline = started + Duration::from_millis(200);
+    let result = client.request_with_deadline("slow_method", json!({}), deadline).await;
+
+    assert!(matches!(result, Err(CommunexError::RequestTimeout(_))));
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[tokio::test]
+async fn test_sequential_requests_use_unique_ids() -> Result<(), CommunexError> {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "result": {"balance": "1000"}
+        })))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let client = RpcClient::new(mock_server.uri());
+    client.request("query_balance", json!({"address": "addr1"})).await?;
+    client.request("query_balance", json!({"address": "addr2"})).await?;
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let ids: Vec<_> = requests.iter()
+        .map(|r| r.body_json::<serde_json::Value>().unwrap()["id"].clone())
+        .collect();
+
+    assert_eq!(ids.len(), 2);
+    assert!(ids[0].is_u64());
+    assert_ne!(ids[0], ids[1]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mismatched_response_id_rejected() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "jsonrpc": "2.0",
+            "id": 987654321,
+            "result": {"balance": "1000"}
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = RpcClient::new(mock_server.uri());
+    let result = client.request("query_balance", json!({"address": "test"})).await;
+
+    assert!(matches!(result, Err(CommunexError::MalformedResponse(msg)) if msg.contains("does not match")));
+}
+
+#[tokio::test]
+async fn test_missing_jsonrpc_version() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "result": {"balance": "1000"}
+        })))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    // Strict mode is the default
+    let client = RpcClient::new(mock_server.uri());
+    let result = client.request("query_balance", json!({"address": "test"})).await;
+    assert!(matches!(result, Err(CommunexError::MalformedResponse(_))));
+
+    let lenient = RpcClient::new_with_config(
+        mock_server.uri(),
+        RpcClientConfig::default().with_strict_jsonrpc(false),
+    );
+    let result = lenient.request("query_balance", json!({"address": "test"})).await;
+    assert!(result.is_ok());
+}
+
+#[derive(Debug, serde::Serialize)]
+struct BalanceQuery<'a> {
+    address: &'a str,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+struct BalanceReply {
+    balance: String,
+    denom: String,
+}
+
+#[tokio::test]
+async fn test_request_typed() -> Result<(), CommunexError> {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .and(wiremock::matchers::body_partial_json(json!({
+            "method": "query_balance",
+            "params": {"address": "cmx1abcd123"}
+        })))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {"balance": "1000", "denom": "COMAI"}
+        })))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let client = RpcClient::new(mock_server.uri());
+    let reply: BalanceReply = client
+        .request_typed("query_balance", BalanceQuery { address: "cmx1abcd123" })
+        .await?;
+
+    assert_eq!(reply, BalanceReply { balance: "1000".into(), denom: "COMAI".into() });
+
+    // A result that does not fit the requested type is a parse error
+    let mismatched = client
+        .request_typed::<_, Vec<u64>>("query_balance", BalanceQuery { address: "cmx1abcd123" })
+        .await;
+    assert!(matches!(mismatched, Err(CommunexError::ParseError(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_request_typed_mixed_results() -> Result<(), CommunexError> {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {
+                "jsonrpc": "2.0",
+                "id": 1,
+                "error": {"code": -32602, "message": "Invalid address"}
+            },
+            {
+                "jsonrpc": "2.0",
+                "id": 0,
+                "result": {"balance": "1000", "denom": "COMAI"}
+            }
+        ])))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = RpcClient::new(mock_server.uri());
+    let mut batch = BatchRequest::new();
+    batch.add_request("query_balance", json!({"address": "cmx1valid"}));
+    batch.add_request("query_balance", json!({"address": "cmx1invalid"}));
+
+    let results = client.batch_request_typed::<BalanceReply>(batch).await?;
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0], Ok(BalanceReply { balance: "1000".into(), denom: "COMAI".into() }));
+    assert!(matches!(&results[1], Err(CommunexError::RpcError { code: -32602, .. })));
+    Ok(())
+}
+
+/// Runtime that records requested sleeps and completes them immediately,
+/// driven by the `futures` executor rather than tokio
+#[derive(Debug, Default)]
+struct RecordingRuntime {
+    sleeps: std::sync::Mutex<Vec<Duration>>,
+}
+
+impl comx_api::runtime::Runtime for RecordingRuntime {
+    fn sleep(&self, duration: Duration) -> futures::future::BoxFuture<'static, ()> {
+        self.sleeps.lock().unwrap().push(duration);
+        Box::pin(futures::future::ready(()))
+    }
+
+    fn spawn(&self, task: futures::future::BoxFuture<'static, ()>) {
+        std::thread::spawn(move || futures::executor::block_on(task));
+    }
+}
+
+#[test]
+fn test_execute_with_retry_without_tokio() {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    let runtime = std::sync::Arc::new(RecordingRuntime::default());
+    let config = RpcClientConfig {
+        max_retries: 3,
+        ..RpcClientConfig::default()
+    }
+    .with_runtime(runtime.clone());
+    let client = RpcClient::new_with_config("http://127.0.0.1:1", config);
+
+    let attempts = AtomicU32::new(0);
+    let result = futures::executor::block_on(client.execute_with_retry(|| async {
+        if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+            Err(CommunexError::ConnectionError("unavailable".into()))
+        } else {
+            Ok("done")
+        }
+    }));
+
+    assert_eq!(result, Ok("done"));
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    assert_eq!(
+        *runtime.sleeps.lock().unwrap(),
+        vec![Duration::from_millis(200), Duration::from_millis(400)]
+    );
+}
+
+async fn path_style_server(expected_path: &str) -> MockServer {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(expected_path))
+        .respond_with(rpc_response(json!({"jsonrpc": "2.0", "result": {"balance": "1000"}})))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+    mock_server
+}
+
+#[tokio::test]
+async fn test_path_style_per_method() -> Result<(), CommunexError> {
+    let mock_server = path_style_server("/query_balance").await;
+    let client = RpcClient::new_with_config(
+        mock_server.uri(),
+        RpcClientConfig::default().with_path_style(PathStyle::PerMethod),
+    );
+
+    client.request("query_balance", json!({"address": "test"})).await?;
+    client.request_with_path("query_balance", json!({"address": "test"})).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_path_style_single_endpoint() -> Result<(), CommunexError> {
+    let mock_server = path_style_server("/").await;
+    let client = RpcClient::new_with_config(
+        mock_server.uri(),
+        RpcClientConfig::default().with_path_style(PathStyle::SingleEndpoint),
+    );
+
+    client.request("query_balance", json!({"address": "test"})).await?;
+    client.request_with_path("query_balance", json!({"address": "test"})).await?;
+    Ok(())
+}
+
+/// Fails the first two requests to `expected_path` with HTTP 503, then answers
+async fn flaky_server(expected_path: &str) -> MockServer {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(expected_path))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(2)
+        .expect(2)
+        .with_priority(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(expected_path))
+        .respond_with(rpc_response(json!({"jsonrpc": "2.0", "result": {"balance": "1000"}})))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    mock_server
+}
+
+#[tokio::test]
+async fn test_request_and_request_with_path_retry_alike() -> Result<(), CommunexError> {
+    let config = RpcClientConfig { max_retries: 3, ..RpcClientConfig::default() };
+
+    let mock_server = flaky_server("/").await;
+    let client = RpcClient::new_with_config(mock_server.uri(), config.clone());
+    let result = client.request("query_balance", json!({"address": "test"})).await?;
+    assert_eq!(result["balance"], "1000");
+
+    let mock_server = flaky_server("/query_balance").await;
+    let client = RpcClient::new_with_config(mock_server.uri(), config);
+    let result = client.request_with_path("query_balance", json!({"address": "test"})).await?;
+    assert_eq!(result["balance"], "1000");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_request_with_path_gives_up_after_max_retries() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/query_balance"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(3)
+        .mount(&mock_server)
+        .await;
+
+    let client = RpcClient::new_with_config(
+        mock_server.uri(),
+        RpcClientConfig { max_retries: 2, ..RpcClientConfig::default() },
+    );
+    let result = client.request_with_path("query_balance", json!({})).await;
+    assert!(matches!(result, Err(CommunexError::RpcError { code: 503, .. })));
+}
+
+#[tokio::test]
+async fn test_zero_max_retries_still_sends_once() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/query_balance"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = RpcClient::new_with_config(
+        mock_server.uri(),
+        RpcClientConfig { max_retries: 0, ..RpcClientConfig::default() },
+    );
+    let result = client.request_with_path("query_balance", json!({})).await;
+    assert!(matches!(result, Err(CommunexError::RpcError { code: 503, .. })));
+}
+
+#[tokio::test]
+async fn test_request_with_path_uses_configured_timeout() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/query_balance"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(2)))
+        .mount(&mock_server)
+        .await;
+
+    let client = RpcClient::new_with_config(
+        mock_server.uri(),
+        RpcClientConfig { timeout: Duration::from_millis(100), max_retries: 1, ..RpcClientConfig::default() },
+    );
+    let result = client.request_with_path("query_balance", json!({})).await;
+    assert!(matches!(result, Err(CommunexError::RequestTimeout(_))));
+}
+
+#[tokio::test]
+async fn test_response_size_limit() {
+    let mock_server = MockServer::start().await;
+    let large = "x".repeat(64 * 1024);
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(rpc_response(json!({"jsonrpc": "2.0", "result": large})))
+        .mount(&mock_server)
+        .await;
+
+    let client = RpcClient::new_with_config(
+        mock_server.uri(),
+        RpcClientConfig { max_retries: 1, ..RpcClientConfig::default() }.with_max_response_bytes(1024),
+    );
+    let result = client.request("query_balance", json!({})).await;
+    assert!(matches!(result, Err(CommunexError::MalformedResponse(message)) if message.contains("1024 bytes")));
+
+    let mut batch = BatchRequest::new();
+    batch.add_request("query_balance", json!({}));
+    let result = client.batch_request(batch).await;
+    assert!(matches!(result, Err(CommunexError::MalformedResponse(_))));
+
+    // The same response fits within the default limit
+    let client = RpcClient::new(mock_server.uri());
+    assert!(client.request("query_balance", json!({})).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_retry_budget_throttles_retries() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&mock_server)
+        .await;
+
+    let client = RpcClient::new_with_config(
+        mock_server.uri(),
+        RpcClientConfig { max_retries: 3, ..RpcClientConfig::default() }.with_retry_budget(RetryBudgetConfig {
+            ratio: 0.1,
+            window: Duration::from_secs(60),
+            min_retries: 0,
+        }),
+    );
+
+    // Clones draw on the same budget
+    for _ in 0..20 {
+        let result = client.clone().request("query_balance", json!({})).await;
+        assert!(matches!(result, Err(CommunexError::RpcError { code: 503, .. })));
+    }
+
+    // Without the budget this would be 60 attempts; with it, 10% of 20 requests
+    let received = mock_server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 22);
+}
+
+#[tokio::test]
+async fn test_html_error_page_is_reported_clearly() {
+    let mock_server = MockServer::start().await;
+    let page = format!("<html><body><h1>502 Bad Gateway</h1>{}</body></html>", "<p>nginx</p>".repeat(50));
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(502).set_body_raw(page, "text/html"))
+        .mount(&mock_server)
+        .await;
+
+    let client = RpcClient::new_with_config(
+        mock_server.uri(),
+        RpcClientConfig { max_retries: 1, ..RpcClientConfig::default() },
+    );
+
+    let mut batch = BatchRequest::new();
+    batch.add_request("query_balance", json!({}));
+    match client.batch_request(batch).await {
+        Err(CommunexError::MalformedResponse(message)) => {
+            assert!(message.starts_with("HTTP 502 Bad Gateway returned a non-JSON body (text/html)"), "{}", message);
+            assert!(message.contains("<h1>502 Bad Gateway</h1>"));
+            assert!(message.ends_with("..."));
+        }
+        other => panic!("expected MalformedResponse, got {:?}", other),
+    }
+
+    let result = client.send_request("query_balance", &json!({})).await;
+    assert!(matches!(result, Err(CommunexError::MalformedResponse(message)) if message.contains("HTTP 502")));
+
+    // Single requests keep the status as a retryable RPC error
+    let result = client.request("query_balance", json!({})).await;
+    assert!(result.as_ref().is_err_and(CommunexError::is_retryable));
+    match result {
+        Err(CommunexError::RpcError { code: 502, message }) => {
+            assert!(message.starts_with("HTTP 502 Bad Gateway returned a non-JSON body (text/html)"), "{}", message);
+            assert!(message.contains("<h1>502 Bad Gateway</h1>"));
+            assert!(message.ends_with("..."));
+        }
+        other => panic!("expected RpcError, got {:?}", other),
+    }
+}
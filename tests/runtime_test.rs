@@ -0,0 +1,31 @@
+use comx_api::error::CommunexError;
+use comx_api::runtime::{self, Runtime, TokioRuntime};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Every runtime has to fit behind the `Arc<dyn Runtime>` the clients store
+fn as_dyn<R: Runtime + 'static>(runtime: R) -> Arc<dyn Runtime> {
+    Arc::new(runtime)
+}
+
+#[test]
+fn test_runtimes_are_object_safe() {
+    let _ = as_dyn(TokioRuntime);
+    #[cfg(feature = "wasm")]
+    let _ = as_dyn(runtime::WasmRuntime);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_default_runtime_timeout() {
+    let runtime = runtime::default_runtime();
+
+    let ready = runtime::timeout(runtime.as_ref(), Duration::from_secs(1), async { 42 }).await;
+    assert_eq!(ready.unwrap(), 42);
+
+    let pending = runtime::timeout(
+        runtime.as_ref(),
+        Duration::from_secs(1),
+        futures::future::pending::<()>(),
+    ).await;
+    assert!(matches!(pending, Err(CommunexError::RequestTimeout(_))));
+}
@@ -0,0 +1,15 @@
+//! Compile-time checks that the clients can be shared across threads and held
+//! across `.await` points in multi-threaded runtimes
+
+use comx_api::{
+    cache::QueryMapCache,
+    modules::client::ModuleClient,
+    rpc::RpcClient,
+    wallet::WalletClient,
+};
+use static_assertions::assert_impl_all;
+
+assert_impl_all!(RpcClient: Send, Sync);
+assert_impl_all!(WalletClient: Send, Sync);
+assert_impl_all!(ModuleClient: Send, Sync);
+assert_impl_all!(QueryMapCache: Send, Sync);
@@ -0,0 +1,150 @@
+use comx_api::rpc::SubscriptionManager;
+use comx_api::CommunexError;
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+/// Serve two connections. Each confirms both subscriptions with ids unique to
+/// the connection and pushes one update per subscription; the first
+/// connection is then dropped to force a reconnect.
+async fn start_ws_mock() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        for connection in 1..=2u64 {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+
+            let mut subscriptions = Vec::new();
+            while subscriptions.len() < 2 {
+                let request = match ws.next().await {
+                    Some(Ok(Message::Text(text))) => serde_json::from_str::<Value>(&text).unwrap(),
+                    other => panic!("expected a subscription request, got {:?}", other),
+                };
+                let address = request["params"]["address"].as_str().unwrap().to_string();
+                let subscription = format!("{}-{}", address, connection);
+                ws.send(Message::Text(json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": subscription
+                }).to_string())).await.unwrap();
+                subscriptions.push(subscription);
+            }
+
+            for subscription in &subscriptions {
+                ws.send(Message::Text(json!({
+                    "jsonrpc": "2.0",
+                    "method": "balance_subscription",
+                    "params": {
+                        "subscription": subscription,
+                        "result": { "free": connection * 100 }
+                    }
+                }).to_string())).await.unwrap();
+            }
+
+            if connection == 1 {
+                ws.close(None).await.unwrap();
+            } else {
+                // Hold the second connection open until the test finishes
+                while ws.next().await.is_some() {}
+            }
+        }
+    });
+
+    format!("ws://{}", addr)
+}
+
+#[tokio::test]
+async fn test_subscriptions_survive_reconnect() {
+    let url = start_ws_mock().await;
+    let manager = SubscriptionManager::with_reconnect_delay(&url, Duration::from_millis(10));
+
+    let mut first = Box::pin(manager
+        .subscribe("balance_subscribe", json!({ "address": "cmx1abcd123" }))
+        .await
+        .unwrap());
+    let mut second = Box::pin(manager
+        .subscribe("balance_subscribe", json!({ "address": "cmx1efgh456" }))
+        .await
+        .unwrap());
+
+    let timeout = Duration::from_secs(5);
+    for expected in [100, 200] {
+        let update = tokio::time::timeout(timeout, first.next()).await.unwrap().unwrap().unwrap();
+        assert_eq!(update["free"], expected);
+        let update = tokio::time::timeout(timeout, second.next()).await.unwrap().unwrap().unwrap();
+        assert_eq!(update["free"], expected);
+    }
+
+    let mut ids = manager.subscription_ids().await.unwrap();
+    ids.sort_by_key(|id| id.to_string());
+    assert_eq!(ids, vec![json!("cmx1abcd123-2"), json!("cmx1efgh456-2")]);
+}
+
+#[tokio::test]
+async fn test_subscribe_fails_when_unreachable() {
+    let manager = SubscriptionManager::with_reconnect_delay("ws://127.0.0.1:1", Duration::from_millis(10));
+
+    let result = manager.subscribe("balance_subscribe", json!({ "address": "cmx1abcd123" })).await;
+    assert!(result.is_err());
+}
+
+/// Serve one connection that answers the first request with `reply` merged
+/// over its id, then forwards every later request to the returned channel
+async fn start_single_reply_mock(reply: Value) -> (String, tokio::sync::mpsc::UnboundedReceiver<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (requests, received) = tokio::sync::mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        let mut replied = false;
+        while let Some(Ok(Message::Text(text))) = ws.next().await {
+            let request: Value = serde_json::from_str(&text).unwrap();
+            if replied {
+                let _ = requests.send(request);
+                continue;
+            }
+            let mut response = reply.clone();
+            response["id"] = request["id"].clone();
+            ws.send(Message::Text(response.to_string())).await.unwrap();
+            replied = true;
+        }
+    });
+
+    (format!("ws://{}", addr), received)
+}
+
+#[tokio::test]
+async fn test_dropping_stream_unsubscribes() {
+    let (url, mut requests) = start_single_reply_mock(json!({ "jsonrpc": "2.0", "result": "sub-1" })).await;
+    let manager = SubscriptionManager::with_reconnect_delay(&url, Duration::from_millis(10));
+
+    let updates = manager
+        .subscribe("balance_subscribe", json!({ "address": "cmx1abcd123" }))
+        .await
+        .unwrap();
+    drop(updates);
+
+    let request = tokio::time::timeout(Duration::from_secs(5), requests.recv()).await.unwrap().unwrap();
+    assert_eq!(request["method"], "balance_unsubscribe");
+    assert_eq!(request["params"], json!(["sub-1"]));
+    assert!(manager.subscription_ids().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_subscribe_fails_without_subscription_id() {
+    let (url, _requests) = start_single_reply_mock(json!({ "jsonrpc": "2.0" })).await;
+    let manager = SubscriptionManager::with_reconnect_delay(&url, Duration::from_millis(10));
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(5),
+        manager.subscribe("balance_subscribe", json!({ "address": "cmx1abcd123" })),
+    ).await.unwrap();
+    assert!(matches!(result, Err(CommunexError::MalformedResponse(_))));
+}
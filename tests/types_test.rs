@@ -1,8 +1,12 @@
 use comx_api::{
-    types::{Address, Balance, Transaction, SignedTransaction},
+    types::{Address, Balance, DenomRegistry, Netuid, Transaction, SignedTransaction, SIGNING_PAYLOAD_VERSION},
     crypto::KeyPair,
+    CommunexError,
 };
 use serde_json::json;
+use sp_core::sr25519::{PUBLIC_KEY_SERIALIZED_SIZE, SIGNATURE_SERIALIZED_SIZE};
+use std::collections::HashSet;
+use std::path::Path;
 
 #[test]
 fn test_address_validation() {
@@ -16,6 +20,52 @@ fn test_address_validation() {
     assert!(Address::new(invalid_address).is_err());
 }
 
+#[test]
+fn test_address_parse() {
+    let address: Address = "cmx1abc123def456".parse().unwrap();
+    assert_eq!(address, Address::new("cmx1abc123def456").unwrap());
+    assert_eq!(Address::try_from("cmx1abc123def456").unwrap(), address);
+    assert_eq!(Address::try_from(String::from("cmx1abc123def456")).unwrap(), address);
+
+    assert!(matches!(
+        "invalid_address".parse::<Address>(),
+        Err(CommunexError::InvalidAddress(_))
+    ));
+}
+
+#[test]
+fn test_address_public_key_bytes() {
+    let keypair = KeyPair::generate();
+    let address = Address::new(keypair.cmx_address()).unwrap();
+    assert_eq!(address.public_key_bytes().unwrap(), keypair.public_key());
+
+    // Valid base58, but not a 32-byte key
+    let short = Address::new("cmx1abc123def456").unwrap();
+    assert!(matches!(short.public_key_bytes(), Err(CommunexError::InvalidAddress(_))));
+}
+
+#[test]
+fn test_keypair_cmx_address() {
+    let keypair = KeyPair::generate();
+    let address = keypair.cmx_address();
+
+    assert!(address.starts_with("cmx1"));
+    assert!(Address::new(address.as_str()).is_ok());
+    assert_ne!(address, keypair.ss58_address());
+}
+
+#[test]
+fn test_address_set_membership() {
+    let addresses: HashSet<Address> = ["cmx1addr1", "cmx1addr2", "cmx1addr1"]
+        .into_iter()
+        .map(|address| Address::new(address).unwrap())
+        .collect();
+
+    assert_eq!(addresses.len(), 2);
+    assert!(addresses.contains(&Address::new("cmx1addr1").unwrap()));
+    assert!(!addresses.contains(&Address::new("cmx1addr3").unwrap()));
+}
+
 #[test]
 fn test_balance_parsing() {
     let balance_json = json!({
@@ -84,14 +134,31 @@ fn test_transaction_signing() {
     let different_seed = "field mistake sustain bench foster cactus anxiety until riot capable obscure service"; // Test key2.json
     let different_keypair = KeyPair::from_seed_phrase(different_seed).unwrap();
     
-    // Convert public key to fixed-size array
-    let public_key: [u8; 32] = different_keypair.public_key()
-        .try_into()
-        .expect("Invalid public key length");
+    let public_key: [u8; 32] = different_keypair.public_key();
         
     assert!(signed_tx.verify_signature_with_key(&public_key).is_err());
 }
 
+#[test]
+fn test_keypair_sign_transaction() {
+    let keypair = KeyPair::generate();
+    let tx = Transaction::new(keypair.cmx_address(), "cmx1receiver", "1000", "COMAI", "");
+
+    let signed_tx = keypair.sign_transaction(&tx).unwrap();
+    assert_eq!(signed_tx.transaction, tx);
+    assert_eq!(signed_tx.public_key, keypair.public_key());
+    assert!(signed_tx.verify_signature().is_ok());
+}
+
+#[test]
+fn test_keypair_sign_hex() {
+    let keypair = KeyPair::generate();
+    let signature = hex::decode(keypair.sign_hex(b"message")).unwrap();
+
+    assert_eq!(signature.len(), SIGNATURE_SERIALIZED_SIZE);
+    assert!(keypair.verify(b"message", &signature.try_into().unwrap()));
+}
+
 #[test]
 fn test_keypair_address_derivation() {
     let seed_phrase = "wait swarm general shield hope target rebuild profit later pepper under hunt";
@@ -124,6 +191,91 @@ fn test_keypair_from_testkey() {
     );
 }
 
+#[test]
+fn test_keypair_from_key_file() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/testkey.json");
+    let keypair = KeyPair::from_key_file(&path).unwrap();
+
+    assert_eq!(keypair.ss58_address(), "5CfjkoBAQ2LvJRmdcsoWXKSZkzR4k2KvpDVf2u1ohgm3UczR");
+}
+
+#[test]
+fn test_keypair_from_key_file_with_wrong_address() {
+    let path = std::env::temp_dir().join(format!("comx-wrong-address-{}.json", std::process::id()));
+    let data = json!({
+        "mnemonic": "wait swarm general shield hope target rebuild profit later pepper under hunt",
+        "ss58_address": KeyPair::generate().ss58_address(),
+    });
+    std::fs::write(&path, json!({ "data": data.to_string() }).to_string()).unwrap();
+
+    let result = KeyPair::from_key_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(result, Err(CommunexError::KeyFileError(_))));
+}
+
+#[test]
+fn test_keypair_key_file_round_trip() {
+    let dir = std::env::temp_dir();
+    let phrase = "wait swarm general shield hope target rebuild profit later pepper under hunt";
+
+    for (name, keypair) in [
+        ("restored", KeyPair::from_seed_phrase(phrase).unwrap()),
+        ("generated", KeyPair::generate()),
+    ] {
+        let path = dir.join(format!("comx-{}-{}.json", name, std::process::id()));
+        keypair.save_key_file(&path, name).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let loaded = KeyPair::from_key_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap().ss58_address(), keypair.ss58_address());
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_keypair_key_file_overwrite_restricts_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = std::env::temp_dir().join(format!("comx-overwrite-{}.json", std::process::id()));
+    std::fs::write(&path, "{}").unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+    let keypair = KeyPair::generate();
+    keypair.save_key_file(&path, "overwrite").unwrap();
+
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    let loaded = KeyPair::from_key_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(mode & 0o777, 0o600);
+    assert_eq!(loaded.unwrap().ss58_address(), keypair.ss58_address());
+}
+
+#[test]
+fn test_keypair_key_file_save_leaves_sibling_files() {
+    let dir = std::env::temp_dir().join(format!("comx-siblings-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let sibling = dir.join("alice.tmp");
+    std::fs::write(&sibling, "unrelated").unwrap();
+
+    KeyPair::generate().save_key_file(&dir.join("alice.json"), "alice").unwrap();
+
+    let sibling_contents = std::fs::read_to_string(&sibling);
+    let mut names: Vec<_> = std::fs::read_dir(&dir).unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(sibling_contents.unwrap(), "unrelated");
+    assert_eq!(names, vec!["alice.json", "alice.tmp"]);
+}
+
 #[test]
 fn test_transaction_serialization() {
     let tx = Transaction::new(
@@ -161,6 +313,62 @@ fn test_signed_transaction_serialization() {
     assert!(deserialized.verify_signature().is_ok());
 }
 
+#[test]
+fn test_signed_transaction_hex_fields_round_trip() {
+    let keypair = KeyPair::generate();
+    let signed_tx = Transaction::new(keypair.cmx_address(), "cmx1receiver", "1000000", "COMAI", "")
+        .sign(&keypair)
+        .unwrap();
+
+    let value = serde_json::to_value(&signed_tx).unwrap();
+    assert_eq!(value["signature"], json!(hex::encode(signed_tx.signature)));
+    assert_eq!(value["public_key"], json!(keypair.public_key_hex()));
+    assert_eq!(value["signature"].as_str().unwrap().len(), 128);
+    assert_eq!(value["public_key"].as_str().unwrap().len(), 64);
+
+    let deserialized: SignedTransaction = serde_json::from_value(value).unwrap();
+    assert_eq!(deserialized.signature, signed_tx.signature);
+    assert_eq!(deserialized.public_key, signed_tx.public_key);
+
+    let mut truncated = serde_json::to_value(&signed_tx).unwrap();
+    truncated["public_key"] = json!(hex::encode([0u8; 31]));
+    assert!(serde_json::from_value::<SignedTransaction>(truncated).is_err());
+}
+
+#[test]
+fn test_signed_transaction_uses_sr25519_sizes() {
+    let keypair = KeyPair::generate();
+    let signed_tx = Transaction::new(keypair.cmx_address(), "cmx1receiver", "1", "COMAI", "")
+        .sign(&keypair)
+        .unwrap();
+
+    assert_eq!(signed_tx.signature.len(), SIGNATURE_SERIALIZED_SIZE);
+    assert_eq!(signed_tx.public_key.len(), PUBLIC_KEY_SERIALIZED_SIZE);
+}
+
+#[test]
+fn test_signing_payload_version() {
+    let keypair = KeyPair::generate();
+    let tx = Transaction::new(keypair.cmx_address(), "cmx1receiver", "1000", "COMAI", "");
+
+    let payload: serde_json::Value = serde_json::from_slice(&tx.signing_payload().unwrap()).unwrap();
+    assert_eq!(payload["version"], json!(SIGNING_PAYLOAD_VERSION));
+
+    // A signature over a payload with another version does not verify
+    let current = String::from_utf8(tx.signing_payload().unwrap()).unwrap();
+    let other = current.replacen(
+        &format!("\"version\":{}", SIGNING_PAYLOAD_VERSION),
+        &format!("\"version\":{}", SIGNING_PAYLOAD_VERSION + 1),
+        1,
+    );
+    assert_ne!(current, other);
+
+    let mut signed_tx = tx.sign(&keypair).unwrap();
+    assert!(signed_tx.verify_signature().is_ok());
+    signed_tx.signature = keypair.sign(other.as_bytes());
+    assert!(matches!(signed_tx.verify_signature(), Err(CommunexError::InvalidSignature(_))));
+}
+
 #[test]
 fn test_transaction_with_zero_amount() {
     let tx = Transaction::new(
@@ -183,6 +391,30 @@ fn test_invalid_denomination() {
     assert!(balance_result.is_err());
 }
 
+#[test]
+fn test_custom_denomination_registry() {
+    let denoms = DenomRegistry::default().with_denom("TCOMAI", 12);
+    assert_eq!(denoms.decimals("COMAI"), Some(9));
+    assert_eq!(denoms.decimals("TCOMAI"), Some(12));
+    assert_eq!(denoms.denoms(), vec!["COMAI", "TCOMAI"]);
+
+    let balance_json = json!({ "amount": "1000000", "denom": "TCOMAI" });
+    let balance = Balance::from_rpc_with_registry(&balance_json, &denoms).unwrap();
+    assert_eq!(balance.denom(), "TCOMAI");
+    assert!(Balance::new_with_registry("5", "TCOMAI", &denoms).is_ok());
+
+    // The default registry only knows COMAI
+    assert!(matches!(Balance::from_rpc(&balance_json), Err(CommunexError::InvalidDenom(_))));
+    assert!(matches!(
+        Balance::new_with_registry("5", "COMAI", &DenomRegistry::empty()),
+        Err(CommunexError::InvalidDenom(_))
+    ));
+
+    let tx = Transaction::new("cmx1sender", "cmx1receiver", "100", "TCOMAI", "");
+    assert!(tx.validate().is_err());
+    assert!(tx.validate_with_registry(&denoms).is_ok());
+}
+
 #[test]
 fn test_large_amount_parsing() {
     let balance_json = json!({
@@ -197,4 +429,90 @@ fn test_large_amount_parsing() {
 fn test_invalid_address_characters() {
     let invalid_address = "cmx1$%^&*()";
     assert!(Address::new(invalid_address).is_err());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_netuid_validation() {
+    let netuid = Netuid::new(2).unwrap();
+    assert_eq!(netuid.value(), 2);
+    assert!(Netuid::new(0).is_ok());
+
+    // Values at or above the subnet limit are rejected
+    assert!(Netuid::new(256).is_err());
+    assert!(Netuid::try_from(u16::MAX).is_err());
+}
+
+#[test]
+fn test_netuid_serialization() {
+    let netuid = Netuid::new(17).unwrap();
+    assert_eq!(serde_json::to_value(netuid).unwrap(), json!(17));
+
+    let parsed: Netuid = serde_json::from_value(json!(17)).unwrap();
+    assert_eq!(parsed, netuid);
+
+    // Deserialization applies the same validation as construction
+    assert!(serde_json::from_value::<Netuid>(json!(1000)).is_err());
+}
+
+#[test]
+fn test_transaction_scale_round_trip() {
+    let tx = Transaction::new(
+        "cmx1sender",
+        "cmx1receiver",
+        "1000000",
+        "COMAI",
+        "transfer tokens",
+    );
+
+    let encoded = tx.to_scale_bytes();
+    let decoded = Transaction::from_scale_bytes(&encoded).unwrap();
+    assert_eq!(decoded, tx);
+
+    // Truncated or padded input is rejected
+    assert!(Transaction::from_scale_bytes(&encoded[..encoded.len() - 1]).is_err());
+    let mut padded = encoded.clone();
+    padded.push(0);
+    assert!(Transaction::from_scale_bytes(&padded).is_err());
+}
+
+#[test]
+fn test_signed_transaction_hex_round_trip() {
+    let seed_phrase = "wait swarm general shield hope target rebuild profit later pepper under hunt";
+    let keypair = KeyPair::from_seed_phrase(seed_phrase).unwrap();
+
+    let tx = Transaction::new(
+        keypair.ss58_address(),
+        "cmx1receiver...",
+        "1000000",
+        "COMAI",
+        "transfer tokens",
+    );
+
+    let signed_tx = tx.sign(&keypair).unwrap();
+    let encoded = signed_tx.to_hex();
+    let decoded = SignedTransaction::from_hex(&encoded).unwrap();
+
+    assert_eq!(decoded.transaction, tx);
+    assert!(decoded.verify_signature().is_ok());
+    assert!(SignedTransaction::from_hex(&format!("0x{}", encoded)).is_ok());
+    assert!(SignedTransaction::from_hex("not hex").is_err());
+}
+
+#[test]
+fn test_error_matching_needs_wildcard() {
+    use comx_api::CommunexError;
+
+    // CommunexError is #[non_exhaustive]: downstream matches must keep a
+    // wildcard arm so new variants are not a breaking change
+    fn describe(error: &CommunexError) -> &'static str {
+        match error {
+            CommunexError::ValidationError(_) => "validation",
+            CommunexError::RpcError { .. } => "rpc",
+            _ => "other",
+        }
+    }
+
+    assert_eq!(describe(&CommunexError::ValidationError("bad".into())), "validation");
+    assert_eq!(describe(&CommunexError::RpcError { code: -32000, message: "boom".into() }), "rpc");
+    assert_eq!(describe(&CommunexError::CacheError("full".into())), "other");
+}
@@ -1,14 +1,22 @@
 use comx_api::{
-    wallet::{WalletClient, TransferRequest, Txstate, TransactionStatus, staking::StakeRequest},
+    wallet::{WalletClient, TransferRequest, TxStatus, staking::StakeRequest, subnet::RegisterModuleRequest},
     error::CommunexError,
+    types::DenomRegistry,
+    rpc::{RpcTransport, BatchRequest, BatchResponse},
 };
 use wiremock::{
     Mock, 
     MockServer,
     ResponseTemplate,
-    matchers::{method, path, body_json}
+    matchers::{method, path, body_partial_json}
 };
-use serde_json::json;
+use serde_json::{json, Value};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+#[path = "common/mod.rs"]
+mod common;
+use common::rpc_response;
 
 #[tokio::test]
 async fn test_transfer_success() {
@@ -16,9 +24,8 @@ async fn test_transfer_success() {
     
     Mock::given(method("POST"))
         .and(path("/transfer"))
-        .and(body_json(json!({
+        .and(body_partial_json(json!({
             "jsonrpc": "2.0",
-            "id": 1,
             "method": "transfer",
             "params": {
                 "from": "cmx1abcd123",
@@ -27,8 +34,7 @@ async fn test_transfer_success() {
                 "denom": "COMAI"
             }
         })))
-        .respond_with(ResponseTemplate::new(200)
-            .set_body_json(json!({
+        .respond_with(rpc_response(json!({
                 "jsonrpc": "2.0",
                 "id": 1,
                 "result": {
@@ -52,15 +58,94 @@ async fn test_transfer_success() {
     assert!(result.is_ok());
 }
 
+#[tokio::test]
+async fn test_transfer_balance_precheck() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/balance/free"))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "result": { "free": 500 }
+        })))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/transfer"))
+        .and(body_partial_json(json!({ "params": { "amount": "500" } })))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "result": { "status": "success" }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/transfer"))
+        .and(body_partial_json(json!({ "params": { "amount": "1000" } })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri()).with_balance_precheck(true);
+    let request = |amount| TransferRequest {
+        from: "cmx1abcd123".into(),
+        to: "cmx1efgh456".into(),
+        amount,
+        denom: "COMAI".into(),
+    };
+
+    assert_eq!(
+        client.transfer(request(1000)).await.unwrap_err(),
+        CommunexError::InsufficientFunds { required: 1000, available: 500 }
+    );
+    assert!(client.transfer(request(500)).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_transfer_in_registered_denom() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/transfer"))
+        .and(body_partial_json(json!({
+            "params": { "denom": "TCOMAI" }
+        })))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "result": { "status": "success" }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let request = TransferRequest {
+        from: "cmx1abcd123".into(),
+        to: "cmx1efgh456".into(),
+        amount: 1000,
+        denom: "TCOMAI".into(),
+    };
+
+    let client = WalletClient::new(&mock_server.uri());
+    assert!(matches!(
+        client.transfer(request.clone()).await,
+        Err(CommunexError::RpcError { code: -32003, .. })
+    ));
+
+    let client = client.with_denoms(DenomRegistry::default().with_denom("TCOMAI", 9));
+    assert!(client.transfer(request).await.is_ok());
+}
+
 #[tokio::test]
 async fn test_transfer_insufficient_funds() {
     let mock_server = MockServer::start().await;
     
     Mock::given(method("POST"))
         .and(path("/transfer"))
-        .and(body_json(json!({
+        .and(body_partial_json(json!({
             "jsonrpc": "2.0",
-            "id": 1,
             "method": "transfer",
             "params": {
                 "from": "cmx1abcd123",
@@ -69,8 +154,7 @@ async fn test_transfer_insufficient_funds() {
                 "denom": "COMAI"
             }
         })))
-        .respond_with(ResponseTemplate::new(200)
-            .set_body_json(json!({
+        .respond_with(rpc_response(json!({
                 "jsonrpc": "2.0",
                 "id": 1,
                 "error": {
@@ -98,22 +182,78 @@ async fn test_transfer_insufficient_funds() {
     ));
 }
 
+#[tokio::test]
+async fn test_transfer_reads_rpc_error_from_http_error_body() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/transfer"))
+        .respond_with(ResponseTemplate::new(500).set_body_json(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": {
+                "code": -32000,
+                "message": "insufficient funds"
+            }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+
+    let request = TransferRequest {
+        from: "cmx1abcd123".into(),
+        to: "cmx1efgh456".into(),
+        amount: 1000000000,
+        denom: "COMAI".into(),
+    };
+
+    let result = client.transfer(request).await;
+    assert!(matches!(
+        result,
+        Err(CommunexError::RpcError { code: -32000, ref message }) if message == "Insufficient funds"
+    ));
+}
+
+#[tokio::test]
+async fn test_failed_transfer_is_not_retried() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/transfer"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+
+    let request = TransferRequest {
+        from: "cmx1abcd123".into(),
+        to: "cmx1efgh456".into(),
+        amount: 1000,
+        denom: "COMAI".into(),
+    };
+
+    let result = client.transfer(request).await;
+    assert!(matches!(result, Err(CommunexError::RpcError { code: 500, .. })));
+}
+
 #[tokio::test]
 async fn test_get_free_balance() {
     let mock_server = MockServer::start().await;
     
     Mock::given(method("POST"))
         .and(path("/balance/free"))
-        .and(body_json(json!({
+        .and(body_partial_json(json!({
             "jsonrpc": "2.0",
-            "id": 1,
             "method": "balance/free",
             "params": {
                 "address": "cmx1abcd123"
             }
         })))
-        .respond_with(ResponseTemplate::new(200)
-            .set_body_json(json!({
+        .respond_with(rpc_response(json!({
                 "jsonrpc": "2.0",
                 "id": 1,
                 "result": {
@@ -129,14 +269,35 @@ async fn test_get_free_balance() {
     assert_eq!(balance, 1000000);
 }
 
+#[tokio::test]
+async fn test_cloned_clients_share_node() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/balance/free"))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "result": { "free": 1000000 }
+        })))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    let clone = client.clone();
+    let task = tokio::spawn(async move { clone.get_free_balance("cmx1abcd123").await });
+
+    assert_eq!(client.get_free_balance("cmx1abcd123").await.unwrap(), 1000000);
+    assert_eq!(task.await.unwrap().unwrap(), 1000000);
+}
+
 #[tokio::test]
 async fn test_get_all_balances() {
     let mock_server = MockServer::start().await;
     
     Mock::given(method("POST"))
         .and(path("/balance/all"))
-        .respond_with(ResponseTemplate::new(200)
-            .set_body_json(json!({
+        .respond_with(rpc_response(json!({
                 "jsonrpc": "2.0",
                 "id": 1,
                 "result": {
@@ -159,22 +320,115 @@ async fn test_get_all_balances() {
     assert_eq!(balances.fee_frozen, 5000);
 }
 
+#[tokio::test]
+async fn test_strict_parsing_rejects_unknown_fields() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/balance/all"))
+        .respond_with(rpc_response(json!({
+                "jsonrpc": "2.0",
+                "result": {
+                    "free": 1000000,
+                    "reserved": 0,
+                    "miscFrozen": 0,
+                    "feeFrozen": 0,
+                    "vesting": 500
+                }
+            })))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let lenient = WalletClient::new(&mock_server.uri());
+    assert_eq!(lenient.get_all_balances("cmx1abcd123").await.unwrap().free, 1000000);
+
+    let strict = WalletClient::new(&mock_server.uri()).with_strict_parsing(true);
+    match strict.get_all_balances("cmx1abcd123").await {
+        Err(CommunexError::ParseError(message)) => {
+            assert!(message.contains("'vesting'"), "{}", message);
+            assert!(message.contains("balance/all"), "{}", message);
+        }
+        other => panic!("expected ParseError, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_get_all_balances_with_block_num() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/balance/all"))
+        .respond_with(rpc_response(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "free": 1000000,
+                    "reserved": 0,
+                    "miscFrozen": 0,
+                    "feeFrozen": 0,
+                    "block_num": 424242
+                }
+            })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    let before = chrono::Utc::now();
+    let balances = client.get_all_balances("cmx1abcd123").await.unwrap();
+
+    assert_eq!(balances.free, 1000000);
+    assert_eq!(balances.block_num, Some(424242));
+    assert!(balances.fetched_at >= before);
+    assert!(balances.fetched_at <= chrono::Utc::now());
+}
+
+#[tokio::test]
+async fn test_get_nonce() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/account/nonce"))
+        .and(body_partial_json(json!({
+            "jsonrpc": "2.0",
+            "method": "account/nonce",
+            "params": {
+                "address": "cmx1abcd123"
+            }
+        })))
+        .respond_with(rpc_response(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "nonce": 7
+                }
+            })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    assert_eq!(client.get_nonce("cmx1abcd123").await.unwrap(), 7);
+
+    let invalid = client.get_nonce("invalid_address").await;
+    assert!(matches!(invalid, Err(CommunexError::RpcError { code: -32001, .. })));
+}
+
 #[tokio::test]
 async fn test_get_transaction_history() {
     let mock_server = MockServer::start().await;
     
     Mock::given(method("POST"))
         .and(path("/transaction/history"))
-        .and(body_json(json!({
+        .and(body_partial_json(json!({
             "jsonrpc": "2.0",
-            "id": 1,
             "method": "transaction/history",
             "params": {
                 "address": "cmx1abcd123"
             }
         })))
-        .respond_with(ResponseTemplate::new(200)
-            .set_body_json(json!({
+        .respond_with(rpc_response(json!({
                 "jsonrpc": "2.0",
                 "id": 1,
                 "result": {
@@ -212,10 +466,10 @@ async fn test_get_transaction_history() {
     assert_eq!(history.len(), 2);
     assert_eq!(history[0].hash, "0x123...");
     assert_eq!(history[0].amount, 1000);
-    assert!(matches!(history[0].state, TransactionStatus::Success));
+    assert!(matches!(history[0].state, TxStatus::Success));
     assert_eq!(history[1].hash, "0x456...");
     assert_eq!(history[1].amount, 2000);
-    assert!(matches!(history[1].state, TransactionStatus::Pending));
+    assert!(matches!(history[1].state, TxStatus::Pending));
 }
 
 #[tokio::test]
@@ -237,9 +491,8 @@ async fn test_stake_tokens() -> Result<(), CommunexError> {
     // Mock the stake request with the correct path and body matcher
     Mock::given(method("POST"))
         .and(path("/staking/stake"))
-        .and(body_json(json!({
+        .and(body_partial_json(json!({
             "jsonrpc": "2.0",
-            "id": 1,
             "method": "staking/stake",
             "params": {
                 "from": "cmx1sender...",
@@ -247,7 +500,7 @@ async fn test_stake_tokens() -> Result<(), CommunexError> {
                 "denom": "COMAI"
             }
         })))
-        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        .respond_with(rpc_response(json!({
             "jsonrpc": "2.0",
             "id": 1,
             "result": {
@@ -262,15 +515,14 @@ async fn test_stake_tokens() -> Result<(), CommunexError> {
     // Mock the transaction status check
     Mock::given(method("POST"))
         .and(path("/transaction/state"))
-        .and(body_json(json!({
+        .and(body_partial_json(json!({
             "jsonrpc": "2.0",
-            "id": 1,
             "method": "transaction/state",
             "params": {
                 "hash": "0xabcd1234"
             }
         })))
-        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+        .respond_with(rpc_response(json!({
             "jsonrpc": "2.0",
             "id": 1,
             "result": {
@@ -294,8 +546,98 @@ async fn test_stake_tokens() -> Result<(), CommunexError> {
     };
 
     let result = client.stake(stake_request).await?;
-    assert!(matches!(result.state, Txstate::Success));
+    assert!(matches!(result.state, TxStatus::Success));
+
+    Ok(())
+}
+
+async fn mount_claim_mocks(mock_server: &MockServer) {
+    // The first claim attempt fails as if the response were lost
+    Mock::given(method("POST"))
+        .and(path("/staking/claim"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .mount(mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/staking/claim"))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "result": { "hash": "0xc1a1m" }
+        })))
+        .mount(mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/transaction/state"))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "result": {
+                "state": "success",
+                "confirmations": 1,
+                "block_num": 12345,
+                "timestamp": 1704067200
+            }
+        })))
+        .mount(mock_server)
+        .await;
+}
+
+async fn sent_claim_keys(mock_server: &MockServer) -> Vec<String> {
+    mock_server.received_requests().await.unwrap().iter()
+        .filter(|request| request.url.path() == "/staking/claim")
+        .map(|request| request.body_json::<Value>().unwrap()["params"]["idempotency_key"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_claim_rewards_reuses_idempotency_key_on_retry() -> Result<(), CommunexError> {
+    let mock_server = MockServer::start().await;
+    mount_claim_mocks(&mock_server).await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    let result = client.claim_rewards("cmx1abcd123").await?;
+    assert!(matches!(result.state, TxStatus::Success));
+
+    let keys = sent_claim_keys(&mock_server).await;
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0], keys[1]);
+    assert_eq!(keys[0].len(), 32);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_claim_rewards_with_key_sends_key() -> Result<(), CommunexError> {
+    let mock_server = MockServer::start().await;
+    mount_claim_mocks(&mock_server).await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    client.claim_rewards_with_key("cmx1abcd123", "claim-2024-01-01").await?;
+
+    assert_eq!(sent_claim_keys(&mock_server).await, vec!["claim-2024-01-01", "claim-2024-01-01"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_claim_rewards_reuses_key_until_claim_settles() -> Result<(), CommunexError> {
+    let mock_server = MockServer::start().await;
+    // Every attempt of the first call fails as if the response were lost
+    Mock::given(method("POST"))
+        .and(path("/staking/claim"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(3)
+        .mount(&mock_server)
+        .await;
+    mount_claim_mocks(&mock_server).await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    assert!(client.claim_rewards("cmx1abcd123").await.is_err());
+    client.clone().claim_rewards("cmx1abcd123").await?;
+    client.claim_rewards("cmx1abcd123").await?;
 
+    let keys = sent_claim_keys(&mock_server).await;
+    assert_eq!(keys.len(), 6);
+    assert!(keys[..5].iter().all(|key| *key == keys[0]));
+    assert_ne!(keys[5], keys[0]);
     Ok(())
 }
 
@@ -305,8 +647,7 @@ async fn test_get_staking_info() {
     
     Mock::given(method("POST"))
         .and(path("/staking/info"))
-        .respond_with(ResponseTemplate::new(200)
-            .set_body_json(json!({
+        .respond_with(rpc_response(json!({
                 "jsonrpc": "2.0",
                 "id": 1,
                 "result": {
@@ -328,22 +669,84 @@ async fn test_get_staking_info() {
     assert_eq!(info.denom, "COMAI");
 }
 
+#[tokio::test]
+async fn test_get_portfolio() -> Result<(), CommunexError> {
+    let mock_server = MockServer::start().await;
+
+    let endpoints = [
+        ("/balance/all", json!({ "free": 1000, "reserved": 200, "miscFrozen": 0, "feeFrozen": 0 })),
+        ("/balance/staked", json!({ "staked": 5000 })),
+        ("/staking/info", json!({
+            "total_staked": 5000,
+            "rewards_available": 75,
+            "last_claim_time": 1704067200,
+            "denom": "COMAI"
+        })),
+    ];
+    for (endpoint, result) in endpoints {
+        Mock::given(method("POST"))
+            .and(path(endpoint))
+            .and(body_partial_json(json!({ "params": { "address": "cmx1abcd123" } })))
+            .respond_with(rpc_response(json!({ "jsonrpc": "2.0", "result": result })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+    }
+
+    let client = WalletClient::new(&mock_server.uri());
+    let portfolio = client.get_portfolio("cmx1abcd123").await?;
+
+    assert_eq!(portfolio.free, 1000);
+    assert_eq!(portfolio.reserved, 200);
+    assert_eq!(portfolio.staked, 5000);
+    assert_eq!(portfolio.pending_rewards, 75);
+    assert_eq!(portfolio.total(), 6275);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_portfolio_fetches_concurrently() -> Result<(), CommunexError> {
+    let mock_server = MockServer::start().await;
+    let delay = std::time::Duration::from_millis(400);
+
+    for (endpoint, result) in [
+        ("/balance/all", json!({ "free": 1 })),
+        ("/balance/staked", json!({ "staked": 2 })),
+        ("/staking/info", json!({ "rewards_available": 3, "last_claim_time": 1704067200 })),
+    ] {
+        let respond = rpc_response(json!({ "jsonrpc": "2.0", "result": result }));
+        Mock::given(method("POST"))
+            .and(path(endpoint))
+            .respond_with(move |request: &wiremock::Request| respond(request).set_delay(delay))
+            .mount(&mock_server)
+            .await;
+    }
+
+    let client = WalletClient::new(&mock_server.uri());
+    let started = std::time::Instant::now();
+    let portfolio = client.get_portfolio("cmx1abcd123").await?;
+    let elapsed = started.elapsed();
+
+    assert_eq!(portfolio.total(), 6);
+    // Sequential requests would take at least three delays
+    assert!(elapsed < delay * 2, "took {:?}", elapsed);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_get_transaction_status() {
     let mock_server = MockServer::start().await;
     
     Mock::given(method("POST"))
         .and(path("/transaction/state"))
-        .and(body_json(json!({
+        .and(body_partial_json(json!({
             "jsonrpc": "2.0",
-            "id": 1,
             "method": "transaction/state",
             "params": {
                 "hash": "0x123..."
             }
         })))
-        .respond_with(ResponseTemplate::new(200)
-            .set_body_json(json!({
+        .respond_with(rpc_response(json!({
                 "jsonrpc": "2.0",
                 "id": 1,
                 "result": {
@@ -364,6 +767,563 @@ async fn test_get_transaction_status() {
     assert_eq!(status.hash, "0x123...");
     assert_eq!(status.block_num, Some(12345));
     assert_eq!(status.confirmations, 5);
-    assert!(matches!(status.state, Txstate::Success));
+    assert!(matches!(status.state, TxStatus::Success));
     assert!(status.error.is_none());
-} 
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_register_module() -> Result<(), CommunexError> {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/subnet/register"))
+        .and(body_partial_json(json!({
+            "jsonrpc": "2.0",
+            "method": "subnet/register",
+            "params": {
+                "from": "cmx1sender",
+                "netuid": 1,
+                "name": "my_module",
+                "address": "127.0.0.1:8000"
+            }
+        })))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "hash": "0xfeed"
+            }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/transaction/state"))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "state": "success",
+                "confirmations": 1,
+                "block_num": 12345,
+                "timestamp": 1704067200
+            }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    let result = client.register_module(RegisterModuleRequest {
+        from: "cmx1sender".to_string(),
+        netuid: 1,
+        name: "my_module".to_string(),
+        address: "127.0.0.1:8000".to_string(),
+    }).await?;
+
+    assert_eq!(result.hash, "0xfeed");
+    assert!(matches!(result.state, TxStatus::Success));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_register_module_validation() {
+    let mock_server = MockServer::start().await;
+    let client = WalletClient::new(&mock_server.uri());
+
+    let request = RegisterModuleRequest {
+        from: "cmx1sender".to_string(),
+        netuid: 1,
+        name: "a".repeat(33),
+        address: "127.0.0.1:8000".to_string(),
+    };
+    let result = client.register_module(request.clone()).await;
+    assert!(matches!(result, Err(CommunexError::ValidationError(_))));
+
+    let result = client.register_module(RegisterModuleRequest {
+        name: "my_module".to_string(),
+        address: "not-an-address".to_string(),
+        ..request
+    }).await;
+    assert!(matches!(result, Err(CommunexError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_set_weights() -> Result<(), CommunexError> {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/subnet/set_weights"))
+        .and(body_partial_json(json!({
+            "jsonrpc": "2.0",
+            "method": "subnet/set_weights",
+            "params": {
+                "from": "cmx1validator",
+                "netuid": 2,
+                "uids": [0, 1, 2],
+                "weights": [100, 200, 300]
+            }
+        })))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "hash": "0xweights"
+            }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/transaction/state"))
+        .respond_with(rpc_response(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "state": "success",
+                "confirmations": 1,
+                "block_num": 12345,
+                "timestamp": 1704067200
+            }
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    let result = client.set_weights("cmx1validator", 2, vec![0, 1, 2], vec![100, 200, 300]).await?;
+
+    assert!(matches!(result.state, TxStatus::Success));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_weights_length_mismatch() {
+    let mock_server = MockServer::start().await;
+    let client = WalletClient::new(&mock_server.uri());
+
+    let result = client.set_weights("cmx1validator", 2, vec![0, 1], vec![100]).await;
+    assert!(matches!(result, Err(CommunexError::ValidationError(_))));
+}
+
+/// Transport returning canned results keyed by method, without any HTTP
+#[derive(Debug)]
+struct FakeTransport {
+    responses: Vec<(&'static str, Value)>,
+}
+
+#[async_trait]
+impl RpcTransport for FakeTransport {
+    async fn request(&self, method: &str, params: Value) -> Result<Value, CommunexError> {
+        self.request_with_path(method, params).await
+    }
+
+    async fn batch_request(&self, _batch: BatchRequest) -> Result<BatchResponse, CommunexError> {
+        Err(CommunexError::ConnectionError("Batch requests not supported".into()))
+    }
+
+    async fn request_with_path(&self, path: &str, _params: Value) -> Result<Value, CommunexError> {
+        self.responses.iter()
+            .find(|(method, _)| *method == path)
+            .map(|(_, result)| result.clone())
+            .ok_or_else(|| CommunexError::RpcError { code: -32601, message: "Method not found".into() })
+    }
+}
+
+#[tokio::test]
+async fn test_get_free_balance_with_fake_transport() -> Result<(), CommunexError> {
+    let transport = FakeTransport {
+        responses: vec![("balance/free", json!({ "free": 4242 }))],
+    };
+
+    let client = WalletClient::with_transport(Arc::new(transport));
+    assert_eq!(client.get_free_balance("cmx1abcd123").await?, 4242);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_transaction_states_batch() {
+    let mock_server = MockServer::start().await;
+
+    // Answer out of order; results must still follow the request order
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .and(body_partial_json(json!([
+            {"method": "transaction/state", "params": {"hash": "0xaaa"}},
+            {"method": "transaction/state", "params": {"hash": "0xbbb"}}
+        ])))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            {
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {"state": "pending", "confirmations": 0, "timestamp": 1700000100}
+            },
+            {
+                "jsonrpc": "2.0",
+                "id": 0,
+                "result": {"state": "success", "block_num": 12345, "confirmations": 3, "timestamp": 1700000000}
+            }
+        ])))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    let states = client.get_transaction_states(&["0xaaa", "0xbbb"]).await.unwrap();
+
+    assert_eq!(states.len(), 2);
+    assert_eq!(states[0].hash, "0xaaa");
+    assert!(matches!(states[0].state, TxStatus::Success));
+    assert_eq!(states[0].block_num, Some(12345));
+    assert_eq!(states[0].confirmations, 3);
+    assert_eq!(states[1].hash, "0xbbb");
+    assert!(matches!(states[1].state, TxStatus::Pending));
+    assert_eq!(states[1].block_num, None);
+}
+
+#[tokio::test]
+async fn test_out_of_range_timestamps_are_malformed() {
+    let mock_server = MockServer::start().await;
+    let absurd_ts = i64::MAX;
+
+    Mock::given(method("POST"))
+        .and(path("/transaction/state"))
+        .respond_with(rpc_response(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {"state": "success", "confirmations": 1, "timestamp": absurd_ts}
+            })))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/transaction/history"))
+        .respond_with(rpc_response(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "transactions": [{
+                        "hash": "0x123",
+                        "block_num": 1,
+                        "timestamp": absurd_ts,
+                        "from": "cmx1abcd123",
+                        "to": "cmx1efgh456",
+                        "amount": 1,
+                        "denom": "COMAI",
+                        "state": "success"
+                    }]
+                }
+            })))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/staking/info"))
+        .respond_with(rpc_response(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {"total_staked": 5000, "last_claim_time": absurd_ts}
+            })))
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+
+    let state = client.get_transaction_state("0x123").await;
+    assert!(matches!(state, Err(CommunexError::MalformedResponse(_))), "{:?}", state);
+
+    let history = client.get_transaction_history("cmx1abcd123").await;
+    assert!(matches!(history, Err(CommunexError::MalformedResponse(_))), "{:?}", history);
+
+    let info = client.get_staking_info("cmx1abcd123").await;
+    assert!(matches!(info, Err(CommunexError::MalformedResponse(_))), "{:?}", info);
+}
+
+#[tokio::test]
+async fn test_missing_optional_timestamps_are_none() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/transaction/state"))
+        .respond_with(rpc_response(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {"state": "notfound"}
+            })))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/staking/info"))
+        .respond_with(rpc_response(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {"total_staked": 5000, "rewards_available": 0}
+            })))
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+
+    let state = client.get_transaction_state("0x123").await.unwrap();
+    assert!(matches!(state.state, TxStatus::NotFound));
+    assert_eq!(state.timestamp, None);
+
+    let info = client.get_staking_info("cmx1abcd123").await.unwrap();
+    assert_eq!(info.total_staked, 5000);
+    assert_eq!(info.last_claim_time, None);
+}
+
+#[tokio::test]
+async fn test_wait_for_confirmations_threshold() {
+    let mock_server = MockServer::start().await;
+
+    for confirmations in [0, 1] {
+        Mock::given(method("POST"))
+            .and(path("/transaction/state"))
+            .respond_with(rpc_response(json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": {
+                        "state": "success",
+                        "block_num": 12345,
+                        "confirmations": confirmations,
+                        "timestamp": 1704067200
+                    }
+                })))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+    }
+
+    Mock::given(method("POST"))
+        .and(path("/transaction/state"))
+        .respond_with(rpc_response(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "state": "success",
+                    "block_num": 12345,
+                    "confirmations": 3,
+                    "timestamp": 1704067200
+                }
+            })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    let state = client
+        .wait_for_confirmations("0x123", 3, std::time::Duration::from_secs(30))
+        .await
+        .unwrap();
+
+    assert_eq!(state.confirmations, 3);
+    assert_eq!(state.state, TxStatus::Success);
+}
+
+#[tokio::test]
+async fn test_wait_for_confirmations_detects_reorg() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/transaction/state"))
+        .respond_with(rpc_response(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "state": "success",
+                    "block_num": 12345,
+                    "confirmations": 1,
+                    "timestamp": 1704067200
+                }
+            })))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/transaction/state"))
+        .respond_with(rpc_response(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "state": "pending",
+                    "confirmations": 0,
+                    "timestamp": 1704067200
+                }
+            })))
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    let result = client
+        .wait_for_confirmations("0x123", 3, std::time::Duration::from_secs(30))
+        .await;
+
+    assert!(matches!(result, Err(CommunexError::TransactionReorged(_))), "{:?}", result);
+}
+
+#[tokio::test]
+async fn test_get_latest_block() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/chain/block"))
+        .and(body_partial_json(json!({
+            "method": "chain/block",
+            "params": {}
+        })))
+        .respond_with(rpc_response(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "number": 12346,
+                    "hash": "0xbeef",
+                    "timestamp": 1704067206,
+                    "parent_hash": "0xcafe"
+                }
+            })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    let block = client.get_latest_block().await.unwrap();
+
+    assert_eq!(block.number, 12346);
+    assert_eq!(block.hash, "0xbeef");
+    assert_eq!(block.timestamp.timestamp(), 1704067206);
+    assert_eq!(block.parent_hash, "0xcafe");
+}
+
+#[tokio::test]
+async fn test_get_block() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/chain/block"))
+        .and(body_partial_json(json!({
+            "method": "chain/block",
+            "params": {"number": 12345}
+        })))
+        .respond_with(rpc_response(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "number": 12345,
+                    "hash": "0xcafe",
+                    "timestamp": 1704067200,
+                    "parent_hash": "0xf00d"
+                }
+            })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    let block = client.get_block(12345).await.unwrap();
+
+    assert_eq!(block.number, 12345);
+    assert_eq!(block.hash, "0xcafe");
+    assert_eq!(block.timestamp.timestamp(), 1704067200);
+    assert_eq!(block.parent_hash, "0xf00d");
+}
+
+/// Answers `transaction/state` with pending until `pending_polls` calls have
+/// been made, recording the (paused) clock at each call
+#[derive(Debug)]
+struct PollRecorder {
+    pending_polls: usize,
+    calls: std::sync::Mutex<Vec<tokio::time::Instant>>,
+}
+
+#[async_trait]
+impl RpcTransport for PollRecorder {
+    async fn request(&self, method: &str, params: Value) -> Result<Value, CommunexError> {
+        self.request_with_path(method, params).await
+    }
+
+    async fn batch_request(&self, _batch: BatchRequest) -> Result<BatchResponse, CommunexError> {
+        Err(CommunexError::ConnectionError("Batch requests not supported".into()))
+    }
+
+    async fn request_with_path(&self, _path: &str, _params: Value) -> Result<Value, CommunexError> {
+        let mut calls = self.calls.lock().unwrap();
+        calls.push(tokio::time::Instant::now());
+        let state = if calls.len() > self.pending_polls { "success" } else { "pending" };
+        Ok(json!({ "state": state, "confirmations": 1, "timestamp": 1704067200 }))
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_wait_for_transaction_backs_off() {
+    let transport = Arc::new(PollRecorder {
+        pending_polls: 6,
+        calls: std::sync::Mutex::new(Vec::new()),
+    });
+
+    let client = WalletClient::with_transport(transport.clone())
+        .with_poll_backoff(std::time::Duration::from_millis(500), std::time::Duration::from_secs(8));
+    let state = client
+        .wait_for_transaction("0x123", std::time::Duration::from_secs(60))
+        .await
+        .unwrap();
+    assert_eq!(state.state, TxStatus::Success);
+
+    let calls = transport.calls.lock().unwrap();
+    let intervals: Vec<u128> = calls.windows(2)
+        .map(|pair| (pair[1] - pair[0]).as_millis())
+        .collect();
+    assert_eq!(intervals, vec![500, 1000, 2000, 4000, 8000, 8000]);
+}
+
+#[test]
+fn test_default_clients_target_local_node() {
+    use comx_api::rpc::{RpcClient, DEFAULT_RPC_URL};
+
+    assert_eq!(RpcClient::default().url, DEFAULT_RPC_URL);
+
+    let wallet = WalletClient::default();
+    assert_eq!(wallet.ws_url.as_deref(), Some("ws://127.0.0.1:9944"));
+}
+
+#[tokio::test]
+async fn test_wait_for_transaction_cancelled() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/transaction/state"))
+        .respond_with(rpc_response(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "state": "pending",
+                    "block_num": null,
+                    "confirmations": 0,
+                    "timestamp": 1704067200
+                }
+            })))
+        .mount(&mock_server)
+        .await;
+
+    let client = WalletClient::new(&mock_server.uri());
+    let cancel = tokio_util::sync::CancellationToken::new();
+
+    let trigger = cancel.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        trigger.cancel();
+    });
+
+    let started = std::time::Instant::now();
+    let result = client
+        .wait_for_transaction_cancellable("0x123", std::time::Duration::from_secs(30), Some(cancel))
+        .await;
+
+    assert!(matches!(result, Err(CommunexError::Cancelled)));
+    assert!(started.elapsed() < std::time::Duration::from_secs(2));
+}
@@ -0,0 +1,111 @@
+use comx_api::{
+    wallet::WalletClient,
+    error::CommunexError,
+};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_tungstenite::{accept_async, tungstenite::Message};
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer,
+};
+
+#[path = "common/mod.rs"]
+mod common;
+use common::rpc_response;
+
+/// Accept one WebSocket connection, confirm the subscription and push
+/// `balances` as notifications before closing.
+async fn start_ws_mock(balances: Vec<u64>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        let request = match ws.next().await {
+            Some(Ok(Message::Text(text))) => serde_json::from_str::<Value>(&text).unwrap(),
+            other => panic!("expected a subscription request, got {:?}", other),
+        };
+        assert_eq!(request["method"], "balance_subscribe");
+        assert_eq!(request["params"]["address"], "cmx1abcd123");
+
+        ws.send(Message::Text(json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "result": "sub-1"
+        }).to_string())).await.unwrap();
+
+        for free in balances {
+            ws.send(Message::Text(json!({
+                "jsonrpc": "2.0",
+                "method": "balance_subscription",
+                "params": {
+                    "subscription": "sub-1",
+                    "result": { "free": free }
+                }
+            }).to_string())).await.unwrap();
+        }
+
+        ws.close(None).await.unwrap();
+    });
+
+    format!("ws://{}", addr)
+}
+
+#[tokio::test]
+async fn test_subscribe_balance_yields_updates() {
+    let ws_url = start_ws_mock(vec![1_000_000, 750_000]).await;
+    let client = WalletClient::new("http://127.0.0.1:1").with_ws_url(&ws_url);
+
+    let updates: Vec<u64> = client.subscribe_balance("cmx1abcd123")
+        .await
+        .unwrap()
+        .map(|update| update.unwrap())
+        .collect()
+        .await;
+
+    assert_eq!(updates, vec![1_000_000, 750_000]);
+}
+
+#[tokio::test]
+async fn test_subscribe_balance_invalid_address() {
+    let client = WalletClient::new("http://127.0.0.1:1");
+
+    let result = client.subscribe_balance("invalid_address").await;
+    assert!(matches!(result, Err(CommunexError::RpcError { code: -32001, .. })));
+}
+
+#[tokio::test]
+async fn test_subscribe_or_poll_falls_back_to_polling() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/balance/free"))
+        .respond_with(rpc_response(json!({ "jsonrpc": "2.0", "id": 1, "result": { "free": 1_000_000 } })))
+        .up_to_n_times(2)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/balance/free"))
+        .respond_with(rpc_response(json!({ "jsonrpc": "2.0", "id": 1, "result": { "free": 750_000 } })))
+        .mount(&mock_server)
+        .await;
+
+    // Nothing listens on this port, so the subscription is refused
+    let client = WalletClient::new(&mock_server.uri()).with_ws_url("ws://127.0.0.1:1");
+
+    let updates: Vec<u64> = client.subscribe_or_poll("cmx1abcd123", Duration::from_millis(10))
+        .await
+        .unwrap()
+        .take(2)
+        .map(|update| update.unwrap())
+        .collect()
+        .await;
+
+    // The unchanged second poll is not yielded
+    assert_eq!(updates, vec![1_000_000, 750_000]);
+}